@@ -0,0 +1,225 @@
+use std::{collections::HashMap, error::Error};
+
+use log::{error, info, warn};
+use serde::Serialize;
+
+use crate::utils::konnect_utils::FetchFilter;
+
+use super::{
+    api_product_documents::{
+        APIProductDocumentResponse, CreateAPIProductDocumentRequest, GetAPIProductDocumentRequest,
+        GetAllAPIProductDocumentsRequest,
+    },
+    api_product_version_spec::{
+        APIProductVersionSpecResponse, CreateAPIProductVersionSpecRequest,
+        GetAllAPIProductVersionSpecRequest,
+    },
+    api_product_versions::{
+        APIProductVersionRequest, GetAllApiProductVersionRequest, GetApiProductVersionResponse,
+    },
+    api_products::{APIProductRequest, APIProductResponse, GetAPIProductRequest},
+    api_utils::{base64_to_string, roll_back, Executable},
+};
+
+pub struct CloneOptions {
+    pub name: String,
+    pub with_gateway_service: bool,
+    pub with_documents: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IdMapping {
+    pub name: String,
+    pub source_id: String,
+    pub clone_id: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CloneReport {
+    pub product: IdMapping,
+    pub versions: Vec<IdMapping>,
+    pub specifications: Vec<IdMapping>,
+    pub documents: Vec<IdMapping>,
+}
+
+/// What a clone copies, read from the source product before anything is created.
+struct CloneSource {
+    product: APIProductResponse,
+    versions: Vec<(
+        GetApiProductVersionResponse,
+        Vec<APIProductVersionSpecResponse>,
+    )>,
+    documents: Vec<APIProductDocumentResponse>,
+}
+
+fn read_source(source_id: &str, options: &CloneOptions) -> Result<CloneSource, Box<dyn Error>> {
+    let path = format!("/api-products/{}", source_id);
+    let product = GetAPIProductRequest {
+        id: source_id.to_string(),
+        name: String::new(),
+    }
+    .fetch(&path, FetchFilter::None)?;
+
+    let mut versions = Vec::new();
+    for version in GetAllApiProductVersionRequest::new()
+        .fetch_all(&format!("{}/product-versions", path), FetchFilter::None)?
+    {
+        let specs = GetAllAPIProductVersionSpecRequest::new().fetch_all(
+            &format!("{}/product-versions/{}/specifications", path, version.id),
+            FetchFilter::None,
+        )?;
+        versions.push((version, specs));
+    }
+
+    let mut documents = Vec::new();
+    if options.with_documents {
+        for summary in GetAllAPIProductDocumentsRequest::new()
+            .fetch_all(&format!("{}/documents", path), FetchFilter::None)?
+        {
+            documents.push(GetAPIProductDocumentRequest::new().fetch(
+                &format!("{}/documents/{}", path, summary.id),
+                FetchFilter::None,
+            )?);
+        }
+    }
+    Ok(CloneSource {
+        product,
+        versions,
+        documents,
+    })
+}
+
+/// What cloning `source_id` would create, one line per object, for --dry-run.
+pub fn describe_clone(source_id: &str, options: &CloneOptions) -> Result<String, Box<dyn Error>> {
+    let source = read_source(source_id, options)?;
+    let mut plan = format!(
+        "API product {} would be cloned as {}\n",
+        source.product.name, options.name
+    );
+    for (version, specs) in &source.versions {
+        plan.push_str(&format!("  version {}\n", version.name));
+        for spec in specs {
+            plan.push_str(&format!("  specification {}/{}\n", version.name, spec.name));
+        }
+    }
+    for document in &source.documents {
+        plan.push_str(&format!("  document {}\n", document.slug));
+    }
+    Ok(plan)
+}
+
+/// Copies a product with its versions, specifications and documents. A failed copy is rolled
+/// back, see `api_utils::roll_back`.
+pub fn clone_api_product(
+    source_id: &str,
+    options: &CloneOptions,
+) -> Result<CloneReport, Box<dyn Error>> {
+    let source = read_source(source_id, options)?;
+    let mut created = Vec::new();
+    clone_into(&source, options, &mut created).map_err(|err| {
+        error!(
+            "Cloning {} failed, removing the objects created so far",
+            source_id
+        );
+        roll_back(&created);
+        format!("Unable to clone API product {}: {}", source_id, err).into()
+    })
+}
+
+fn clone_into(
+    source: &CloneSource,
+    options: &CloneOptions,
+    created: &mut Vec<String>,
+) -> Result<CloneReport, Box<dyn Error>> {
+    let product =
+        APIProductRequest::from_product(&source.product, &options.name).create("/api-products")?;
+    info!(
+        "Created API product {} from {}",
+        product.id, source.product.id
+    );
+    let clone_path = format!("/api-products/{}", product.id);
+    created.push(clone_path.clone());
+
+    let mut report = CloneReport {
+        product: IdMapping {
+            name: product.name.clone(),
+            source_id: source.product.id.clone(),
+            clone_id: product.id.clone(),
+        },
+        versions: Vec::new(),
+        specifications: Vec::new(),
+        documents: Vec::new(),
+    };
+
+    for (version, specs) in &source.versions {
+        let new_version =
+            APIProductVersionRequest::from_version(version, options.with_gateway_service)
+                .create(&format!("{}/product-versions", clone_path))?;
+        let version_path = format!("{}/product-versions/{}", clone_path, new_version.id);
+        created.push(version_path.clone());
+        report.versions.push(IdMapping {
+            name: version.name.clone(),
+            source_id: version.id.clone(),
+            clone_id: new_version.id.clone(),
+        });
+
+        for spec in specs {
+            let new_spec = CreateAPIProductVersionSpecRequest::from_content(
+                &spec.name,
+                base64_to_string(&spec.content),
+            )
+            .create(&format!("{}/specifications", version_path))?;
+            created.push(format!("{}/specifications/{}", version_path, new_spec.id));
+            report.specifications.push(IdMapping {
+                name: spec.name.clone(),
+                source_id: spec.id.clone(),
+                clone_id: new_spec.id,
+            });
+        }
+    }
+
+    report.documents = clone_documents(&source.documents, &clone_path, created)?;
+    Ok(report)
+}
+
+/// Documents are created parents first so the new parent ids are known when children are sent.
+fn clone_documents(
+    documents: &[APIProductDocumentResponse],
+    clone_path: &str,
+    created: &mut Vec<String>,
+) -> Result<Vec<IdMapping>, Box<dyn Error>> {
+    let mut pending: Vec<&APIProductDocumentResponse> = documents.iter().collect();
+    let mut id_map: HashMap<String, String> = HashMap::new();
+    let mut mappings = Vec::new();
+    while !pending.is_empty() {
+        let ready = pending.iter().position(|d| match &d.parent_document_id {
+            None => true,
+            Some(parent) => id_map.contains_key(parent),
+        });
+        let (document, parent) = match ready {
+            Some(i) => {
+                let d = pending.remove(i);
+                let parent = d.parent_document_id.as_ref().map(|p| id_map[p].clone());
+                (d, parent)
+            }
+            None => {
+                let d = pending.remove(0);
+                warn!(
+                    "Parent of document {} was not found, it is added at the top level",
+                    d.slug
+                );
+                (d, None)
+            }
+        };
+        let new_document = CreateAPIProductDocumentRequest::from_document(document, parent)
+            .create(&format!("{}/documents", clone_path))?;
+        created.push(format!("{}/documents/{}", clone_path, new_document.id));
+        id_map.insert(document.id.clone(), new_document.id.clone());
+        mappings.push(IdMapping {
+            name: document.slug.clone(),
+            source_id: document.id.clone(),
+            clone_id: new_document.id,
+        });
+    }
+    Ok(mappings)
+}
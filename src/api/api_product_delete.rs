@@ -0,0 +1,333 @@
+use std::{error::Error, fmt, fs, io, thread, time::SystemTime};
+
+use log::{error, info};
+
+use crate::{
+    cli::{api_products_cli::DeleteApiProduct, cli_utils},
+    utils::{
+        konnect_utils::{dry_run, FetchFilter, KonnectClient},
+        label_selector::LabelSelector,
+    },
+};
+
+use super::{
+    api_product_describe::{describe_api_product, ProductDescription},
+    api_products::{
+        fetch_products_by_selector, resolve_api_product_id, DeleteAPIProductRequest,
+        GetAPIProductsArrayRequest,
+    },
+    api_utils::Executable,
+};
+
+#[derive(Debug, Default)]
+pub struct DeleteSummary {
+    pub deleted: usize,
+    pub failed: usize,
+}
+
+impl fmt::Display for DeleteSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Deleted {} API products, {} failed",
+            self.deleted, self.failed
+        )
+    }
+}
+
+/// Runs `task` on every item, `concurrency` items at a time, and returns the results in order.
+fn in_parallel<T: Sync, R: Send>(
+    items: &[T],
+    concurrency: usize,
+    task: impl Fn(&T) -> R + Sync,
+) -> Vec<R> {
+    let task = &task;
+    let mut results = Vec::with_capacity(items.len());
+    for batch in items.chunks(concurrency.max(1)) {
+        thread::scope(|scope| {
+            let handles: Vec<_> = batch
+                .iter()
+                .map(|item| scope.spawn(move || task(item)))
+                .collect();
+            results.extend(
+                handles
+                    .into_iter()
+                    .map(|h| h.join().expect("a delete thread panicked")),
+            );
+        });
+    }
+    results
+}
+
+/// Describes the products `ids`, `concurrency` at a time, so what they hold can be listed before
+/// they are deleted.
+pub fn describe_api_products(
+    ids: &[String],
+    concurrency: usize,
+) -> Result<Vec<ProductDescription>, String> {
+    in_parallel(ids, concurrency, |id| {
+        describe_api_product(id).map_err(|e| format!("Unable to look API product {} up: {}", id, e))
+    })
+    .into_iter()
+    .collect()
+}
+
+/// One line describing what deleting the product removes along with it.
+pub fn deletion_summary(description: &ProductDescription) -> String {
+    let product = &description.product;
+    let specifications: usize = description
+        .versions
+        .iter()
+        .map(|v| v.specifications.len())
+        .sum();
+    let publications: usize = description
+        .versions
+        .iter()
+        .map(|v| v.version.portals.len())
+        .sum();
+    format!(
+        "API product {} ({}): {} versions, {} specifications, {} portal publications, {} documents",
+        product.name,
+        product.id,
+        description.versions.len(),
+        specifications,
+        publications,
+        description.documents.len()
+    )
+}
+
+/// Deletes the products, `concurrency` at a time. A failure does not stop the other deletions,
+/// it is counted in the summary.
+pub fn delete_api_products(
+    descriptions: &[ProductDescription],
+    cascade: bool,
+    concurrency: usize,
+) -> DeleteSummary {
+    let results = in_parallel(descriptions, concurrency, |description| {
+        let result = delete_api_product(description, cascade);
+        let product = &description.product;
+        match &result {
+            Ok(0) => println!("API product {} ({}) deleted", product.name, product.id),
+            Ok(children) => println!(
+                "API product {} ({}) deleted along with {} objects",
+                product.name, product.id, children
+            ),
+            Err(e) => error!("{}", e),
+        }
+        result
+    });
+    let mut summary = DeleteSummary::default();
+    for result in results {
+        match result {
+            Ok(_) => summary.deleted += 1,
+            Err(_) => summary.failed += 1,
+        }
+    }
+    summary
+}
+
+/// Deletes the product and returns how many objects below it were deleted. With `cascade` its
+/// portal publications, specifications, versions and documents are deleted first, children
+/// before their parents, since Konnect refuses to delete a product that still has versions.
+/// Stops at the first failure.
+fn delete_api_product(description: &ProductDescription, cascade: bool) -> Result<usize, String> {
+    let product = &description.product;
+    let product_path = format!("/api-products/{}", product.id);
+    let mut deleted = 0;
+    if cascade {
+        let kc = KonnectClient::new();
+        for (what, path) in children_bottom_up(description) {
+            kc.delete(&path).map_err(|e| {
+                format!(
+                    "Unable to delete {} of API product {}: {}",
+                    what, product.name, e
+                )
+            })?;
+            info!("Deleted {} of API product {}", what, product.name);
+            deleted += 1;
+        }
+    }
+    DeleteAPIProductRequest {
+        id: product.id.clone(),
+    }
+    .delete(&product_path)
+    .map_err(|e| {
+        if !cascade && !description.versions.is_empty() {
+            format!(
+                "Unable to delete API product {}: {}. It still has {} versions, pass --cascade to delete them first",
+                product.name,
+                e,
+                description.versions.len()
+            )
+        } else {
+            format!("Unable to delete API product {}: {}", product.name, e)
+        }
+    })?;
+    Ok(deleted)
+}
+
+/// What to delete before the product, as (description, path) pairs in the order to delete them.
+fn children_bottom_up(description: &ProductDescription) -> Vec<(String, String)> {
+    let product_path = format!("/api-products/{}", description.product.id);
+    let mut children = Vec::new();
+    for v in &description.versions {
+        let version = &v.version;
+        let version_path = format!("{}/product-versions/{}", product_path, version.id);
+        for portal in &version.portals {
+            children.push((
+                format!(
+                    "publication of version {} on portal {}",
+                    version.name, portal.portal_name
+                ),
+                format!(
+                    "/portals/{}/product-versions/{}",
+                    portal.portal_id, version.id
+                ),
+            ));
+        }
+        for spec in &v.specifications {
+            children.push((
+                format!("specification {} of version {}", spec.name, version.name),
+                format!("{}/specifications/{}", version_path, spec.id),
+            ));
+        }
+        children.push((format!("version {}", version.name), version_path));
+    }
+
+    // Documents are deleted once none of the remaining ones is their child
+    let mut documents: Vec<_> = description.documents.iter().collect();
+    while !documents.is_empty() {
+        let leaf = documents
+            .iter()
+            .position(|d| {
+                !documents
+                    .iter()
+                    .any(|other| other.parent_document_id.as_deref() == Some(d.id.as_str()))
+            })
+            .unwrap_or(0);
+        let document = documents.remove(leaf);
+        children.push((
+            format!("document {}", document.slug),
+            format!("{}/documents/{}", product_path, document.id),
+        ));
+    }
+    children
+}
+
+/// Lists the targeted API products with what each one holds, deletes them once the user
+/// confirmed and prints a summary of the deletions.
+pub fn delete_products(delete_cli: &DeleteApiProduct) -> Result<(), Box<dyn Error>> {
+    // A dry run deletes nothing, so there is nothing to confirm
+    let confirm = !delete_cli.yes && dry_run().is_none();
+    if confirm && delete_cli.filename.as_deref() == Some("-") {
+        return Err("Pass --yes with -f -, stdin holds the API products and cannot confirm".into());
+    }
+    let ids = products_to_delete(delete_cli)?;
+    if ids.is_empty() {
+        println!("No API products to delete");
+        return Ok(());
+    }
+    let concurrency = delete_cli.concurrency.into();
+    let descriptions = describe_api_products(&ids, concurrency)?;
+    println!("The following API products will be deleted:");
+    for description in &descriptions {
+        println!("  {}", deletion_summary(description));
+    }
+    if !delete_cli.cascade && descriptions.iter().any(|d| !d.versions.is_empty()) {
+        println!("API products that still have versions can only be deleted with --cascade");
+    }
+    if confirm && !cli_utils::confirm("Delete them?") {
+        return Err(
+            "Delete aborted, nothing was deleted. Pass --yes to delete without confirmation".into(),
+        );
+    }
+    let summary = delete_api_products(&descriptions, delete_cli.cascade, concurrency);
+    if summary.failed > 0 {
+        return Err(summary.to_string().into());
+    }
+    if descriptions.len() > 1 {
+        println!("{}", summary);
+    }
+    Ok(())
+}
+
+/// Ids of the API products `delete api-product` targets: the one given with --id, the ones
+/// listed with --filename, or the ones matching every filter given.
+fn products_to_delete(delete_cli: &DeleteApiProduct) -> Result<Vec<String>, Box<dyn Error>> {
+    if let Some(id) = &delete_cli.id {
+        return Ok(vec![resolve_api_product_id(id)?]);
+    }
+    if let Some(file) = &delete_cli.filename {
+        let content = if file == "-" {
+            io::read_to_string(io::stdin()).map_err(|e| format!("Unable to read stdin: {}", e))?
+        } else {
+            fs::read_to_string(file).map_err(|e| format!("Unable to read {}: {}", file, e))?
+        };
+        let mut ids: Vec<String> = Vec::new();
+        for line in content.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let id = resolve_api_product_id(line)?;
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+        return Ok(ids);
+    }
+
+    let products = match &delete_cli.selector {
+        Some(selector) => fetch_products_by_selector(selector)?.data,
+        None => GetAPIProductsArrayRequest {}.fetch_all("/api-products", FetchFilter::None)?,
+    };
+    let cutoff = delete_cli.older_than.map(|age| SystemTime::now() - age);
+    let mut ids = Vec::new();
+    for product in products {
+        if let Some(prefix) = &delete_cli.name_prefix {
+            if !product.name.starts_with(prefix.as_str()) {
+                continue;
+            }
+        }
+        if let Some(cutoff) = cutoff {
+            let created_at = humantime::parse_rfc3339_weak(&product.created_at).map_err(|e| {
+                format!(
+                    "API product {} has an invalid created_at {}: {}",
+                    product.name, product.created_at, e
+                )
+            })?;
+            if created_at >= cutoff {
+                continue;
+            }
+        }
+        ids.push(product.id);
+    }
+    Ok(ids)
+}
+
+/// Deletes every `(name, path)` matched by a label selector, listing them first.
+pub fn delete_selected(
+    what: &str,
+    selector: &LabelSelector,
+    targets: Vec<(String, String)>,
+) -> Result<(), Box<dyn Error>> {
+    if targets.is_empty() {
+        println!("No {} match {}", what, selector);
+        return Ok(());
+    }
+    println!("Deleting {} {} matching {}:", targets.len(), what, selector);
+    let total = targets.len();
+    let mut failed = 0;
+    for (name, path) in targets {
+        match KonnectClient::new().delete(&path) {
+            Ok(_) => println!("  {} deleted", name),
+            Err(err) => {
+                failed += 1;
+                error!("Error deleting {} ({}): {}", name, path, err);
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(format!("{} of {} {} could not be deleted", failed, total, what).into());
+    }
+    Ok(())
+}
@@ -0,0 +1,207 @@
+use std::{error::Error, fmt};
+
+use crate::utils::konnect_utils::FetchFilter;
+
+use super::{
+    api_product_documents::{APIProductDocumentSummary, GetAllAPIProductDocumentsRequest},
+    api_product_version_spec::{APIProductVersionSpecResponse, GetAllAPIProductVersionSpecRequest},
+    api_product_versions::{GetAllApiProductVersionRequest, GetApiProductVersionResponse},
+    api_products::{APIProductResponse, GetAPIProductRequest},
+    api_utils::Executable,
+};
+
+/// A product with everything below it, displayed as a tree.
+pub struct ProductDescription {
+    pub product: APIProductResponse,
+    pub versions: Vec<VersionDescription>,
+    pub documents: Vec<APIProductDocumentSummary>,
+}
+
+pub struct VersionDescription {
+    pub version: GetApiProductVersionResponse,
+    pub specifications: Vec<APIProductVersionSpecResponse>,
+}
+
+/// Fetches a product, its versions with their specifications, and its documents.
+pub fn describe_api_product(product_id: &str) -> Result<ProductDescription, Box<dyn Error>> {
+    let product_path = format!("/api-products/{}", product_id);
+    let product = GetAPIProductRequest {
+        id: product_id.to_string(),
+        name: String::new(),
+    }
+    .fetch(&product_path, FetchFilter::None)?;
+    let versions_path = format!("{}/product-versions", product_path);
+    let mut versions = Vec::new();
+    for version in
+        GetAllApiProductVersionRequest::new().fetch_all(&versions_path, FetchFilter::None)?
+    {
+        let specifications = GetAllAPIProductVersionSpecRequest::new().fetch_all(
+            &format!("{}/{}/specifications", versions_path, version.id),
+            FetchFilter::None,
+        )?;
+        versions.push(VersionDescription {
+            version,
+            specifications,
+        });
+    }
+    let documents = GetAllAPIProductDocumentsRequest::new()
+        .fetch_all(&format!("{}/documents", product_path), FetchFilter::None)?;
+    Ok(ProductDescription {
+        product,
+        versions,
+        documents,
+    })
+}
+
+struct Node {
+    label: String,
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn new(label: impl Into<String>) -> Self {
+        Node {
+            label: label.into(),
+            children: Vec::new(),
+        }
+    }
+
+    fn with(mut self, children: Vec<Node>) -> Self {
+        self.children = children;
+        self
+    }
+
+    fn render_children(&self, f: &mut fmt::Formatter<'_>, prefix: &str) -> fmt::Result {
+        for (i, child) in self.children.iter().enumerate() {
+            let last = i + 1 == self.children.len();
+            let (branch, indent) = if last {
+                ("└── ", "    ")
+            } else {
+                ("├── ", "│   ")
+            };
+            writeln!(f, "{}{}{}", prefix, branch, child.label)?;
+            child.render_children(f, &format!("{}{}", prefix, indent))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ProductDescription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let product = &self.product;
+        let mut children = Vec::new();
+        if let Some(description) = product.description.as_deref().filter(|d| !d.is_empty()) {
+            children.push(Node::new(format!("description: {}", description)));
+        }
+        if !product.labels.is_empty() {
+            let mut labels: Vec<String> = product
+                .labels
+                .iter()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect();
+            labels.sort();
+            children.push(Node::new(format!("labels: {}", labels.join(", "))));
+        }
+        if !product.portal_ids.is_empty() {
+            children.push(Node::new(format!(
+                "portals: {}",
+                product.portal_ids.join(", ")
+            )));
+        }
+        children.push(
+            Node::new(format!("versions ({})", self.versions.len()))
+                .with(self.versions.iter().map(version_node).collect()),
+        );
+        children.push(
+            Node::new(format!("documents ({})", self.documents.len()))
+                .with(document_nodes(&self.documents, None)),
+        );
+
+        writeln!(f, "{} ({})", product.name, product.id)?;
+        Node::new("").with(children).render_children(f, "")
+    }
+}
+
+fn version_node(description: &VersionDescription) -> Node {
+    let version = &description.version;
+    let specs = &description.specifications;
+    let mut children = Vec::new();
+    children.push(Node::new(match &version.gateway_service {
+        Some(gs) => format!(
+            "gateway service: {} on control plane {}",
+            gs.id, gs.control_plane_id
+        ),
+        None => "gateway service: none".to_string(),
+    }));
+    if let Some(errors) = &version.auth_strategy_sync_errors {
+        children.push(Node::new(format!("auth strategy sync errors: {}", errors)));
+    }
+    children.push(
+        Node::new(format!("portals ({})", version.portals.len())).with(
+            version
+                .portals
+                .iter()
+                .map(|portal| {
+                    let mut state = vec![portal.publish_status.clone()];
+                    if portal.deprecated {
+                        state.push("deprecated".to_string());
+                    }
+                    if portal.application_registration_enabled {
+                        state.push(if portal.auto_approve_registration {
+                            "registration auto approved".to_string()
+                        } else {
+                            "registration enabled".to_string()
+                        });
+                    }
+                    let strategies: Vec<&str> = portal
+                        .auth_strategies
+                        .iter()
+                        .map(|s| s.name.as_str())
+                        .collect();
+                    if !strategies.is_empty() {
+                        state.push(format!("auth: {}", strategies.join(", ")));
+                    }
+                    Node::new(format!(
+                        "{} ({}): {}",
+                        portal.portal_name,
+                        portal.portal_id,
+                        state.join(", ")
+                    ))
+                })
+                .collect(),
+        ),
+    );
+    children.push(
+        Node::new(format!("specifications ({})", specs.len())).with(
+            specs
+                .iter()
+                .map(|spec| Node::new(format!("{} ({})", spec.name, spec.id)))
+                .collect(),
+        ),
+    );
+
+    let mut state = version.publish_status.clone();
+    if version.deprecated {
+        state.push_str(", deprecated");
+    }
+    Node::new(format!("{} ({}) {}", version.name, version.id, state)).with(children)
+}
+
+/// Documents under `parent`, nested by parent document. Documents whose parent is not listed
+/// are shown at the top level.
+fn document_nodes(documents: &[APIProductDocumentSummary], parent: Option<&str>) -> Vec<Node> {
+    documents
+        .iter()
+        .filter(|d| match parent {
+            Some(parent) => d.parent_document_id.as_deref() == Some(parent),
+            None => d
+                .parent_document_id
+                .as_deref()
+                .is_none_or(|p| !documents.iter().any(|other| other.id == p)),
+        })
+        .map(|d| {
+            Node::new(format!("{} \"{}\" {}", d.slug, d.title, d.status))
+                .with(document_nodes(documents, Some(&d.id)))
+        })
+        .collect()
+}
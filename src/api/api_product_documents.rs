@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{
+    api_utils::{Executable, Paginated},
+    common_structs::Meta,
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetAllAPIProductDocumentsRequest {}
+
+impl Executable for GetAllAPIProductDocumentsRequest {
+    type Response = GetAllAPIProductDocumentsResponse;
+}
+
+impl GetAllAPIProductDocumentsRequest {
+    pub fn new() -> Self {
+        GetAllAPIProductDocumentsRequest {}
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetAllAPIProductDocumentsResponse {
+    pub data: Vec<APIProductDocumentSummary>,
+    pub meta: Meta,
+}
+
+impl Paginated for GetAllAPIProductDocumentsResponse {
+    type Item = APIProductDocumentSummary;
+
+    fn meta(&self) -> &Meta {
+        &self.meta
+    }
+    fn into_data(self) -> Vec<Self::Item> {
+        self.data
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct APIProductDocumentSummary {
+    pub id: String,
+    pub parent_document_id: Option<String>,
+    pub title: String,
+    pub slug: String,
+    pub status: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetAPIProductDocumentRequest {}
+
+impl Executable for GetAPIProductDocumentRequest {
+    type Response = APIProductDocumentResponse;
+}
+
+impl GetAPIProductDocumentRequest {
+    pub fn new() -> Self {
+        GetAPIProductDocumentRequest {}
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct APIProductDocumentResponse {
+    pub id: String,
+    pub parent_document_id: Option<String>,
+    pub title: String,
+    pub slug: String,
+    pub status: String,
+    pub content: Option<String>,
+    pub metadata: Option<Value>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateAPIProductDocumentRequest {
+    pub parent_document_id: Option<String>,
+    pub title: String,
+    pub slug: String,
+    pub status: String,
+    pub content: Option<String>,
+    pub metadata: Option<Value>,
+}
+
+impl Executable for CreateAPIProductDocumentRequest {
+    type Response = APIProductDocumentResponse;
+}
+
+impl CreateAPIProductDocumentRequest {
+    /// Copy of an existing document, attached to `parent_document_id` in the target product.
+    pub fn from_document(
+        document: &APIProductDocumentResponse,
+        parent_document_id: Option<String>,
+    ) -> Self {
+        CreateAPIProductDocumentRequest {
+            parent_document_id,
+            title: document.title.clone(),
+            slug: document.slug.clone(),
+            status: document.status.clone(),
+            content: document.content.clone(),
+            metadata: document.metadata.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PatchAPIProductDocumentRequest {
+    pub parent_document_id: Option<String>,
+    pub title: Option<String>,
+    pub slug: Option<String>,
+    pub status: Option<String>,
+    pub content: Option<String>,
+}
+
+impl Executable for PatchAPIProductDocumentRequest {
+    type Response = APIProductDocumentResponse;
+}
@@ -0,0 +1,53 @@
+use std::error::Error;
+
+use serde_json::json;
+
+use crate::{
+    cli::api_products_cli::EditApiProduct,
+    utils::{
+        editor::edit_yaml,
+        json_query,
+        konnect_utils::{FetchFilter, NotSent},
+    },
+};
+
+use super::{
+    api_products::{resolve_api_product_id, GetAPIProductRequest, PatchAPIProductRequest},
+    api_utils::Executable,
+};
+
+/// Opens the editable fields of an API product in the editor and patches what was changed.
+pub fn edit_product(edit_cli: &EditApiProduct) -> Result<(), Box<dyn Error>> {
+    let id = resolve_api_product_id(&edit_cli.id_or_name)?;
+    let path = format!("/api-products/{}", id);
+    let product = GetAPIProductRequest {
+        id: id.clone(),
+        name: String::new(),
+    }
+    .fetch(&path, FetchFilter::None)?;
+    let editable = json!({
+        "name": product.name,
+        "description": product.description,
+        "labels": product.labels,
+        "portal_ids": product.portal_ids,
+    });
+    let header = format!(
+        "Editing API product {} ({}).\n\
+         Lines starting with # are ignored and an empty file cancels the edit. Only the fields\n\
+         changed are sent, the file is opened again if Konnect rejects them.",
+        product.name, id
+    );
+    let saved = edit_yaml(&header, &editable, |patch| {
+        match PatchAPIProductRequest::default().patch(&path, patch) {
+            Ok(updated) => json_query::print(&updated)?,
+            // A dry run printed the patch, there is no response
+            Err(e) if e.is::<NotSent>() => {}
+            Err(e) => return Err(e),
+        }
+        Ok(())
+    })?;
+    if !saved {
+        println!("Edit cancelled, no changes made");
+    }
+    Ok(())
+}
@@ -1,18 +1,46 @@
-use std::fs;
+use std::error::Error;
 
-use log::{error, info};
+use log::info;
 use serde::{Deserialize, Serialize};
 
-use crate::cli::api_product_version_spec::{
-    CreateApiProductVersionSpec, DeleteApiProductVersionSpec, PatchApiProductVersionSpec,
+use crate::{
+    cli::api_product_version_spec::{
+        CreateApiProductVersionSpec, DeleteApiProductVersionSpec, PatchApiProductVersionSpec,
+    },
+    utils::{konnect_utils::FetchFilter, spec_bundler, spec_validator},
 };
 
 use super::{
-    api_utils::{get_filename_from_path, string_to_base64, Executable},
+    api_utils::{
+        base64_to_string, find_id_by_name, get_filename_from_path, is_uuid, string_to_base64,
+        Executable, Paginated, Tabular,
+    },
     common_structs::Meta,
 };
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Returns the id of a specification of a product version given either its id or its name.
+pub fn resolve_api_product_version_spec_id(
+    product_id: &str,
+    product_version_id: &str,
+    id_or_name: &str,
+) -> Result<String, Box<dyn Error>> {
+    if is_uuid(id_or_name) {
+        return Ok(id_or_name.to_string());
+    }
+    let specs = GetAllAPIProductVersionSpecRequest::new().fetch_all(
+        &format!(
+            "/api-products/{}/product-versions/{}/specifications",
+            product_id, product_version_id
+        ),
+        FetchFilter::FieldName("name".to_string(), id_or_name.to_string()),
+    )?;
+    let scope = format!(" in API product version {}", product_version_id);
+    find_id_by_name(&specs, id_or_name, "specification", &scope, |s| {
+        (&s.name, &s.id)
+    })
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct PatchAPIProductVersionSpecRequest {
     pub name: String,
     pub content: String,
@@ -23,26 +51,46 @@ impl Executable for PatchAPIProductVersionSpecRequest {
 }
 
 impl PatchAPIProductVersionSpecRequest {
-    pub fn new(spec_cli: &PatchApiProductVersionSpec) -> Self {
-        let path = &spec_cli.path_to_spec_file;
-        let name = get_filename_from_path(path).expect("Please pass a valid file name");
-        let file_content = fs::read_to_string(path);
-        let content = match file_content {
-            Ok(c) => {
-                info!("Content: {}", &c);
-                string_to_base64(c)
-            }
-            Err(er) => {
-                error!("Error reading file {}", er);
-                "NO_CONTENT".to_string()
-            }
-        };
+    pub fn new(spec_cli: &PatchApiProductVersionSpec) -> Result<Self, Box<dyn Error>> {
+        let (name, content) =
+            read_spec_for_upload(&spec_cli.path_to_spec_file, spec_cli.skip_validation)?;
+        Ok(PatchAPIProductVersionSpecRequest { name, content })
+    }
+}
 
-        PatchAPIProductVersionSpecRequest {
-            name: name.to_string(),
-            content,
-        }
+/// Reads the spec file, bundles any external `$ref`s into it and validates it (unless told
+/// otherwise). Returns the file name and the base64 encoded content expected by Konnect.
+fn read_spec_for_upload(
+    path: &str,
+    skip_validation: bool,
+) -> Result<(String, String), Box<dyn Error>> {
+    let name = get_filename_from_path(path)
+        .ok_or_else(|| format!("Unable to get a file name from {}", path))?
+        .to_string();
+    let content = read_spec_for_upload_content(path, skip_validation)?;
+    info!("Content: {}", &content);
+    Ok((name, string_to_base64(content)))
+}
+
+/// Same as `read_spec_for_upload` but returns the bundled content as is.
+pub fn read_spec_for_upload_content(
+    path: &str,
+    skip_validation: bool,
+) -> Result<String, Box<dyn Error>> {
+    let mut content = spec_validator::read_spec_file(path)?;
+    if let Some(bundled) = spec_bundler::bundle_spec(path, &content)? {
+        info!(
+            "Uploading {} bundled with {} external reference(s)",
+            path, bundled.external_refs
+        );
+        content = bundled.content;
     }
+    if !skip_validation {
+        let spec = spec_validator::validate_spec_content(path, content)?;
+        info!("{} is a valid {} document", path, spec.kind);
+        content = spec.content;
+    }
+    Ok(content)
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -102,8 +150,28 @@ impl GetAllAPIProductVersionSpecRequest {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GetAllAPIProductVersionSpecResponse {
-    data: Vec<APIProductVersionSpecResponse>,
-    meta: Meta,
+    pub data: Vec<APIProductVersionSpecResponse>,
+    pub meta: Meta,
+}
+
+// The content is left out, it is the whole base64 encoded spec
+impl Tabular for APIProductVersionSpecResponse {
+    const COLUMNS: &'static [&'static str] = &["id", "name", "created_at", "updated_at"];
+}
+
+impl Tabular for GetAllAPIProductVersionSpecResponse {
+    const COLUMNS: &'static [&'static str] = APIProductVersionSpecResponse::COLUMNS;
+}
+
+impl Paginated for GetAllAPIProductVersionSpecResponse {
+    type Item = APIProductVersionSpecResponse;
+
+    fn meta(&self) -> &Meta {
+        &self.meta
+    }
+    fn into_data(self) -> Vec<Self::Item> {
+        self.data
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -114,28 +182,18 @@ pub struct CreateAPIProductVersionSpecRequest {
 // Function to extract the filename from a path
 
 impl CreateAPIProductVersionSpecRequest {
-    pub fn new(spec_cli: CreateApiProductVersionSpec) -> Self {
-        // let file = File::open(spec_cli.path_to_spec_file));
-        let path = spec_cli.path_to_spec_file;
-        let name_t = get_filename_from_path(&path);
-        let mut name = String::new();
-        match name_t {
-            Some(f) => name = f.to_string(),
-            None => error!("Error getting file name"),
-        }
-        let mut content = String::new();
-        let file_content = fs::read_to_string(&path);
-        match file_content {
-            Ok(c) => {
-                info!("Content: {}", &c);
-                content = string_to_base64(c);
-            }
-            Err(er) => {
-                error!("Error reading file {}", er);
-            }
-        }
+    pub fn new(spec_cli: CreateApiProductVersionSpec) -> Result<Self, Box<dyn Error>> {
+        let (name, content) =
+            read_spec_for_upload(&spec_cli.path_to_spec_file, spec_cli.skip_validation)?;
+        Ok(CreateAPIProductVersionSpecRequest { name, content })
+    }
 
-        CreateAPIProductVersionSpecRequest { name, content }
+    /// Request for already loaded (not encoded) spec content.
+    pub fn from_content(name: &str, content: String) -> Self {
+        CreateAPIProductVersionSpecRequest {
+            name: name.to_string(),
+            content: string_to_base64(content),
+        }
     }
 }
 
@@ -146,9 +204,49 @@ impl Executable for CreateAPIProductVersionSpecRequest {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct APIProductVersionSpecResponse {
-    id: String,
-    name: String,
-    content: String,
+    pub id: String,
+    pub name: String,
+    pub content: String,
     created_at: String,
     updated_at: String,
 }
+
+/// Fetches a spec of a product version and returns its decoded content. When no spec id is given
+/// the version must have exactly one specification.
+pub fn fetch_spec_content(
+    product_id: &str,
+    product_version_id: &str,
+    id: Option<&str>,
+) -> Result<APIProductVersionSpecResponse, Box<dyn Error>> {
+    let path = format!(
+        "/api-products/{}/product-versions/{}/specifications",
+        product_id, product_version_id
+    );
+    let mut spec = match id {
+        Some(i) => GetAPIProductVersionSpecRequest::new()
+            .fetch(&format!("{}/{}", path, i), FetchFilter::None)?,
+        None => {
+            let mut all =
+                GetAllAPIProductVersionSpecRequest::new().fetch(&path, FetchFilter::None)?;
+            match all.data.len() {
+                1 => all.data.remove(0),
+                0 => {
+                    return Err(format!(
+                        "Product version {} has no specification",
+                        product_version_id
+                    )
+                    .into())
+                }
+                n => {
+                    return Err(format!(
+                        "Product version {} has {} specifications, pass the specification id",
+                        product_version_id, n
+                    )
+                    .into())
+                }
+            }
+        }
+    };
+    spec.content = base64_to_string(&spec.content);
+    Ok(spec)
+}
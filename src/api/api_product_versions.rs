@@ -1,12 +1,36 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, error::Error};
+
+use crate::utils::{konnect_utils::FetchFilter, label_selector::LabelSelector};
 
 use crate::cli::api_product_versions_cli::{
-    CreateApiProductVersion, DeleteAPIProductVersion, PublishStatus,
+    CreateApiProductVersion, DeleteAPIProductVersion, PatchApiProductVersion, PublishStatus,
 };
 use log::info;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::{
+    api_utils::{find_id_by_name, is_uuid, Executable, Paginated, Tabular},
+    common_structs::Meta,
+};
 
-use super::{api_utils::Executable, common_structs::Meta};
+/// Returns the id of a version of a product given either its id or its name.
+pub fn resolve_api_product_version_id(
+    product_id: &str,
+    id_or_name: &str,
+) -> Result<String, Box<dyn Error>> {
+    if is_uuid(id_or_name) {
+        return Ok(id_or_name.to_string());
+    }
+    let versions = GetAllApiProductVersionRequest::new().fetch_all(
+        &format!("/api-products/{}/product-versions", product_id),
+        FetchFilter::FieldName("name".to_string(), id_or_name.to_string()),
+    )?;
+    let scope = format!(" in API product {}", product_id);
+    find_id_by_name(&versions, id_or_name, "API product version", &scope, |v| {
+        (&v.name, &v.id)
+    })
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DeleteAPIProductVersionRequest {
@@ -22,7 +46,7 @@ pub struct DeleteAPIProductVersionResponse {
 impl DeleteAPIProductVersionRequest {
     pub fn new(dprv: DeleteAPIProductVersion) -> Self {
         DeleteAPIProductVersionRequest {
-            id: dprv.id,
+            id: dprv.id.unwrap_or_default(),
             product_id: dprv.product_id,
         }
     }
@@ -53,10 +77,11 @@ impl APIProductVersionRequest {
         let cpid = api_product_version
             .control_plane_id
             .unwrap_or("".to_string());
-        let ps = match api_product_version.publish_status {
-            PublishStatus::Unpublished => "unpublished",
-            PublishStatus::Published => "published",
-        };
+        let ps = api_product_version
+            .publish_status
+            .unwrap_or(PublishStatus::Unpublished)
+            .as_str();
+        let name = api_product_version.name.unwrap_or_default();
         let d = match api_product_version.deprecated {
             Some(dep) => dep == "true",
             None => false,
@@ -71,20 +96,115 @@ impl APIProductVersionRequest {
 
         if gsid.is_empty() || cpid.is_empty() {
             APIProductVersionRequest {
-                name: api_product_version.name,
+                name,
                 publish_status: ps.to_string(),
                 deprecated: d,
                 gateway_service: { None },
             }
         } else {
             APIProductVersionRequest {
-                name: api_product_version.name,
+                name,
                 publish_status: ps.to_string(),
                 deprecated: d,
                 gateway_service: gs,
             }
         }
     }
+
+    /// Only the fields given as flags, to be merged over a body read with -f.
+    pub fn flags(api_product_version: &CreateApiProductVersion) -> Value {
+        let mut flags = Map::new();
+        if let Some(name) = &api_product_version.name {
+            flags.insert("name".to_string(), name.clone().into());
+        }
+        if let Some(status) = api_product_version.publish_status {
+            flags.insert("publish_status".to_string(), status.as_str().into());
+        }
+        if let Some(deprecated) = &api_product_version.deprecated {
+            flags.insert("deprecated".to_string(), (deprecated == "true").into());
+        }
+        if let (Some(id), Some(control_plane_id)) = (
+            &api_product_version.gateway_service_id,
+            &api_product_version.control_plane_id,
+        ) {
+            flags.insert(
+                "gateway_service".to_string(),
+                serde_json::json!({ "id": id, "control_plane_id": control_plane_id }),
+            );
+        }
+        Value::Object(flags)
+    }
+
+    /// Copy of an existing version, optionally keeping the link to its gateway service.
+    pub fn from_version(
+        version: &GetApiProductVersionResponse,
+        with_gateway_service: bool,
+    ) -> Self {
+        let gateway_service = match &version.gateway_service {
+            Some(gs) if with_gateway_service => Some(APIProductVersionGatewayServiceRequest {
+                control_plane_id: gs.control_plane_id.clone(),
+                id: gs.id.clone(),
+            }),
+            _ => None,
+        };
+        APIProductVersionRequest {
+            name: version.name.clone(),
+            publish_status: version.publish_status.clone(),
+            deprecated: version.deprecated,
+            gateway_service,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PatchAPIProductVersionRequest {
+    pub name: Option<String>,
+    pub publish_status: Option<String>,
+    pub deprecated: Option<bool>,
+    pub gateway_service: Option<APIProductVersionGatewayServiceRequest>,
+}
+
+impl Executable for PatchAPIProductVersionRequest {
+    type Response = APIProductVersionResponse;
+}
+
+impl PatchAPIProductVersionRequest {
+    pub fn new(patch_version: &PatchApiProductVersion) -> Self {
+        let gateway_service = match (
+            &patch_version.gateway_service_id,
+            &patch_version.control_plane_id,
+        ) {
+            (Some(id), Some(control_plane_id)) => Some(APIProductVersionGatewayServiceRequest {
+                control_plane_id: control_plane_id.clone(),
+                id: id.clone(),
+            }),
+            _ => None,
+        };
+        PatchAPIProductVersionRequest {
+            name: patch_version.name.clone(),
+            publish_status: patch_version
+                .publish_status
+                .map(|status| status.as_str().to_string()),
+            deprecated: patch_version
+                .deprecated
+                .as_ref()
+                .map(|deprecated| deprecated == "true"),
+            gateway_service,
+        }
+    }
+
+    /// Request that only links the version to a gateway service.
+    pub fn link_gateway_service(control_plane_id: &str, gateway_service_id: &str) -> Self {
+        PatchAPIProductVersionRequest {
+            name: None,
+            publish_status: None,
+            deprecated: None,
+            gateway_service: Some(APIProductVersionGatewayServiceRequest {
+                control_plane_id: control_plane_id.to_string(),
+                id: gateway_service_id.to_string(),
+            }),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -110,11 +230,6 @@ pub struct APIProductVersionResponse {
     updated_at: String,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub struct APIProductVersion {
-    path: String,
-}
-
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GetApiProductVersionRequest {}
 
@@ -143,8 +258,55 @@ impl GetAllApiProductVersionRequest {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct GetAllApiProductVersionResponse {
-    data: Vec<GetApiProductVersionResponse>,
-    meta: Meta,
+    pub data: Vec<GetApiProductVersionResponse>,
+    pub meta: Meta,
+}
+
+impl Tabular for GetApiProductVersionResponse {
+    const COLUMNS: &'static [&'static str] = &[
+        "id",
+        "name",
+        "publish_status",
+        "deprecated",
+        "portals.portal_name",
+        "updated_at",
+    ];
+}
+
+impl Tabular for GetAllApiProductVersionResponse {
+    const COLUMNS: &'static [&'static str] = GetApiProductVersionResponse::COLUMNS;
+}
+
+impl Paginated for GetAllApiProductVersionResponse {
+    type Item = GetApiProductVersionResponse;
+
+    fn meta(&self) -> &Meta {
+        &self.meta
+    }
+    fn into_data(self) -> Vec<Self::Item> {
+        self.data
+    }
+}
+
+/// Every version of a product matching `selector`, in the shape of a single page of results.
+pub fn fetch_versions_by_selector(
+    product_id: &str,
+    selector: &LabelSelector,
+) -> Result<GetAllApiProductVersionResponse, Box<dyn Error>> {
+    let versions = GetAllApiProductVersionRequest::new().fetch_all(
+        &format!("/api-products/{}/product-versions", product_id),
+        FetchFilter::Labels(selector.clone()),
+    )?;
+    let mut data = Vec::new();
+    for version in versions {
+        if selector.matches(&serde_json::to_value(&version.labels)?) {
+            data.push(version);
+        }
+    }
+    Ok(GetAllApiProductVersionResponse {
+        meta: Meta::single_page(data.len()),
+        data,
+    })
 }
 
 #[derive(Serialize, Deserialize, Debug)]
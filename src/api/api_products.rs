@@ -1,14 +1,16 @@
 use serde::Deserialize;
 use serde::Serialize;
 use std::collections::HashMap;
+use std::error::Error;
 
 use crate::cli;
 
-use super::api_utils;
-use super::api_utils::Executable;
+use super::api_utils::{find_id_by_name, is_uuid, Executable, Paginated, Tabular};
 use super::common_structs::Meta;
+use super::{api_product_version_spec, api_product_versions, api_utils};
+use crate::utils::{konnect_utils::FetchFilter, label_selector::LabelSelector};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct PatchAPIProductRequest {
     pub name: Option<String>,
     pub description: Option<String>,
@@ -78,26 +80,11 @@ impl Executable for DeleteAPIProductRequest {
     type Response = DeleteAPIProductResponse;
 }
 
-impl DeleteAPIProductRequest {
-    pub fn new(delp: cli::api_products_cli::DeleteApiProduct) -> Self {
-        let id = delp.id;
-        DeleteAPIProductRequest { id }
-    }
-}
-
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DeleteAPIProductResponse {
     response_code: i32,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct APIProductError {
-    status: u16,
-    title: String,
-    instance: String,
-    detail: String,
-}
-
 #[derive(Debug, Serialize, Deserialize)]
 pub struct APIProductRequest {
     name: String,
@@ -116,12 +103,23 @@ impl APIProductRequest {
             None => Some(Vec::new()),
         };
         APIProductRequest {
-            name: api_product.name,
-            description: api_product.description,
+            name: api_product.name.unwrap_or_default(),
+            description: api_product.description.unwrap_or_default(),
             labels: hs,
             portal_ids: pids,
         }
     }
+
+    /// Copy of an existing product under a new name. Portals are not copied so the copy is not
+    /// published anywhere until asked to.
+    pub fn from_product(product: &APIProductResponse, name: &str) -> Self {
+        APIProductRequest {
+            name: name.to_string(),
+            description: product.description.clone().unwrap_or_default(),
+            labels: Some(product.labels.clone()),
+            portal_ids: Some(Vec::new()),
+        }
+    }
 }
 
 impl Executable for APIProductRequest {
@@ -156,19 +154,92 @@ impl Executable for GetAPIProductsArrayRequest {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct APIProductsArrayResponse {
-    data: Vec<APIProductResponse>,
+    pub data: Vec<APIProductResponse>,
     pub meta: Meta,
 }
 
+impl Paginated for APIProductsArrayResponse {
+    type Item = APIProductResponse;
+
+    fn meta(&self) -> &Meta {
+        &self.meta
+    }
+    fn into_data(self) -> Vec<Self::Item> {
+        self.data
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct APIProductResponse {
     pub id: String,
-    name: String,
-    description: Option<String>,
-    portal_ids: Vec<String>,
-    created_at: String,
-    updated_at: String,
-    labels: HashMap<String, String>,
+    pub name: String,
+    pub description: Option<String>,
+    pub portal_ids: Vec<String>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub labels: HashMap<String, String>,
+}
+
+impl Tabular for APIProductResponse {
+    const COLUMNS: &'static [&'static str] = &["id", "name", "description", "labels", "updated_at"];
+}
+
+impl Tabular for APIProductsArrayResponse {
+    const COLUMNS: &'static [&'static str] = APIProductResponse::COLUMNS;
+}
+
+/// Returns the id of an API product given either its id or its name.
+pub fn resolve_api_product_id(id_or_name: &str) -> Result<String, Box<dyn Error>> {
+    if is_uuid(id_or_name) {
+        return Ok(id_or_name.to_string());
+    }
+    let products = GetAPIProductsArrayRequest {}.fetch_all(
+        "/api-products",
+        FetchFilter::FieldName("name".to_string(), id_or_name.to_string()),
+    )?;
+    find_id_by_name(&products, id_or_name, "API product", "", |p| {
+        (&p.name, &p.id)
+    })
+}
+
+/// Replaces product, version and specification names with the ids they name, in place. Ids are
+/// left as they are. A specification is only resolved along with its version.
+pub fn resolve_ids(
+    product: &mut String,
+    version: Option<&mut String>,
+    specification: Option<&mut String>,
+) -> Result<(), Box<dyn Error>> {
+    *product = resolve_api_product_id(product)?;
+    let Some(version) = version else {
+        return Ok(());
+    };
+    *version = api_product_versions::resolve_api_product_version_id(product, version)?;
+    if let Some(specification) = specification {
+        *specification = api_product_version_spec::resolve_api_product_version_spec_id(
+            product,
+            version,
+            specification,
+        )?;
+    }
+    Ok(())
+}
+
+/// Every API product matching `selector`, in the shape of a single page of results.
+pub fn fetch_products_by_selector(
+    selector: &LabelSelector,
+) -> Result<APIProductsArrayResponse, Box<dyn Error>> {
+    let products = GetAPIProductsArrayRequest {}
+        .fetch_all("/api-products", FetchFilter::Labels(selector.clone()))?;
+    let mut data = Vec::new();
+    for product in products {
+        if selector.matches(&serde_json::to_value(&product.labels)?) {
+            data.push(product);
+        }
+    }
+    Ok(APIProductsArrayResponse {
+        meta: Meta::single_page(data.len()),
+        data,
+    })
 }
 
 // pub struct APIProduct {
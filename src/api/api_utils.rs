@@ -1,16 +1,76 @@
 use std::{collections::HashMap, error::Error, path::Path};
 
-use log::info;
+use log::{error, info};
 use serde::{de::DeserializeOwned, Serialize};
 use serde_json::Value;
 
 use crate::utils::konnect_utils::{FetchFilter, KonnectClient};
+
+use super::common_structs::Meta;
 use base64::{engine::general_purpose, Engine as _};
 
+/// Page size used when walking every page of a list endpoint.
+const PAGE_SIZE: i32 = 100;
+
 pub fn string_to_base64(input: String) -> String {
     general_purpose::STANDARD.encode(input)
 }
 
+/// Decodes base64 content returned by Konnect. Content that is not valid base64 is returned as
+/// is, since older specs were stored unencoded.
+pub fn base64_to_string(input: &str) -> String {
+    let decoded = general_purpose::STANDARD
+        .decode(input.trim())
+        .map_err(|e| e.to_string())
+        .and_then(|bytes| String::from_utf8(bytes).map_err(|e| e.to_string()));
+    match decoded {
+        Ok(content) => content,
+        Err(e) => {
+            info!("Content is not base64 encoded text ({}), using it as is", e);
+            input.to_string()
+        }
+    }
+}
+
+/// Konnect ids are UUIDs, anything else passed where an id is expected is treated as a name.
+pub fn is_uuid(value: &str) -> bool {
+    let parts: Vec<&str> = value.split('-').collect();
+    parts.len() == 5
+        && parts
+            .iter()
+            .zip([8, 4, 4, 4, 12])
+            .all(|(p, len)| p.len() == len && p.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Id of the only item of `items` named `name`. `what` names the kind of item in errors, e.g.
+/// "API product", and `scope` says where it was looked for, e.g. " in API product <id>".
+pub fn find_id_by_name<T>(
+    items: &[T],
+    name: &str,
+    what: &str,
+    scope: &str,
+    name_and_id: impl Fn(&T) -> (&str, &str),
+) -> Result<String, Box<dyn Error>> {
+    let matches: Vec<&str> = items
+        .iter()
+        .map(&name_and_id)
+        .filter(|(n, _)| *n == name)
+        .map(|(_, id)| id)
+        .collect();
+    match matches.as_slice() {
+        [id] => Ok(id.to_string()),
+        [] => Err(format!("No {} named {}{}", what, name, scope).into()),
+        _ => Err(format!(
+            "{} {}s are named {}{}, use the id instead",
+            matches.len(),
+            what,
+            name,
+            scope
+        )
+        .into()),
+    }
+}
+
 pub fn get_filename_from_path(file_path: &str) -> Option<&str> {
     Path::new(file_path).file_name()?.to_str()
 }
@@ -26,6 +86,66 @@ pub fn string_to_hashmap(string: String) -> HashMap<String, String> {
     }
     hs
 }
+/// Reads a request body from a JSON or YAML file, - reads stdin. A top level `id`, as in a body
+/// saved from `get`, is dropped: what is created or patched is never chosen by the body.
+pub fn read_body(filename: &str) -> Result<Value, Box<dyn Error>> {
+    let content = if filename == "-" {
+        std::io::read_to_string(std::io::stdin())
+            .map_err(|e| format!("Unable to read stdin: {}", e))?
+    } else {
+        std::fs::read_to_string(filename)
+            .map_err(|e| format!("Unable to read {}: {}", filename, e))?
+    };
+    let body: Value = serde_yaml::from_str(&content)
+        .map_err(|e| format!("{} is not valid JSON or YAML: {}", filename, e))?;
+    let Value::Object(mut body) = body else {
+        return Err(format!("The body in {} must be an object", filename).into());
+    };
+    if body.remove("id").is_some() {
+        info!("Ignoring the id in {}", filename);
+    }
+    Ok(Value::Object(body))
+}
+
+/// `body` with the fields of `overrides` set over it, objects such as labels merged key by key.
+pub fn merge_json(body: Value, overrides: Value) -> Value {
+    match (body, overrides) {
+        (Value::Object(mut body), Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                match body.get_mut(&key) {
+                    Some(existing) => *existing = merge_json(existing.take(), value),
+                    None => {
+                        body.insert(key, value);
+                    }
+                }
+            }
+            Value::Object(body)
+        }
+        (_, overrides) => overrides,
+    }
+}
+
+/// Deletes the objects at `created` newest first, after the command that created them failed.
+/// Returns the paths deleted, those that could not be are logged to be removed by hand.
+pub fn roll_back(created: &[String]) -> Vec<String> {
+    let kc = KonnectClient::new();
+    created
+        .iter()
+        .rev()
+        .filter(|path| match kc.delete(path) {
+            Ok(_) => {
+                info!("Deleted {}", path);
+                true
+            }
+            Err(e) => {
+                error!("Unable to delete {}, remove it by hand: {}", path, e);
+                false
+            }
+        })
+        .cloned()
+        .collect()
+}
+
 pub trait ToJson {
     fn to_json(&self) -> String;
 }
@@ -39,6 +159,20 @@ impl<T: Serialize> ToJson for T {
     }
 }
 
+/// Responses of list endpoints, which return a page of `data` along with paging `meta`.
+pub trait Paginated {
+    type Item;
+
+    fn meta(&self) -> &Meta;
+    fn into_data(self) -> Vec<Self::Item>;
+}
+
+/// Responses that can be printed as a table with `get -o table`.
+pub trait Tabular {
+    /// Columns shown when none are given with --columns, as dotted paths into each item.
+    const COLUMNS: &'static [&'static str];
+}
+
 pub trait Executable: Serialize {
     type Response: DeserializeOwned;
 
@@ -48,6 +182,13 @@ pub trait Executable: Serialize {
         let parsed_response: Self::Response = serde_json::from_str(&response)?;
         Ok(parsed_response)
     }
+    /// Creates from `body` rather than from the request itself, e.g. a body read from a file.
+    fn create_with(&self, path: &str, body: Value) -> Result<Self::Response, Box<dyn Error>> {
+        let kc = KonnectClient::new();
+        let response = kc.create(path, body)?;
+        let parsed_response: Self::Response = serde_json::from_str(&response)?;
+        Ok(parsed_response)
+    }
     fn patch(&self, path: &str, value: Value) -> Result<Self::Response, Box<dyn Error>> {
         let kc = KonnectClient::new();
         let response = kc.patch(path, value)?;
@@ -63,6 +204,35 @@ pub trait Executable: Serialize {
         let parsed_response: Self::Response = serde_json::from_str(&response)?;
         Ok(parsed_response)
     }
+    /// Fetches every page of a list endpoint and returns all the items.
+    fn fetch_all(
+        &self,
+        path: &str,
+        filter: FetchFilter,
+    ) -> Result<Vec<<Self::Response as Paginated>::Item>, Box<dyn Error>>
+    where
+        Self::Response: Paginated,
+    {
+        let kc = KonnectClient::new();
+        let mut items = Vec::new();
+        let mut number = 1;
+        loop {
+            let mut query = filter.to_query();
+            query.push(("page[size]".to_string(), PAGE_SIZE.to_string()));
+            query.push(("page[number]".to_string(), number.to_string()));
+            let response = kc.fetch_with_query(path, &query)?;
+            let parsed_response: Self::Response = serde_json::from_str(&response)?;
+            let total = parsed_response.meta().page.total as usize;
+            let data = parsed_response.into_data();
+            let received = data.len();
+            items.extend(data);
+            if received == 0 || items.len() >= total {
+                return Ok(items);
+            }
+            number += 1;
+        }
+    }
+
     fn delete(&self, path: &str) -> Result<String, Box<dyn Error>> {
         let kc = KonnectClient::new();
         let response = kc.delete(path)?;
@@ -5,6 +5,19 @@ pub struct Meta {
     pub page: Page,
 }
 
+impl Meta {
+    /// Paging of results gathered from every page, or filtered client side.
+    pub fn single_page(total: usize) -> Self {
+        Meta {
+            page: Page {
+                total: total as i32,
+                size: total as i32,
+                number: 1,
+            },
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Page {
     pub total: i32,
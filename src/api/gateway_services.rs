@@ -0,0 +1,194 @@
+use std::error::Error;
+
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::utils::gateway_config::{PluginConfig, RouteConfig, ServiceConfig};
+
+use super::api_utils::{roll_back, Executable};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateGatewayServiceRequest {
+    pub name: String,
+    pub url: String,
+    #[serde(flatten)]
+    pub defaults: Map<String, Value>,
+}
+
+impl Executable for CreateGatewayServiceRequest {
+    type Response = GatewayServiceResponse;
+}
+
+impl CreateGatewayServiceRequest {
+    pub fn new(service: &ServiceConfig) -> Self {
+        CreateGatewayServiceRequest {
+            name: service.name.clone(),
+            url: service.url.clone(),
+            defaults: service.defaults.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GatewayServiceResponse {
+    pub id: String,
+    pub name: Option<String>,
+    pub host: String,
+    pub port: u16,
+    pub protocol: String,
+    pub path: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateGatewayRouteRequest {
+    pub name: String,
+    pub methods: Vec<String>,
+    pub paths: Vec<String>,
+    pub strip_path: bool,
+    #[serde(flatten)]
+    pub defaults: Map<String, Value>,
+}
+
+impl Executable for CreateGatewayRouteRequest {
+    type Response = GatewayRouteResponse;
+}
+
+impl CreateGatewayRouteRequest {
+    pub fn new(route: &RouteConfig) -> Self {
+        CreateGatewayRouteRequest {
+            name: route.name.clone(),
+            methods: route.methods.clone(),
+            paths: route.paths.clone(),
+            strip_path: route.strip_path,
+            defaults: route.defaults.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GatewayRouteResponse {
+    pub id: String,
+    pub name: Option<String>,
+    pub methods: Option<Vec<String>>,
+    pub paths: Option<Vec<String>>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateGatewayPluginRequest {
+    pub name: String,
+    #[serde(flatten)]
+    pub fields: Map<String, Value>,
+}
+
+impl Executable for CreateGatewayPluginRequest {
+    type Response = GatewayPluginResponse;
+}
+
+impl CreateGatewayPluginRequest {
+    pub fn new(plugin: &PluginConfig) -> Self {
+        CreateGatewayPluginRequest {
+            name: plugin.name.clone(),
+            fields: plugin.fields.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GatewayPluginResponse {
+    pub id: String,
+    pub name: String,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AppliedGatewayConfig {
+    pub control_plane_id: String,
+    pub service: GatewayServiceResponse,
+    pub routes: Vec<GatewayRouteResponse>,
+    pub plugins: Vec<GatewayPluginResponse>,
+}
+
+/// What `apply_service_config` would create, one line per object, for --dry-run.
+pub fn describe_service_config(control_plane_id: &str, service: &ServiceConfig) -> String {
+    let mut plan = format!(
+        "Gateway service {} ({}) would be created on control plane {}\n",
+        service.name, service.url, control_plane_id
+    );
+    for plugin in &service.plugins {
+        plan.push_str(&format!("  plugin {}\n", plugin.name));
+    }
+    for route in &service.routes {
+        plan.push_str(&format!(
+            "  route {} {} {}\n",
+            route.name,
+            route.methods.join(","),
+            route.paths.join(",")
+        ));
+        for plugin in &route.plugins {
+            plan.push_str(&format!("    plugin {}\n", plugin.name));
+        }
+    }
+    plan
+}
+
+/// Creates the service, its routes and plugins on a control plane, or none of them: what was
+/// created before a failure is rolled back.
+pub fn apply_service_config(
+    control_plane_id: &str,
+    service: &ServiceConfig,
+) -> Result<AppliedGatewayConfig, Box<dyn Error>> {
+    let mut created = Vec::new();
+    create_service_config(control_plane_id, service, &mut created).map_err(|err| {
+        error!(
+            "Creating gateway service {} failed, removing the objects created so far",
+            service.name
+        );
+        roll_back(&created);
+        format!("Unable to create gateway service {}: {}", service.name, err).into()
+    })
+}
+
+fn create_service_config(
+    control_plane_id: &str,
+    service: &ServiceConfig,
+    created: &mut Vec<String>,
+) -> Result<AppliedGatewayConfig, Box<dyn Error>> {
+    let base = format!("/control-planes/{}/core-entities", control_plane_id);
+    let new_service =
+        CreateGatewayServiceRequest::new(service).create(&format!("{}/services", base))?;
+    info!("Created gateway service {}", new_service.id);
+    created.push(format!("{}/services/{}", base, new_service.id));
+
+    let mut plugins = Vec::new();
+    for plugin in &service.plugins {
+        let path = format!("{}/services/{}/plugins", base, new_service.id);
+        let new_plugin = CreateGatewayPluginRequest::new(plugin).create(&path)?;
+        created.push(format!("{}/plugins/{}", base, new_plugin.id));
+        plugins.push(new_plugin);
+    }
+    let mut routes = Vec::new();
+    for route in &service.routes {
+        let path = format!("{}/services/{}/routes", base, new_service.id);
+        let new_route = CreateGatewayRouteRequest::new(route).create(&path)?;
+        info!("Created route {}", new_route.id);
+        created.push(format!("{}/routes/{}", base, new_route.id));
+        for plugin in &route.plugins {
+            let path = format!("{}/routes/{}/plugins", base, new_route.id);
+            let new_plugin = CreateGatewayPluginRequest::new(plugin).create(&path)?;
+            created.push(format!("{}/plugins/{}", base, new_plugin.id));
+            plugins.push(new_plugin);
+        }
+        routes.push(new_route);
+    }
+    Ok(AppliedGatewayConfig {
+        control_plane_id: control_plane_id.to_string(),
+        service: new_service,
+        routes,
+        plugins,
+    })
+}
@@ -1,5 +1,11 @@
+pub mod api_product_clone;
+pub mod api_product_delete;
+pub mod api_product_describe;
+pub mod api_product_documents;
+pub mod api_product_edit;
 pub mod api_product_version_spec;
 pub mod api_product_versions;
 pub mod api_products;
 pub mod api_utils;
 pub mod common_structs;
+pub mod gateway_services;
@@ -2,64 +2,72 @@ use clap::Args;
 
 #[derive(Debug, Args)]
 pub struct CreateApiProductVersionSpec {
-    #[arg(short = 'p', long, required = true)]
-    /// API product id
+    #[arg(short = 'p', long, required = true, visible_alias = "product")]
+    /// API product id or name
     pub product_id: String,
 
-    #[arg(short = 'v', long, required = true)]
-    /// API product version id
+    #[arg(short = 'v', long, required = true, visible_alias = "version")]
+    /// API product version id or name
     pub product_version_id: String,
 
     #[arg(short = 'f', long, required = true)]
     /// Path to spec file
     pub path_to_spec_file: String,
+
+    #[arg(long)]
+    /// Upload the spec file without validating it first
+    pub skip_validation: bool,
 }
 
 #[derive(Debug, Args)]
 pub struct DeleteApiProductVersionSpec {
-    #[arg(short = 'p', long, required = true)]
-    /// API product id
+    #[arg(short = 'p', long, required = true, visible_alias = "product")]
+    /// API product id or name
     pub product_id: String,
 
-    #[arg(short = 'v', long, required = true)]
-    /// API product version id
+    #[arg(short = 'v', long, required = true, visible_alias = "version")]
+    /// API product version id or name
     pub product_version_id: String,
 
     #[arg(short, long, required = true)]
-    /// API specification id
+    /// API specification id or name
     pub id: String,
 }
 
 #[derive(Debug, Args)]
 pub struct GetApiProductVersionSpec {
-    #[arg(short = 'p', long, required = true)]
-    /// API product id
+    #[arg(short = 'p', long, required = true, visible_alias = "product")]
+    /// API product id or name
     pub product_id: String,
 
-    #[arg(short = 'v', long, required = true)]
-    /// API product version id
+    #[arg(short = 'v', long, required = true, visible_alias = "version")]
+    /// API product version id or name
     pub product_version_id: String,
 
     #[arg(short, long)]
-    /// API specification id
+    /// API specification id or name
     pub id: Option<String>,
 }
 
 #[derive(Debug, Args)]
 pub struct PatchApiProductVersionSpec {
-    #[arg(short = 'p', long, required = true)]
-    /// API product id
+    #[arg(short = 'p', long, required = true, visible_alias = "product")]
+    /// API product id or name
     pub product_id: String,
 
-    #[arg(short = 'v', long, required = true)]
-    /// API product id
+    #[arg(short = 'v', long, required = true, visible_alias = "version")]
+    /// API product version id or name
     pub product_version_id: String,
 
     #[arg(short, long, required = true)]
-    /// API specification id
+    /// API specification id or name
     pub id: String,
 
     #[arg(short = 'f', long, required = true)]
     /// Path to spec file
     pub path_to_spec_file: String,
+
+    #[arg(long)]
+    /// Upload the spec file without validating it first
+    pub skip_validation: bool,
 }
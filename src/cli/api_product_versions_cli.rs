@@ -1,20 +1,22 @@
 use core::str;
 
-use clap::{Args, ValueEnum};
+use clap::{ArgGroup, Args, ValueEnum};
+
+use crate::utils::label_selector::LabelSelector;
 
 #[derive(Debug, Args)]
 pub struct CreateApiProductVersion {
-    #[arg(short = 'p', long, required = true)]
-    /// Id of the product
+    #[arg(short = 'p', long, required = true, visible_alias = "product")]
+    /// Id or name of the product
     pub product_id: String,
 
-    #[arg(short, long)]
+    #[arg(short, long, required_unless_present = "filename")]
     /// The name of the API product version
-    pub name: String,
+    pub name: Option<String>,
 
-    #[arg(short = 's', long, value_enum, default_value_t = PublishStatus::Unpublished )]
-    /// The publish status of the product
-    pub publish_status: PublishStatus,
+    #[arg(short = 's', long, value_enum)]
+    /// The publish status of the product, unpublished when not given
+    pub publish_status: Option<PublishStatus>,
 
     #[arg(short, long)]
     /// The deprecated of the API product version
@@ -27,30 +29,80 @@ pub struct CreateApiProductVersion {
     #[arg(short, long)]
     /// The id of the controla plane on which the gateway service is deployed
     pub control_plane_id: Option<String>,
+
+    #[arg(short = 'f', long)]
+    /// Read the body of the request from a JSON or YAML file, - reads stdin. Any field Konnect
+    /// supports can be set, the flags given as well take precedence
+    pub filename: Option<String>,
     // #[arg(short, long)]
     // /// The list of comma separate of the API product
     // pub gateway_service: Option<GatewayService>,
 }
 
+#[derive(Debug, Args)]
+pub struct PatchApiProductVersion {
+    #[arg(short = 'p', long, required = true, visible_alias = "product")]
+    /// Id or name of the product
+    pub product_id: String,
+
+    #[arg(short, long, required = true)]
+    /// Id or name of the API product version to be updated
+    pub id: String,
+
+    #[arg(short, long)]
+    /// The name of the API product version
+    pub name: Option<String>,
+
+    #[arg(short = 's', long, value_enum)]
+    /// The publish status of the product version
+    pub publish_status: Option<PublishStatus>,
+
+    #[arg(short, long)]
+    /// The deprecated of the API product version
+    pub deprecated: Option<String>,
+
+    #[arg(short, long, requires = "control_plane_id")]
+    /// The id of the gateway service associated with API product
+    pub gateway_service_id: Option<String>,
+
+    #[arg(short, long, requires = "gateway_service_id")]
+    /// The id of the controla plane on which the gateway service is deployed
+    pub control_plane_id: Option<String>,
+
+    #[arg(short = 'f', long)]
+    /// Read the body of the request from a JSON or YAML file, - reads stdin. Any field Konnect
+    /// supports can be set, the flags given as well take precedence
+    pub filename: Option<String>,
+}
+
 #[derive(Debug, Args)]
 pub struct GetAPIProductVersion {
-    #[arg(short = 'p', long, required = true)]
-    /// Product id
+    #[arg(short = 'p', long, required = true, visible_alias = "product")]
+    /// Product id or name
     pub product_id: String,
 
     #[arg(short, long)]
-    /// Product version id
+    /// Product version id or name
     pub id: Option<String>,
+
+    #[arg(short = 'l', long, conflicts_with = "id")]
+    /// Only versions whose labels match, e.g. 'team=payments,env!=prod'
+    pub selector: Option<LabelSelector>,
 }
 
 #[derive(Debug, Args)]
+#[command(group(ArgGroup::new("target").required(true).args(["id", "selector"])))]
 pub struct DeleteAPIProductVersion {
-    #[arg(short, long, required = true)]
-    /// Product version id
-    pub id: String,
+    #[arg(short, long)]
+    /// Product version id or name
+    pub id: Option<String>,
 
-    #[arg(short, long, required = true)]
-    /// Product  id
+    #[arg(short = 'l', long)]
+    /// Delete every version of the product whose labels match, e.g. 'env=dev'
+    pub selector: Option<LabelSelector>,
+
+    #[arg(short, long, required = true, visible_alias = "product")]
+    /// Product id or name
     pub product_id: String,
 }
 
@@ -62,3 +114,12 @@ pub enum PublishStatus {
     // Staus is published
     Published,
 }
+
+impl PublishStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PublishStatus::Unpublished => "unpublished",
+            PublishStatus::Published => "published",
+        }
+    }
+}
@@ -1,33 +1,74 @@
 use super::cli_utils;
-use clap::Args;
+use crate::utils::label_selector::LabelSelector;
+use clap::{ArgGroup, Args};
+use std::time::Duration;
 
 #[derive(Debug, Args)]
+#[command(group(
+    ArgGroup::new("target")
+        .required(true)
+        .multiple(true)
+        .args(["id", "selector", "name_prefix", "older_than", "filename"])
+))]
 pub struct DeleteApiProduct {
+    #[arg(short, long, conflicts_with_all = ["selector", "name_prefix", "older_than", "filename"])]
+    /// The id or name of the API Product
+    pub id: Option<String>,
+
+    #[arg(short = 'l', long)]
+    /// Delete every API product whose labels match, e.g. 'team=payments,env!=prod'
+    pub selector: Option<LabelSelector>,
+
+    #[arg(long, value_name = "PREFIX")]
+    /// Delete every API product whose name starts with PREFIX
+    pub name_prefix: Option<String>,
+
+    #[arg(long, value_name = "AGE", value_parser = humantime::parse_duration)]
+    /// Delete every API product created more than AGE ago, e.g. 7d or 12h
+    pub older_than: Option<Duration>,
+
+    #[arg(short = 'f', long, conflicts_with_all = ["selector", "name_prefix", "older_than"])]
+    /// Delete the API products listed in a file, one id or name per line, - reads stdin
+    pub filename: Option<String>,
+
     #[arg(short, long)]
-    /// The id of the API Product
-    pub id: String,
+    /// Delete without asking for confirmation
+    pub yes: bool,
+
+    #[arg(long)]
+    /// Also delete the portal publications, specifications, versions and documents of the API
+    /// product, children first
+    pub cascade: bool,
+
+    #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(u16).range(1..))]
+    /// How many API products are looked up and deleted at the same time
+    pub concurrency: u16,
 }
 
 #[derive(Debug, Args)]
 pub struct GetApiProduct {
     #[arg(short, long)]
-    /// The id of the API Product
+    /// The id or name of the API Product
     pub id: Option<String>,
 
     #[arg(short, long)]
     /// The name of the API Product
     pub name: Option<String>,
+
+    #[arg(short = 'l', long, conflicts_with_all = ["id", "name"])]
+    /// Only API products whose labels match, e.g. 'team=payments,env!=prod'
+    pub selector: Option<LabelSelector>,
 }
 
 #[derive(Debug, Args)]
 pub struct CreateApiProduct {
-    #[arg(short, long)]
+    #[arg(short, long, required_unless_present = "filename")]
     /// The name of the API product
-    pub name: String,
+    pub name: Option<String>,
 
-    #[arg(short, long)]
+    #[arg(short, long, required_unless_present = "filename")]
     /// The description of the API product
-    pub description: String,
+    pub description: Option<String>,
 
     #[arg(short, long, value_parser=cli_utils::validate_label)]
     /// Provide labels in the formart "key1:value1,key2:value2
@@ -36,12 +77,17 @@ pub struct CreateApiProduct {
     #[arg(short, long)]
     /// The list of comma separate of the API product
     pub portal_ids: Option<String>,
+
+    #[arg(short = 'f', long)]
+    /// Read the body of the request from a JSON or YAML file, - reads stdin. Any field Konnect
+    /// supports can be set, the flags given as well take precedence
+    pub filename: Option<String>,
 }
 
 #[derive(Debug, Args)]
 pub struct PatchApiProduct {
     #[arg(short, long, required = true)]
-    /// ID of the API product to be updated
+    /// ID or name of the API product to be updated
     pub id: String,
 
     #[arg(short, long)]
@@ -59,4 +105,39 @@ pub struct PatchApiProduct {
     #[arg(short, long)]
     /// The list of comma separate of the API product
     pub portal_ids: Option<String>,
+
+    #[arg(short = 'f', long)]
+    /// Read the body of the request from a JSON or YAML file, - reads stdin. Any field Konnect
+    /// supports can be set, the flags given as well take precedence
+    pub filename: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct DescribeApiProduct {
+    /// The id or name of the API product to describe
+    pub id_or_name: String,
+}
+
+#[derive(Debug, Args)]
+pub struct EditApiProduct {
+    /// The id or name of the API product to edit
+    pub id_or_name: String,
+}
+
+#[derive(Debug, Args)]
+pub struct CloneApiProduct {
+    /// The id or name of the API product to clone
+    pub id_or_name: String,
+
+    #[arg(short, long, required = true)]
+    /// The name of the new API product
+    pub name: String,
+
+    #[arg(long)]
+    /// Do not link the new versions to the gateway services of the source versions
+    pub skip_gateway_service: bool,
+
+    #[arg(long)]
+    /// Do not copy the documents of the API product
+    pub skip_documents: bool,
 }
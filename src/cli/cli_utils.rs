@@ -1,3 +1,20 @@
+use std::io::{self, BufRead, IsTerminal, Write};
+
+/// Asks a yes/no question on the terminal. Anything but y or yes is a no, and so is a stdin that
+/// is not a terminal.
+pub fn confirm(question: &str) -> bool {
+    if !io::stdin().is_terminal() {
+        return false;
+    }
+    print!("{} [y/N] ", question);
+    io::stdout().flush().ok();
+    let mut answer = String::new();
+    if io::stdin().lock().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
 pub fn validate_label(string: &str) -> Result<String, String> {
     if string.is_empty() {
         Err("labels if provided should not be empty".to_string())
@@ -0,0 +1,32 @@
+use clap::{Args, ValueEnum};
+
+#[derive(Debug, Args)]
+pub struct GenerateCompletion {
+    #[arg(value_enum)]
+    /// Shell to generate the completion script for
+    pub shell: CompletionShell,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum CompletionShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl From<CompletionShell> for clap_complete::Shell {
+    fn from(shell: CompletionShell) -> Self {
+        match shell {
+            CompletionShell::Bash => clap_complete::Shell::Bash,
+            CompletionShell::Zsh => clap_complete::Shell::Zsh,
+            CompletionShell::Fish => clap_complete::Shell::Fish,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct CompleteValues {
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    /// Words of the command line after konnectctl, the last one being the word to complete
+    pub words: Vec<String>,
+}
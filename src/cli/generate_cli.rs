@@ -0,0 +1,36 @@
+use clap::Args;
+
+#[derive(Debug, Args)]
+pub struct GenerateGatewayConfig {
+    #[arg(short = 'f', long, required = true)]
+    /// Path to the OpenAPI spec file to generate the configuration from
+    pub from_spec: String,
+
+    #[arg(short = 'n', long)]
+    /// Name of the gateway service, defaults to x-kong-name or the spec title
+    pub service_name: Option<String>,
+
+    #[arg(short = 'u', long)]
+    /// Upstream url of the service, defaults to the first server of the spec
+    pub upstream_url: Option<String>,
+
+    #[arg(long)]
+    /// Also generate plugins from x-kong-plugin-<name> extensions
+    pub with_plugins: bool,
+
+    #[arg(short = 'o', long, conflicts_with = "control_plane_id")]
+    /// Write the declarative configuration to this file instead of stdout
+    pub output_file: Option<String>,
+
+    #[arg(short, long)]
+    /// Create the service and routes on this control plane instead of printing them
+    pub control_plane_id: Option<String>,
+
+    #[arg(short = 'p', long, requires_all = ["control_plane_id", "product_version_id"], visible_alias = "product")]
+    /// API product id or name whose version should be linked to the created service
+    pub product_id: Option<String>,
+
+    #[arg(short = 'v', long, requires = "product_id", visible_alias = "version")]
+    /// API product version id or name to link to the created service
+    pub product_version_id: Option<String>,
+}
@@ -1,4 +1,8 @@
-use clap::{Args, Parser, Subcommand};
+use std::time::Duration;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use crate::utils::{json_query::Query, konnect_utils::DryRun};
 
 use super::{
     api_product_version_spec::{
@@ -7,8 +11,19 @@ use super::{
     },
     api_product_versions_cli::{
         CreateApiProductVersion, DeleteAPIProductVersion, GetAPIProductVersion,
+        PatchApiProductVersion,
+    },
+    api_products_cli::{
+        CloneApiProduct, CreateApiProduct, DeleteApiProduct, DescribeApiProduct, EditApiProduct,
+        GetApiProduct, PatchApiProduct,
+    },
+    completion_cli::{CompleteValues, GenerateCompletion},
+    generate_cli::GenerateGatewayConfig,
+    manifest_cli::{
+        ApplyManifests, DiffManifests, DriftManifests, ExportCatalog, ListState, MoveState,
+        RefreshState, RenderManifests,
     },
-    api_products_cli::{CreateApiProduct, DeleteApiProduct, GetApiProduct, PatchApiProduct},
+    spec_cli::{BundleSpec, DiffApiProductVersionSpec, ValidateSpec},
 };
 
 #[derive(Debug, Parser)] // requires `derive` feature
@@ -17,6 +32,24 @@ use super::{
 pub struct KonnectCLIArgs {
     #[clap(subcommand)]
     pub entity_type: EntityType,
+
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "http"
+    )]
+    /// Print the requests that would change Konnect instead of sending them, as HTTP or as curl
+    /// commands. Reads are still sent. apply, clone and generate print what they would create
+    pub dry_run: Option<DryRun>,
+
+    #[arg(long, global = true, value_name = "EXPR")]
+    /// Print only what a jq expression selects from the JSON output, e.g. '.data[].name'. Paths,
+    /// [], length, keys and select(.field == value) are supported, joined with |. Strings are
+    /// printed without quotes
+    pub query: Option<Query>,
 }
 
 #[derive(Debug, Subcommand)]
@@ -26,6 +59,11 @@ pub enum EntityType {
     /// try konnectctl get --help
     Get(GetCommand),
 
+    /// Show Konnect objects with everything below them
+    ///
+    /// try konnectctl describe --help
+    Describe(DescribeCommand),
+
     /// Create Konnect objects
     ///
     /// try konnectctl create --help
@@ -40,6 +78,310 @@ pub enum EntityType {
     ///
     /// try konnectctl patch --help
     Patch(PatchCommand),
+
+    /// Edit Konnect objects in your editor
+    ///
+    /// try konnectctl edit --help
+    Edit(EditCommand),
+
+    /// Validate local files without talking to Konnect
+    ///
+    /// try konnectctl validate --help
+    Validate(ValidateCommand),
+
+    /// Compare Konnect objects
+    ///
+    /// With -f, compares manifests with the live objects they describe and exits with 1 when
+    /// applying them would change anything.
+    ///
+    /// e.g usage
+    ///
+    /// konnectctl diff -f ./konnect/
+    ///
+    /// try konnectctl diff --help
+    Diff(DiffCommand),
+
+    /// Generate configuration from local files
+    ///
+    /// try konnectctl generate --help
+    Generate(GenerateCommand),
+
+    /// Bundle local files without talking to Konnect
+    ///
+    /// try konnectctl bundle --help
+    Bundle(BundleCommand),
+
+    /// Copy Konnect objects
+    ///
+    /// try konnectctl clone --help
+    Clone(CloneCommand),
+
+    /// Create or update Konnect objects from manifest files
+    ///
+    /// Manifests are YAML documents with a kind (APIProduct, APIProductVersion or
+    /// APIProductVersionSpecification), metadata.name and a spec. Objects are matched by name,
+    /// created when missing and patched when they differ. Unchanged objects are not written.
+    ///
+    /// e.g usage
+    ///
+    /// konnectctl apply -f ./konnect/
+    ///
+    /// konnectctl apply -f ./konnect/overlays/prod/ --values values-prod.yaml
+    Apply(ApplyManifests),
+
+    /// Report objects that drifted from their manifests, are missing or are not declared
+    ///
+    /// Every declared object is compared with Konnect, and products, versions, specifications and
+    /// documents that no manifest declares are reported as extra. The report is JSON or JUnit XML
+    /// and the command exits with 1 when anything drifted, so a scheduled job can alert on it.
+    ///
+    /// e.g usage
+    ///
+    /// konnectctl drift -f ./konnect/ --format junit > drift.xml
+    Drift(DriftManifests),
+
+    /// Print manifests once variables are substituted and overlays are patched
+    ///
+    /// ${NAME} is replaced with the value of NAME from the --values files or the environment, and
+    /// ${NAME:-default} falls back to default. A directory holding an overlay.yaml is an overlay:
+    /// its resources are loaded and its patches are merged into them. The output can be piped
+    /// to apply -f - or diff -f -.
+    ///
+    /// e.g usage
+    ///
+    /// konnectctl render -f ./konnect/overlays/prod/ --values values-prod.yaml
+    Render(RenderManifests),
+
+    /// Inspect and update the state file mapping manifests to Konnect ids
+    ///
+    /// try konnectctl state --help
+    State(StateCommand),
+
+    /// Write manifests for every API product, version, specification and document
+    ///
+    /// One file is written per object, ids are left out and parents are referenced by name so the
+    /// directory can be applied with konnectctl apply. Specifications and documents are written
+    /// next to their manifests.
+    ///
+    /// e.g usage
+    ///
+    /// konnectctl export --dir ./konnect/
+    Export(ExportCatalog),
+
+    /// Print a shell completion script
+    ///
+    /// Besides commands and flags, product, version and specification names are completed after
+    /// --product-id, --product-version-id and --id. They are fetched from Konnect and cached for a
+    /// minute so completion stays fast.
+    ///
+    /// e.g usage
+    ///
+    /// source <(konnectctl completion bash)
+    ///
+    /// source <(konnectctl completion zsh)
+    ///
+    /// konnectctl completion fish | source
+    Completion(GenerateCompletion),
+
+    /// Print the names completing the last word, used by the completion scripts
+    #[command(name = "__complete", hide = true)]
+    Complete(CompleteValues),
+}
+
+#[derive(Debug, Args)]
+pub struct StateCommand {
+    #[clap(subcommand)]
+    pub command: StateSubCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum StateSubCommand {
+    /// List the objects recorded in the state file with their ids
+    ///
+    /// e.g usage
+    ///
+    /// konnectctl state list
+    List(ListState),
+
+    /// Look every declared object up again and record its id
+    ///
+    /// Recorded ids that still exist are kept, the other objects are looked up by name. Objects
+    /// the manifests no longer declare are dropped from the state.
+    ///
+    /// e.g usage
+    ///
+    /// konnectctl state refresh -f ./konnect/
+    Refresh(RefreshState),
+
+    /// Record that an object is renamed in the manifests
+    ///
+    /// Run it before applying manifests where the object has its new name, the object is then
+    /// patched with the new name rather than created again. Children of the object follow it.
+    ///
+    /// e.g usage
+    ///
+    /// konnectctl state mv APIProduct payments-api payments
+    ///
+    /// konnectctl state mv APIProductVersion payments/v1 payments/v1.0
+    Mv(MoveState),
+}
+
+#[derive(Debug, Args)]
+pub struct DescribeCommand {
+    #[clap(subcommand)]
+    pub command: DescribeSubCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DescribeSubCommand {
+    /// Show an API product as a tree of its versions, specifications, portals and documents
+    ///
+    /// Versions are shown with their publish and deprecation state, the gateway service they are
+    /// linked to and the portals they are published to.
+    ///
+    /// e.g usage
+    ///
+    /// konnectctl describe api-product <api-product-id|name>
+    ApiProduct(DescribeApiProduct),
+}
+
+#[derive(Debug, Args)]
+pub struct EditCommand {
+    #[clap(subcommand)]
+    pub command: EditSubCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum EditSubCommand {
+    /// Edit an API product as YAML in $EDITOR and patch what changed
+    ///
+    /// The editor is taken from KONNECTCTL_EDITOR, then EDITOR, and defaults to vi. Only the
+    /// fields changed are sent, removed fields are set to null. If Konnect rejects the change the
+    /// editor is opened again with the error, emptying the file or saving it unchanged cancels.
+    ///
+    /// e.g usage
+    ///
+    /// konnectctl edit api-product <api-product-id|name>
+    ///
+    /// EDITOR="code --wait" konnectctl edit api-product payments
+    ApiProduct(EditApiProduct),
+}
+
+#[derive(Debug, Args)]
+pub struct CloneCommand {
+    #[clap(subcommand)]
+    pub command: CloneSubCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CloneSubCommand {
+    /// Copy an API product with all its versions, specifications and documents
+    ///
+    /// Labels are copied, portals are not. If anything fails the objects created so far are
+    /// deleted again. The id mapping between the source and the copy is printed at the end.
+    ///
+    /// e.g usage
+    ///
+    /// konnectctl clone api-product <api-product-id|name> --name "new name"
+    ApiProduct(CloneApiProduct),
+}
+
+#[derive(Debug, Args)]
+pub struct BundleCommand {
+    #[clap(subcommand)]
+    pub command: BundleSubCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum BundleSubCommand {
+    /// Resolve the external $refs of a spec split across files into a single document
+    ///
+    /// Referenced schemas, parameters, responses and request bodies are hoisted into
+    /// components, anything else is inlined. Specs are bundled the same way before they are
+    /// uploaded with create or patch api-product-version-specification.
+    ///
+    /// e.g usage
+    ///
+    /// konnectctl bundle spec -f ./openapi/root.yaml -o ./openapi.bundled.yaml
+    Spec(BundleSpec),
+}
+
+#[derive(Debug, Args)]
+pub struct GenerateCommand {
+    #[clap(subcommand)]
+    pub command: GenerateSubCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum GenerateSubCommand {
+    /// Generate a gateway service and routes from an OpenAPI spec
+    ///
+    /// One route is created per operation. x-kong-name, x-kong-service-defaults,
+    /// x-kong-route-defaults and x-kong-plugin-<name> extensions are honoured.
+    ///
+    /// e.g usage
+    ///
+    /// Print Kong declarative configuration
+    ///
+    /// konnectctl generate gateway-config -f ./openapi.yaml > kong.yaml
+    ///
+    /// Create the service on a control plane and link it to a product version
+    ///
+    /// konnectctl generate gateway-config -f ./openapi.yaml -c <control-plane-id> -p
+    /// <api-product-id> -v <api-product-version-id>
+    GatewayConfig(GenerateGatewayConfig),
+}
+
+#[derive(Debug, Args)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+pub struct DiffCommand {
+    #[clap(subcommand)]
+    pub command: Option<DiffSubCommand>,
+
+    #[clap(flatten)]
+    pub manifests: DiffManifests,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DiffSubCommand {
+    /// Compare two revisions of a specification and report breaking changes
+    ///
+    /// The base is a remote specification (or --base-file) and the revision is either a local file
+    /// or the specification of another product version. The command exits with 1 when breaking
+    /// changes are found.
+    ///
+    /// e.g usage
+    ///
+    /// Compare a local file with the uploaded spec
+    ///
+    /// konnectctl diff api-product-version-specification -p <api-product-id> -v
+    /// <api-product-version-id> -f ./openapi.yaml
+    ///
+    /// Compare the specs of two product versions
+    ///
+    /// konnectctl diff api-product-version-specification -p <api-product-id> -v
+    /// <api-product-version-id> --against-version-id <api-product-version-id>
+    ApiProductVersionSpecification(DiffApiProductVersionSpec),
+}
+
+#[derive(Debug, Args)]
+pub struct ValidateCommand {
+    #[clap(subcommand)]
+    pub command: ValidateSubCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ValidateSubCommand {
+    /// Validate an OpenAPI (2.0, 3.0, 3.1) or AsyncAPI specification file
+    ///
+    /// OpenAPI documents are validated against the JSON Schema of their version, AsyncAPI
+    /// documents only go through structural checks. Validation also runs before `create` and
+    /// `patch` of an api-product-version-specification unless --skip-validation is passed.
+    ///
+    /// e.g usage
+    ///
+    /// konnectctl validate spec -f ./openapi.yaml
+    Spec(ValidateSpec),
 }
 
 #[derive(Debug, Args)]
@@ -58,6 +400,36 @@ pub struct DeleteCommand {
 pub struct GetCommand {
     #[clap(subcommand)]
     pub command: GetSubCommand,
+
+    #[arg(short, long, global = true)]
+    /// Keep polling and print what changes, added, removed or modified, with a timestamp
+    pub watch: bool,
+
+    #[arg(long, global = true, value_name = "INTERVAL", default_value = "5s", value_parser = humantime::parse_duration, requires = "watch")]
+    /// How often to poll with --watch, e.g. 5s or 1m
+    pub interval: Duration,
+
+    #[arg(short, long, global = true, value_enum)]
+    /// Print JSON, the default, or a table. --columns, --sort-by and --no-headers print a table
+    pub output: Option<OutputFormat>,
+
+    #[arg(long, global = true, value_delimiter = ',')]
+    /// Columns of the table, as dotted paths into each item, e.g. id,name,labels.team,updated_at
+    pub columns: Vec<String>,
+
+    #[arg(long, global = true, value_name = "COLUMN")]
+    /// Sort the rows of the table by a column, e.g. updated_at
+    pub sort_by: Option<String>,
+
+    #[arg(long, global = true)]
+    /// Do not print the header row of the table
+    pub no_headers: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    Json,
+    Table,
 }
 
 #[derive(Debug, Args)]
@@ -67,14 +439,32 @@ pub struct CreateCommand {
 }
 
 #[derive(Debug, Subcommand)]
+#[allow(clippy::enum_variant_names)]
 pub enum PatchSubCommand {
     /// Patch an API product
     ///
     /// e.g usage
     ///
     /// konnectctl patch api-product -i <api-product-id> -n "new name"
+    ///
+    /// Patching from a JSON or YAML body, - reads stdin
+    ///
+    /// konnectctl patch api-product -i <api-product-id> -f body.yaml
     ApiProduct(PatchApiProduct),
 
+    /// Patch an API product version
+    ///
+    /// e.g usage
+    ///
+    /// konnectctl patch api-product-version -p <api-product-id> -i <api-product-version-id> -s
+    /// published
+    ///
+    /// Patching from a JSON or YAML body, - reads stdin
+    ///
+    /// konnectctl patch api-product-version -p <api-product-id> -i <api-product-version-id> -f
+    /// version.yaml
+    ApiProductVersion(PatchApiProductVersion),
+
     /// Patch product version specification
     ///
     /// e.g usage
@@ -85,12 +475,33 @@ pub enum PatchSubCommand {
 }
 
 #[derive(Debug, Subcommand)]
+#[allow(clippy::enum_variant_names)]
 pub enum DeleteSubCommand {
     /// Delete an API product
     ///
+    /// The products to delete are listed with their versions, specifications and documents, and
+    /// the deletion has to be confirmed unless --yes is passed. Products that still have versions
+    /// are deleted with --cascade, which deletes everything below them first.
+    ///
     /// e.g usage
     ///
     /// konnectctl delete api-product -i <api-product-id>
+    ///
+    /// To delete a product with its versions, specifications and documents, without a prompt
+    ///
+    /// konnectctl delete api-product -i <api-product-id> --cascade --yes
+    ///
+    /// To delete every API product whose labels match a selector
+    ///
+    /// konnectctl delete api-product -l team=payments,env!=prod
+    ///
+    /// To delete the API products named Test... created more than a week ago
+    ///
+    /// konnectctl delete api-product --name-prefix Test --older-than 7d --cascade
+    ///
+    /// To delete the API products listed on stdin, one id or name per line
+    ///
+    /// cat leftovers.txt | konnectctl delete api-product -f - --yes
     ApiProduct(DeleteApiProduct),
 
     /// Delete an API product version
@@ -98,6 +509,8 @@ pub enum DeleteSubCommand {
     /// e.g usage
     ///
     /// konnectctl delete api-product-version -p <api-product-id> -i <api-product-version-id>
+    ///
+    /// konnectctl delete api-product-version -p <api-product-id> -l env=dev
     ApiProductVersion(DeleteAPIProductVersion),
 
     /// Delete a API production version specification
@@ -110,6 +523,7 @@ pub enum DeleteSubCommand {
 }
 
 #[derive(Debug, Subcommand)]
+#[allow(clippy::enum_variant_names)]
 pub enum GetSubCommand {
     /// Get API product(s). This will return a list of products or a product depending on whether a
     /// product id is passed or not.
@@ -129,6 +543,18 @@ pub enum GetSubCommand {
     /// To get a product by name
     ///
     /// konnectctl get api-product --name "abd" | jq '.data[0].id'
+    ///
+    /// To get the products whose labels match a selector
+    ///
+    /// konnectctl get api-product -l team=payments,env!=prod,!deprecated
+    ///
+    /// To list the products as a table sorted by their last update
+    ///
+    /// konnectctl get api-product --columns id,name,labels.team,updated_at --sort-by updated_at
+    ///
+    /// To print the changes to a product as they happen
+    ///
+    /// konnectctl get api-product --id <api-product-id> --watch --interval 10s
     ApiProduct(GetApiProduct),
 
     /// Get version(s) for a given API product.
@@ -147,6 +573,14 @@ pub enum GetSubCommand {
     ///
     /// konnectctl get api-product-version -p <api-product-id>--i <api-product-version-id> | jq '.id'
     ///
+    /// Names can be used instead of ids
+    ///
+    /// konnectctl get api-product-version --product payments-api -i v2
+    ///
+    /// To follow the portal publish status of the versions during a rollout
+    ///
+    /// konnectctl get api-product-version --product payments-api --watch
+    ///
     /// konnectctl get api-product --name "abd" | jq '.data[0].id'
     ApiProductVersion(GetAPIProductVersion),
 
@@ -164,6 +598,7 @@ pub enum GetSubCommand {
 }
 
 #[derive(Debug, Subcommand)]
+#[allow(clippy::enum_variant_names)]
 pub enum CreateSubCommand {
     /// Create an API Product
     ///
@@ -180,6 +615,10 @@ pub enum CreateSubCommand {
     /// Adding portal ids
     ///
     /// konnectctl create api-product -n <NAME> -d <DESCRIPTION> -p "portalid-1,portalid-2"
+    ///
+    /// From a JSON or YAML body, flags given as well take precedence
+    ///
+    /// konnectctl create api-product -f product.yaml -n <NAME>
     ApiProduct(CreateApiProduct),
 
     /// Create an API Product version
@@ -194,8 +633,20 @@ pub enum CreateSubCommand {
     ///
     /// konnectctl create api-product-version -p <api-product-id> -n <NAME> -g <gateway-service-id> -c
     /// <control-plane-id>
+    ///
+    /// From a JSON or YAML body
+    ///
+    /// konnectctl create api-product-version -p <api-product-id> -f version.json
     ApiProductVersion(CreateApiProductVersion),
 
     /// Add a specification file to a product version
+    ///
+    /// The file is validated locally before it is uploaded, pass --skip-validation to upload it
+    /// as is.
+    ///
+    /// e.g. usage
+    ///
+    /// konnectctl create api-product-version-specification -p <api-product-id> -v
+    /// <api-product-version-id> -f ./openapi.yaml
     ApiProductVersionSpecification(CreateApiProductVersionSpec),
 }
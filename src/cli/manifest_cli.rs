@@ -0,0 +1,168 @@
+use clap::{Args, ValueEnum};
+
+use crate::utils::label_selector::LabelSelector;
+
+/// State file used by --state without a value and by the state commands.
+pub const DEFAULT_STATE_FILE: &str = ".konnectctl/state.json";
+
+/// A selector for --prune and drift, which must name a label the owned products carry: with only
+/// `!=` and `!key` clauses every unlabelled product would be pruned or reported.
+fn parse_ownership_selector(s: &str) -> Result<LabelSelector, String> {
+    let selector: LabelSelector = s.parse()?;
+    if !selector.requires_a_label() {
+        return Err(format!(
+            "{} also matches products without labels, the selector needs a key=value or key clause",
+            s
+        ));
+    }
+    Ok(selector)
+}
+
+/// The manifests a command reads.
+#[derive(Debug, Args)]
+pub struct ManifestFiles {
+    #[arg(short = 'f', long = "filename", required = true)]
+    /// Manifest file, directory or overlay, directories are read recursively, - reads stdin. Can
+    /// be repeated
+    pub files: Vec<String>,
+
+    #[arg(long = "values")]
+    /// YAML file of values for the ${NAME} variables of the manifests, variables it does not
+    /// define are read from the environment. Can be repeated, later files take precedence
+    pub values: Vec<String>,
+}
+
+/// The manifests a command compares with Konnect, and how their objects are found there.
+#[derive(Debug, Args)]
+pub struct ManifestSource {
+    #[command(flatten)]
+    pub manifests: ManifestFiles,
+
+    #[arg(long, num_args = 0..=1, default_missing_value = DEFAULT_STATE_FILE)]
+    /// Find declared objects by the ids recorded in a state file rather than by name,
+    /// .konnectctl/state.json when no file is given
+    pub state: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct ApplyManifests {
+    #[command(flatten)]
+    pub source: ManifestSource,
+
+    #[arg(long, requires = "selector")]
+    /// Delete the objects matching --selector that the manifests do not declare
+    pub prune: bool,
+
+    #[arg(short = 'l', long, requires = "prune", value_parser = parse_ownership_selector)]
+    /// Labels marking the products owned by the manifests, e.g. managed-by=platform-repo
+    pub selector: Option<LabelSelector>,
+
+    #[arg(short, long)]
+    /// Delete pruned objects without asking for confirmation
+    pub yes: bool,
+
+    #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(u16).range(1..))]
+    /// How many independent objects are written at the same time
+    pub concurrency: u16,
+}
+
+#[derive(Debug, Args)]
+pub struct DiffManifests {
+    #[command(flatten)]
+    pub source: ManifestSource,
+
+    #[arg(short, long, value_enum, default_value_t = ManifestDiffFormat::Unified)]
+    /// How changed resources are shown
+    pub output: ManifestDiffFormat,
+
+    #[arg(long, requires = "selector")]
+    /// Also show the objects apply --prune would delete
+    pub prune: bool,
+
+    #[arg(short = 'l', long, requires = "prune", value_parser = parse_ownership_selector)]
+    /// Labels marking the products owned by the manifests, e.g. managed-by=platform-repo
+    pub selector: Option<LabelSelector>,
+
+    #[arg(long)]
+    /// Do not color the output, it is never colored when not printed to a terminal
+    pub no_color: bool,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ManifestDiffFormat {
+    /// Unified diff of the live object and the manifest
+    Unified,
+
+    /// One line per changed field
+    Fields,
+}
+
+#[derive(Debug, Args)]
+pub struct DriftManifests {
+    #[command(flatten)]
+    pub source: ManifestSource,
+
+    #[arg(long, value_enum, default_value_t = DriftFormat::Json)]
+    /// Format of the report
+    pub format: DriftFormat,
+
+    #[arg(short = 'l', long, value_parser = parse_ownership_selector)]
+    /// Labels marking the products owned by the manifests, undeclared objects are only reported
+    /// for those, e.g. managed-by=platform-repo
+    pub selector: LabelSelector,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum DriftFormat {
+    /// JSON, a summary and the status of every object
+    Json,
+
+    /// JUnit XML, one test case per object
+    Junit,
+}
+
+#[derive(Debug, Args)]
+pub struct RenderManifests {
+    #[command(flatten)]
+    pub manifests: ManifestFiles,
+}
+
+#[derive(Debug, Args)]
+pub struct ExportCatalog {
+    #[arg(short, long, required = true)]
+    /// Directory to write the manifests to, it must be empty or not exist yet
+    pub dir: String,
+}
+
+#[derive(Debug, Args)]
+pub struct ListState {
+    #[arg(long, default_value = DEFAULT_STATE_FILE)]
+    /// State file to read
+    pub state: String,
+}
+
+#[derive(Debug, Args)]
+pub struct RefreshState {
+    #[command(flatten)]
+    pub manifests: ManifestFiles,
+
+    #[arg(long, default_value = DEFAULT_STATE_FILE)]
+    /// State file to write
+    pub state: String,
+}
+
+#[derive(Debug, Args)]
+pub struct MoveState {
+    /// Kind of the renamed object, e.g. APIProduct
+    pub kind: String,
+
+    /// Current path of the object, e.g. payments-api or payments-api/v1 for a version
+    pub from: String,
+
+    /// New path of the object
+    pub to: String,
+
+    #[arg(long, default_value = DEFAULT_STATE_FILE)]
+    /// State file to update
+    pub state: String,
+}
@@ -2,4 +2,8 @@ pub mod api_product_version_spec;
 pub mod api_product_versions_cli;
 pub mod api_products_cli;
 pub mod cli_utils;
+pub mod completion_cli;
+pub mod generate_cli;
 pub mod main_cli;
+pub mod manifest_cli;
+pub mod spec_cli;
@@ -0,0 +1,65 @@
+use clap::{ArgGroup, Args, ValueEnum};
+
+#[derive(Debug, Args)]
+pub struct ValidateSpec {
+    #[arg(short = 'f', long, required = true)]
+    /// Path to spec file
+    pub path_to_spec_file: String,
+}
+
+#[derive(Debug, Args)]
+#[command(group(ArgGroup::new("base").required(true).args(["product_version_id", "base_file"])))]
+#[command(group(ArgGroup::new("revision").required(true).args(["path_to_spec_file", "against_version_id"])))]
+pub struct DiffApiProductVersionSpec {
+    #[arg(short = 'p', long, visible_alias = "product")]
+    /// API product id or name
+    pub product_id: Option<String>,
+
+    #[arg(short = 'v', long, requires = "product_id", visible_alias = "version")]
+    /// API product version id or name holding the base specification
+    pub product_version_id: Option<String>,
+
+    #[arg(short, long, requires = "product_version_id")]
+    /// Base API specification id or name, defaults to the only specification of the version
+    pub id: Option<String>,
+
+    #[arg(long)]
+    /// Path to a local file to use as the base specification
+    pub base_file: Option<String>,
+
+    #[arg(short = 'f', long)]
+    /// Path to the revised spec file
+    pub path_to_spec_file: Option<String>,
+
+    #[arg(long, requires = "product_id", visible_alias = "against-version")]
+    /// API product version id or name holding the revised specification
+    pub against_version_id: Option<String>,
+
+    #[arg(long, requires = "against_version_id")]
+    /// Revised API specification id or name, defaults to the only specification of the version
+    pub against_id: Option<String>,
+
+    #[arg(short, long, value_enum, default_value_t = DiffOutput::Text)]
+    /// Output format of the report
+    pub output: DiffOutput,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum DiffOutput {
+    /// Human readable list of changes
+    Text,
+
+    /// Machine readable report
+    Json,
+}
+
+#[derive(Debug, Args)]
+pub struct BundleSpec {
+    #[arg(short = 'f', long, required = true)]
+    /// Path to the root spec file
+    pub path_to_spec_file: String,
+
+    #[arg(short = 'o', long)]
+    /// Write the bundled spec to this file instead of stdout
+    pub output_file: Option<String>,
+}
@@ -4,23 +4,31 @@ use api::{
         GetAllAPIProductVersionSpecRequest, PatchAPIProductVersionSpecRequest,
     },
     api_product_versions::DeleteAPIProductVersionRequest,
-    api_products::{
-        DeleteAPIProductRequest, GetAPIProductRequest, GetAPIProductsArrayRequest,
-        PatchAPIProductRequest,
-    },
-    api_utils::Executable,
+    api_products::{GetAPIProductRequest, GetAPIProductsArrayRequest, PatchAPIProductRequest},
+    api_utils::{Executable, Tabular},
 };
-use clap::Parser;
+use clap::{CommandFactory, Parser};
 use cli::main_cli;
 use serde::Serialize;
 use serde_json::Value;
-use utils::konnect_utils::{self, FetchFilter};
+use std::{error::Error, fmt::Display, io::IsTerminal, time::Duration};
+use utils::{
+    completion, gateway_config, json_query,
+    konnect_utils::{self, FetchFilter},
+    spec_bundler, spec_diff, spec_validator, table,
+};
 
-use crate::api::api_utils::ToJson;
+use crate::api::api_utils::{merge_json, ToJson};
+use manifest::{
+    manifest_apply, manifest_diff, manifest_drift, manifest_export, manifest_live::LiveState,
+    manifest_loader, manifest_plan, manifest_prune, manifest_state, manifest_template,
+    manifest_types,
+};
 
 mod cli;
 
 mod api;
+mod manifest;
 mod utils;
 use log::{error, info};
 
@@ -28,7 +36,11 @@ enum Operation {
     Fetch(FetchFilter),
     Delete,
     Create,
+    /// Create from a body read from a file, with the flags merged over it
+    CreateWith(Value),
     Patch(Value),
+    /// Fetch, then print the changes every interval
+    Watch(FetchFilter, Duration),
 }
 
 fn handle_request<T>(request: &T, path: &str, operation: Operation)
@@ -47,134 +59,341 @@ where
                 Err(err) => error!("Error processing request for {} : {}", path, err),
             }
         }
+        Operation::Watch(filter, interval) => {
+            let watched = utils::watch::watch(interval, || {
+                Ok(json_query::select(serde_json::to_value(
+                    request.fetch(path, filter.clone())?,
+                )?)?)
+            });
+            if let Err(err) = watched {
+                error!("Error processing request for {} : {}", path, err);
+            }
+        }
         _ => {
             let response = match operation {
                 Operation::Fetch(filter) => request.fetch(path, filter),
-                Operation::Delete => unreachable!(),
+                Operation::Delete | Operation::Watch(..) => unreachable!(),
                 Operation::Create => request.create(path),
+                Operation::CreateWith(body) => request.create_with(path, body),
                 Operation::Patch(value) => request.patch(path, value),
             };
             match response {
                 Ok(res) => {
                     info!("{}", res.to_json());
-                    println!("{}", res.to_json());
+                    print_json(&res);
                 }
+                // A dry run printed the request, there is no response
+                Err(err) if err.is::<konnect_utils::NotSent>() => {}
                 Err(err) => error!("Error processing request for {} : {}", path, err),
             }
         }
     }
 }
 
+/// How `get` prints what it fetched.
+struct GetView {
+    watch: Option<Duration>,
+    table: Option<table::TableOptions>,
+}
+
+impl GetView {
+    fn new(get_command: &main_cli::GetCommand) -> Result<Self, String> {
+        let table_options = !get_command.columns.is_empty()
+            || get_command.sort_by.is_some()
+            || get_command.no_headers;
+        let table = match get_command.output {
+            Some(main_cli::OutputFormat::Table) => true,
+            Some(main_cli::OutputFormat::Json) if table_options => {
+                return Err(
+                    "--columns, --sort-by and --no-headers print a table, not -o json".to_string(),
+                )
+            }
+            Some(main_cli::OutputFormat::Json) => false,
+            None => table_options,
+        };
+        if table && get_command.watch {
+            return Err("--watch prints changes, it cannot print a table".to_string());
+        }
+        Ok(GetView {
+            watch: get_command.watch.then_some(get_command.interval),
+            table: table.then(|| table::TableOptions {
+                columns: get_command.columns.clone(),
+                sort_by: get_command.sort_by.clone(),
+                no_headers: get_command.no_headers,
+            }),
+        })
+    }
+}
+
+/// Prints `response` as a table, of the columns given or the default columns of the response.
+/// The rows are what --query selects when it is given.
+fn print_table<R: Serialize + Tabular>(
+    response: &R,
+    options: &table::TableOptions,
+) -> Result<(), Box<dyn Error>> {
+    let value = json_query::select(serde_json::to_value(response)?)?;
+    let mut options = options.clone();
+    if options.columns.is_empty() {
+        options.columns = R::COLUMNS.iter().map(|c| c.to_string()).collect();
+    }
+    print!(
+        "{}",
+        table::render_table(table::rows(value), &options, table::terminal_width())
+    );
+    Ok(())
+}
+
+/// Fetches for `get` and prints the response as JSON or as a table, or keeps watching it.
+fn handle_fetch<T>(request: &T, path: &str, filter: FetchFilter, view: &GetView)
+where
+    T: Executable,
+    T::Response: ToJson + Serialize + Tabular,
+{
+    match (&view.table, view.watch) {
+        (Some(options), _) => {
+            let printed = request
+                .fetch(path, filter)
+                .and_then(|res| print_table(&res, options));
+            if let Err(err) = printed {
+                error!("Error processing request for {} : {}", path, err);
+            }
+        }
+        (None, Some(interval)) => handle_request(request, path, Operation::Watch(filter, interval)),
+        (None, None) => handle_request(request, path, Operation::Fetch(filter)),
+    }
+}
+
+/// Prints what `fetch` returns as JSON or as a table, or keeps watching it with --watch.
+fn print_fetched<R: Serialize + Tabular>(
+    view: &GetView,
+    mut fetch: impl FnMut() -> Result<R, Box<dyn Error>>,
+) {
+    let result = match (&view.table, view.watch) {
+        (Some(options), _) => fetch().and_then(|res| print_table(&res, options)),
+        (None, Some(interval)) => utils::watch::watch(interval, || {
+            Ok(json_query::select(serde_json::to_value(fetch()?)?)?)
+        }),
+        (None, None) => fetch().map(|res| print_json(&res)),
+    };
+    if let Err(e) = result {
+        exit_with_error(e);
+    }
+}
+
+/// Prints `value` as JSON, or what --query selects from it.
+/// Prints `value` as JSON, see `json_query::print`.
+fn print_json<T: Serialize>(value: &T) {
+    json_query::print(value).unwrap_or_else(|e| exit_with_error(e));
+}
+
+fn exit_with_error(err: impl Display) -> ! {
+    error!("{}", err);
+    std::process::exit(1)
+}
+
+/// Reads the body given with -f, see `api_utils::read_body`.
+fn read_body(filename: &str) -> Value {
+    api::api_utils::read_body(filename).unwrap_or_else(|e| exit_with_error(e))
+}
+
+/// Reads the manifests given with -f, see `manifest_loader::load_manifest_files`.
+fn load_manifests(manifests: &cli::manifest_cli::ManifestFiles) -> Vec<manifest_types::Manifest> {
+    manifest_loader::load_manifest_files(manifests).unwrap_or_else(|e| exit_with_error(e))
+}
+
+/// Replaces names with the ids they name, see `api_products::resolve_ids`.
+fn resolve_ids(
+    product: &mut String,
+    version: Option<&mut String>,
+    specification: Option<&mut String>,
+) {
+    if let Err(e) = api::api_products::resolve_ids(product, version, specification) {
+        exit_with_error(e);
+    }
+}
+
+/// Loads one side of a spec diff, either from a local file or from Konnect.
+fn load_spec_for_diff(
+    file: &Option<String>,
+    product_id: &Option<String>,
+    version_id: &Option<String>,
+    id: &Option<String>,
+) -> Value {
+    let (name, content) = match (file, product_id, version_id) {
+        (Some(f), _, _) => (
+            f.clone(),
+            spec_validator::read_spec_file(f).unwrap_or_else(|e| exit_with_error(e)),
+        ),
+        (None, Some(pid), Some(vid)) => {
+            let spec = api::api_product_version_spec::fetch_spec_content(pid, vid, id.as_deref())
+                .unwrap_or_else(|e| exit_with_error(e));
+            (spec.name, spec.content)
+        }
+        _ => exit_with_error("Either a spec file or a product version id is required"),
+    };
+    spec_validator::parse_spec(&content)
+        .unwrap_or_else(|issue| exit_with_error(format!("{} could not be parsed, {}", name, issue)))
+}
+
 fn main() {
     env_logger::init();
     let args = cli::main_cli::KonnectCLIArgs::parse();
+    if let Some(format) = args.dry_run {
+        konnect_utils::set_dry_run(format);
+    }
+    if let Some(query) = args.query {
+        json_query::set_query(query);
+    }
     match args.entity_type {
-        cli::main_cli::EntityType::Get(get_command) => match get_command.command {
-            cli::main_cli::GetSubCommand::ApiProduct(apiproduct) => {
-                // let api_product = APIProduct::new();
-                let ap = GetAPIProductRequest::new(apiproduct);
-                let mut filter = utils::konnect_utils::FetchFilter::None;
-
-                let name = &ap.name;
-                let id = &ap.id;
-                let mut path = "/api-products".to_string();
-                if !&name.is_empty() {
-                    let ap = GetAPIProductsArrayRequest {};
-                    filter = FetchFilter::FieldName("name".to_string(), name.to_string());
-                    // handle_fetch(&ap, &path, filter);
-                    handle_request(&ap, &path, Operation::Fetch(filter));
-                } else if !id.is_empty() {
-                    path = format!("{}/{}", path, id);
-                    handle_request(&ap, &path, Operation::Fetch(filter));
-                } else {
-                    let ap = GetAPIProductsArrayRequest {};
-                    handle_request(&ap, &path, Operation::Fetch(filter));
-                }
-                info!("path: {}", &path);
-            }
-            main_cli::GetSubCommand::ApiProductVersionSpecification(get_spec) => {
-                let id = &get_spec.id;
-                let pid = &get_spec.product_id;
-                let vid = &get_spec.product_version_id;
-                match id {
-                    Some(i) => {
-                        let spv = GetAPIProductVersionSpecRequest::new();
-
-                        let path = format!(
-                            "/api-products/{}/product-versions/{}/specifications/{}",
-                            pid, vid, i
-                        );
-                        handle_request(
-                            &spv,
-                            &path,
-                            Operation::Fetch(utils::konnect_utils::FetchFilter::None),
-                        );
+        cli::main_cli::EntityType::Get(get_command) => {
+            let view = GetView::new(&get_command).unwrap_or_else(|e| exit_with_error(e));
+            match get_command.command {
+                cli::main_cli::GetSubCommand::ApiProduct(mut apiproduct) => {
+                    if let Some(id) = apiproduct.id.as_mut() {
+                        resolve_ids(id, None, None);
                     }
-                    None => {
-                        let spv = GetAllAPIProductVersionSpecRequest::new();
-                        let path = format!(
-                            "/api-products/{}/product-versions/{}/specifications",
-                            pid, vid
-                        );
-                        handle_request(
-                            &spv,
-                            &path,
-                            Operation::Fetch(utils::konnect_utils::FetchFilter::None),
-                        );
+                    if let Some(selector) = &apiproduct.selector {
+                        print_fetched(&view, || {
+                            api::api_products::fetch_products_by_selector(selector)
+                        });
+                        return;
+                    }
+                    // let api_product = APIProduct::new();
+                    let ap = GetAPIProductRequest::new(apiproduct);
+                    let mut filter = utils::konnect_utils::FetchFilter::None;
+
+                    let name = &ap.name;
+                    let id = &ap.id;
+                    let mut path = "/api-products".to_string();
+                    if !&name.is_empty() {
+                        let ap = GetAPIProductsArrayRequest {};
+                        filter = FetchFilter::FieldName("name".to_string(), name.to_string());
+                        handle_fetch(&ap, &path, filter, &view);
+                    } else if !id.is_empty() {
+                        path = format!("{}/{}", path, id);
+                        handle_fetch(&ap, &path, filter, &view);
+                    } else {
+                        let ap = GetAPIProductsArrayRequest {};
+                        handle_fetch(&ap, &path, filter, &view);
                     }
+                    info!("path: {}", &path);
                 }
-            }
-            main_cli::GetSubCommand::ApiProductVersion(get_version) => {
-                let id = &get_version.id;
-                let pid = &get_version.product_id;
-                match id {
-                    Some(vid) => {
-                        let pv = api::api_product_versions::GetApiProductVersionRequest::new();
-                        let path = format!("/api-products/{}/product-versions/{}", pid, vid);
-                        handle_request(
-                            &pv,
-                            &path,
-                            Operation::Fetch(utils::konnect_utils::FetchFilter::None),
-                        )
+                main_cli::GetSubCommand::ApiProductVersionSpecification(mut get_spec) => {
+                    resolve_ids(
+                        &mut get_spec.product_id,
+                        Some(&mut get_spec.product_version_id),
+                        get_spec.id.as_mut(),
+                    );
+                    let id = &get_spec.id;
+                    let pid = &get_spec.product_id;
+                    let vid = &get_spec.product_version_id;
+                    match id {
+                        Some(i) => {
+                            let spv = GetAPIProductVersionSpecRequest::new();
+
+                            let path = format!(
+                                "/api-products/{}/product-versions/{}/specifications/{}",
+                                pid, vid, i
+                            );
+                            handle_fetch(
+                                &spv,
+                                &path,
+                                utils::konnect_utils::FetchFilter::None,
+                                &view,
+                            );
+                        }
+                        None => {
+                            let spv = GetAllAPIProductVersionSpecRequest::new();
+                            let path = format!(
+                                "/api-products/{}/product-versions/{}/specifications",
+                                pid, vid
+                            );
+                            handle_fetch(
+                                &spv,
+                                &path,
+                                utils::konnect_utils::FetchFilter::None,
+                                &view,
+                            );
+                        }
                     }
-                    None => {
-                        let pv = api::api_product_versions::GetAllApiProductVersionRequest::new();
-                        let path = format!("/api-products/{}/product-versions", pid);
-                        handle_request(
-                            &pv,
-                            &path,
-                            Operation::Fetch(utils::konnect_utils::FetchFilter::None),
-                        )
+                }
+                main_cli::GetSubCommand::ApiProductVersion(mut get_version) => {
+                    resolve_ids(&mut get_version.product_id, get_version.id.as_mut(), None);
+                    let id = &get_version.id;
+                    let pid = &get_version.product_id;
+                    if let Some(selector) = &get_version.selector {
+                        print_fetched(&view, || {
+                            api::api_product_versions::fetch_versions_by_selector(pid, selector)
+                        });
+                        return;
+                    }
+                    match id {
+                        Some(vid) => {
+                            let pv = api::api_product_versions::GetApiProductVersionRequest::new();
+                            let path = format!("/api-products/{}/product-versions/{}", pid, vid);
+                            handle_fetch(&pv, &path, utils::konnect_utils::FetchFilter::None, &view)
+                        }
+                        None => {
+                            let pv =
+                                api::api_product_versions::GetAllApiProductVersionRequest::new();
+                            let path = format!("/api-products/{}/product-versions", pid);
+                            handle_fetch(&pv, &path, utils::konnect_utils::FetchFilter::None, &view)
+                        }
                     }
                 }
             }
-        },
+        }
         cli::main_cli::EntityType::Create(create_command) => {
             match create_command.command {
                 cli::main_cli::CreateSubCommand::ApiProduct(apiproduct) => {
                     /*                     println!("apiproduc: {:?}", apiproduct); */
+                    let body = apiproduct.filename.as_deref().map(read_body);
                     let apr = api::api_products::APIProductRequest::new(apiproduct);
-                    handle_request(&apr, "/api-products", Operation::Create);
+                    let operation = match body {
+                        Some(body) => {
+                            let flags = konnect_utils::filter_empty_fields(
+                                serde_json::to_value(&apr).unwrap(),
+                            );
+                            Operation::CreateWith(merge_json(body, flags))
+                        }
+                        None => Operation::Create,
+                    };
+                    handle_request(&apr, "/api-products", operation);
                 }
-                cli::main_cli::CreateSubCommand::ApiProductVersion(api_product_version) => {
+                cli::main_cli::CreateSubCommand::ApiProductVersion(mut api_product_version) => {
+                    // The body is read before names are resolved so a bad file fails fast
+                    let body = api_product_version.filename.as_deref().map(read_body);
+                    resolve_ids(&mut api_product_version.product_id, None, None);
                     let pid = &api_product_version.product_id;
                     let path = format!("/api-products/{}/product-versions", pid);
+                    let operation = match body {
+                        Some(body) => Operation::CreateWith(merge_json(
+                            body,
+                            api::api_product_versions::APIProductVersionRequest::flags(
+                                &api_product_version,
+                            ),
+                        )),
+                        None => Operation::Create,
+                    };
                     let apvr = api::api_product_versions::APIProductVersionRequest::new(
                         api_product_version,
                     );
-                    handle_request(&apvr, &path, Operation::Create);
+                    handle_request(&apvr, &path, operation);
                 }
                 main_cli::CreateSubCommand::ApiProductVersionSpecification(spec_version) => {
-                    let pid = &spec_version.product_id;
-                    let vid = &spec_version.product_version_id;
+                    let mut pid = spec_version.product_id.clone();
+                    let mut vid = spec_version.product_version_id.clone();
+                    // The spec file is read before names are resolved so a bad file fails fast
+                    let svr =
+                        api::api_product_version_spec::CreateAPIProductVersionSpecRequest::new(
+                            spec_version,
+                        )
+                        .unwrap_or_else(|e| exit_with_error(e));
+                    resolve_ids(&mut pid, Some(&mut vid), None);
                     let path = format!(
                         "/api-products/{}/product-versions/{}/specifications",
                         pid, vid
                     );
-                    let svr =
-                        api::api_product_version_spec::CreateAPIProductVersionSpecRequest::new(
-                            spec_version,
-                        );
                     handle_request(&svr, &path, Operation::Create);
                 }
             }
@@ -182,11 +401,37 @@ fn main() {
         cli::main_cli::EntityType::Delete(delete_product_command) => {
             match delete_product_command.command {
                 main_cli::DeleteSubCommand::ApiProduct(delprodcli) => {
-                    let dpr = DeleteAPIProductRequest::new(delprodcli);
-                    let path = format!("/api-products/{}", dpr.id);
-                    handle_request(&dpr, &path, Operation::Delete);
+                    if let Err(e) = api::api_product_delete::delete_products(&delprodcli) {
+                        exit_with_error(e);
+                    }
                 }
-                main_cli::DeleteSubCommand::ApiProductVersion(depprodvercli) => {
+                main_cli::DeleteSubCommand::ApiProductVersion(mut depprodvercli) => {
+                    resolve_ids(
+                        &mut depprodvercli.product_id,
+                        depprodvercli.id.as_mut(),
+                        None,
+                    );
+                    if let Some(selector) = &depprodvercli.selector {
+                        let pid = &depprodvercli.product_id;
+                        let versions =
+                            api::api_product_versions::fetch_versions_by_selector(pid, selector)
+                                .unwrap_or_else(|e| exit_with_error(e));
+                        let targets = versions.data.iter().map(|v| {
+                            (
+                                v.name.clone(),
+                                format!("/api-products/{}/product-versions/{}", pid, v.id),
+                            )
+                        });
+                        let deleted = api::api_product_delete::delete_selected(
+                            "API product versions",
+                            selector,
+                            targets.collect(),
+                        );
+                        if let Err(e) = deleted {
+                            exit_with_error(e);
+                        }
+                        return;
+                    }
                     let dprv = DeleteAPIProductVersionRequest::new(depprodvercli);
                     let path = format!(
                         "/api-products/{}/product-versions/{}",
@@ -194,7 +439,12 @@ fn main() {
                     );
                     handle_request(&dprv, &path, Operation::Delete);
                 }
-                main_cli::DeleteSubCommand::ApiProductVersionSpecification(speccli) => {
+                main_cli::DeleteSubCommand::ApiProductVersionSpecification(mut speccli) => {
+                    resolve_ids(
+                        &mut speccli.product_id,
+                        Some(&mut speccli.product_version_id),
+                        Some(&mut speccli.id),
+                    );
                     let dprvs = DeleteAPIProductVersionSpecRequest::new(&speccli);
                     let path = format!(
                         "/api-products/{}/product-versions/{}/specifications/{}",
@@ -205,16 +455,47 @@ fn main() {
             }
         }
         cli::main_cli::EntityType::Patch(patch_command) => match patch_command.command {
-            main_cli::PatchSubCommand::ApiProduct(patchproduct) => {
+            main_cli::PatchSubCommand::ApiProduct(mut patchproduct) => {
+                let body = patchproduct.filename.as_deref().map(read_body);
+                resolve_ids(&mut patchproduct.id, None, None);
                 let ppr = PatchAPIProductRequest::new(&patchproduct);
-                let s_json =
+                let mut s_json =
                     konnect_utils::filter_empty_fields(serde_json::to_value(&ppr).unwrap());
+                if let Some(body) = body {
+                    s_json = merge_json(body, s_json);
+                }
                 let path = format!("/api-products/{}", &patchproduct.id);
                 // let response = ppr.patch(&path, s_json);
                 handle_request(&ppr, &path, Operation::Patch(s_json));
             }
-            main_cli::PatchSubCommand::ApiProductVersionSpecification(patchspec) => {
-                let spr = PatchAPIProductVersionSpecRequest::new(&patchspec);
+            main_cli::PatchSubCommand::ApiProductVersion(mut patchversion) => {
+                let body = patchversion.filename.as_deref().map(read_body);
+                resolve_ids(
+                    &mut patchversion.product_id,
+                    Some(&mut patchversion.id),
+                    None,
+                );
+                let pvr =
+                    api::api_product_versions::PatchAPIProductVersionRequest::new(&patchversion);
+                let mut s_json =
+                    konnect_utils::filter_empty_fields(serde_json::to_value(&pvr).unwrap());
+                if let Some(body) = body {
+                    s_json = merge_json(body, s_json);
+                }
+                let path = format!(
+                    "/api-products/{}/product-versions/{}",
+                    &patchversion.product_id, &patchversion.id
+                );
+                handle_request(&pvr, &path, Operation::Patch(s_json));
+            }
+            main_cli::PatchSubCommand::ApiProductVersionSpecification(mut patchspec) => {
+                let spr = PatchAPIProductVersionSpecRequest::new(&patchspec)
+                    .unwrap_or_else(|e| exit_with_error(e));
+                resolve_ids(
+                    &mut patchspec.product_id,
+                    Some(&mut patchspec.product_version_id),
+                    Some(&mut patchspec.id),
+                );
                 let sj = serde_json::to_value(&spr).unwrap();
                 let pid = &patchspec.product_id;
                 let vid = &patchspec.product_version_id;
@@ -226,5 +507,243 @@ fn main() {
                 handle_request(&spr, &path, Operation::Patch(sj));
             }
         },
+        cli::main_cli::EntityType::Edit(edit_command) => match edit_command.command {
+            main_cli::EditSubCommand::ApiProduct(edit_product_cli) => {
+                if let Err(e) = api::api_product_edit::edit_product(&edit_product_cli) {
+                    exit_with_error(e);
+                }
+            }
+        },
+        cli::main_cli::EntityType::Validate(validate_command) => match validate_command.command {
+            main_cli::ValidateSubCommand::Spec(validate_spec) => {
+                let path = &validate_spec.path_to_spec_file;
+                match spec_validator::validate_spec_file(path) {
+                    Ok(spec) => println!("{} is a valid {} document", path, spec.kind),
+                    Err(err) => exit_with_error(err),
+                }
+            }
+        },
+        cli::main_cli::EntityType::Diff(diff_command) => match diff_command.command {
+            None => {
+                let diff_cli = diff_command.manifests;
+                if diff_cli.no_color || !std::io::stdout().is_terminal() {
+                    colored::control::set_override(false);
+                }
+                let manifests = load_manifests(&diff_cli.source.manifests);
+                let mut live =
+                    LiveState::from_source(&diff_cli.source).unwrap_or_else(|e| exit_with_error(e));
+                let mut plan = manifest_plan::plan_changes(&manifests, &mut live)
+                    .unwrap_or_else(|e| exit_with_error(e));
+                if let Some(selector) = &diff_cli.selector {
+                    manifest_prune::plan_prune(&mut plan, selector, &mut live)
+                        .unwrap_or_else(|e| exit_with_error(e));
+                }
+                println!("{}", manifest_diff::render_plan(&plan, diff_cli.output));
+                if manifest_diff::has_differences(&plan) {
+                    std::process::exit(1);
+                }
+            }
+            Some(main_cli::DiffSubCommand::ApiProductVersionSpecification(mut diff_spec)) => {
+                if let Some(pid) = diff_spec.product_id.as_mut() {
+                    resolve_ids(
+                        pid,
+                        diff_spec.product_version_id.as_mut(),
+                        diff_spec.id.as_mut(),
+                    );
+                    resolve_ids(
+                        pid,
+                        diff_spec.against_version_id.as_mut(),
+                        diff_spec.against_id.as_mut(),
+                    );
+                }
+                let base = load_spec_for_diff(
+                    &diff_spec.base_file,
+                    &diff_spec.product_id,
+                    &diff_spec.product_version_id,
+                    &diff_spec.id,
+                );
+                let revision = load_spec_for_diff(
+                    &diff_spec.path_to_spec_file,
+                    &diff_spec.product_id,
+                    &diff_spec.against_version_id,
+                    &diff_spec.against_id,
+                );
+                let report = spec_diff::diff_specs(&base, &revision);
+                match diff_spec.output {
+                    cli::spec_cli::DiffOutput::Text => println!("{}", report),
+                    cli::spec_cli::DiffOutput::Json => print_json(&report),
+                }
+                if report.has_breaking_changes() {
+                    std::process::exit(1);
+                }
+            }
+        },
+        cli::main_cli::EntityType::Generate(generate_command) => match generate_command.command {
+            main_cli::GenerateSubCommand::GatewayConfig(mut gen) => {
+                if let Some(pid) = gen.product_id.as_mut() {
+                    resolve_ids(pid, gen.product_version_id.as_mut(), None);
+                }
+                let spec = spec_validator::validate_spec_file(&gen.from_spec)
+                    .unwrap_or_else(|e| exit_with_error(e));
+                let options = gateway_config::GenerateOptions {
+                    service_name: gen.service_name.clone(),
+                    upstream_url: gen.upstream_url.clone(),
+                    with_plugins: gen.with_plugins,
+                };
+                let config = gateway_config::generate_gateway_config(&spec.document, &options)
+                    .unwrap_or_else(|e| exit_with_error(e));
+
+                match &gen.control_plane_id {
+                    None => {
+                        let yaml =
+                            serde_yaml::to_string(&config).unwrap_or_else(|e| exit_with_error(e));
+                        match &gen.output_file {
+                            Some(file) => {
+                                std::fs::write(file, yaml).unwrap_or_else(|e| exit_with_error(e))
+                            }
+                            None => print!("{}", yaml),
+                        }
+                    }
+                    // Routes and plugins need the id of the service, a dry run prints a plan
+                    Some(cpid) if konnect_utils::dry_run().is_some() => {
+                        print!(
+                            "{}",
+                            api::gateway_services::describe_service_config(
+                                cpid,
+                                &config.services[0]
+                            )
+                        );
+                        if let Some(vid) = &gen.product_version_id {
+                            println!("and linked to API product version {}", vid);
+                        }
+                    }
+                    Some(cpid) => {
+                        let applied =
+                            api::gateway_services::apply_service_config(cpid, &config.services[0])
+                                .unwrap_or_else(|e| exit_with_error(e));
+                        print_json(&applied);
+                        if let (Some(pid), Some(vid)) = (&gen.product_id, &gen.product_version_id) {
+                            let link =
+                                api::api_product_versions::PatchAPIProductVersionRequest::link_gateway_service(
+                                    cpid,
+                                    &applied.service.id,
+                                );
+                            let body = konnect_utils::filter_empty_fields(
+                                serde_json::to_value(&link).unwrap(),
+                            );
+                            let path = format!("/api-products/{}/product-versions/{}", pid, vid);
+                            let version = link.patch(&path, body).unwrap_or_else(|e| {
+                                exit_with_error(format!(
+                                    "Gateway service {} was created but linking it to version {} failed: {}",
+                                    applied.service.id, vid, e
+                                ))
+                            });
+                            print_json(&version);
+                        }
+                    }
+                }
+            }
+        },
+        cli::main_cli::EntityType::Bundle(bundle_command) => match bundle_command.command {
+            main_cli::BundleSubCommand::Spec(bundle_spec) => {
+                let path = &bundle_spec.path_to_spec_file;
+                let content =
+                    spec_validator::read_spec_file(path).unwrap_or_else(|e| exit_with_error(e));
+                let bundled = match spec_bundler::bundle_spec(path, &content) {
+                    Ok(Some(b)) => b.content,
+                    Ok(None) => content,
+                    Err(e) => exit_with_error(e),
+                };
+                match &bundle_spec.output_file {
+                    Some(file) => {
+                        std::fs::write(file, bundled).unwrap_or_else(|e| exit_with_error(e))
+                    }
+                    None => print!("{}", bundled),
+                }
+            }
+        },
+        cli::main_cli::EntityType::Describe(describe_command) => match describe_command.command {
+            main_cli::DescribeSubCommand::ApiProduct(describe_product) => {
+                let description =
+                    api::api_products::resolve_api_product_id(&describe_product.id_or_name)
+                        .and_then(|id| api::api_product_describe::describe_api_product(&id))
+                        .unwrap_or_else(|e| exit_with_error(e));
+                print!("{}", description);
+            }
+        },
+        cli::main_cli::EntityType::Clone(clone_command) => match clone_command.command {
+            main_cli::CloneSubCommand::ApiProduct(clone_product) => {
+                let source_id =
+                    api::api_products::resolve_api_product_id(&clone_product.id_or_name)
+                        .unwrap_or_else(|e| exit_with_error(e));
+                let options = api::api_product_clone::CloneOptions {
+                    name: clone_product.name,
+                    with_gateway_service: !clone_product.skip_gateway_service,
+                    with_documents: !clone_product.skip_documents,
+                };
+                // Each object of a clone needs the id of its parent, a dry run prints a plan
+                let cloned = if konnect_utils::dry_run().is_some() {
+                    api::api_product_clone::describe_clone(&source_id, &options)
+                        .map(|plan| print!("{}", plan))
+                } else {
+                    api::api_product_clone::clone_api_product(&source_id, &options)
+                        .map(|report| print_json(&report))
+                };
+                if let Err(e) = cloned {
+                    exit_with_error(e);
+                }
+            }
+        },
+        cli::main_cli::EntityType::Apply(apply_cli) => {
+            let manifests = load_manifests(&apply_cli.source.manifests);
+            if let Err(e) = manifest_apply::apply_manifests(&manifests, &apply_cli) {
+                exit_with_error(e);
+            }
+        }
+        cli::main_cli::EntityType::State(state_command) => {
+            if let Err(e) = manifest_state::run_state_command(state_command.command) {
+                exit_with_error(e);
+            }
+        }
+        cli::main_cli::EntityType::Drift(drift_cli) => {
+            let manifests = load_manifests(&drift_cli.source.manifests);
+            let mut live =
+                LiveState::from_source(&drift_cli.source).unwrap_or_else(|e| exit_with_error(e));
+            let mut plan = manifest_plan::plan_changes(&manifests, &mut live)
+                .unwrap_or_else(|e| exit_with_error(e));
+            manifest_prune::plan_prune(&mut plan, &drift_cli.selector, &mut live)
+                .unwrap_or_else(|e| exit_with_error(e));
+            let report = manifest_drift::drift_report(&plan);
+            match drift_cli.format {
+                cli::manifest_cli::DriftFormat::Json => print_json(&report),
+                cli::manifest_cli::DriftFormat::Junit => print!("{}", report.to_junit()),
+            }
+            if report.has_drift() {
+                std::process::exit(1);
+            }
+        }
+        cli::main_cli::EntityType::Render(render_cli) => {
+            let mut manifests = load_manifests(&render_cli.manifests);
+            match manifest_template::render_manifests(&mut manifests) {
+                Ok(rendered) => print!("{}", rendered),
+                Err(e) => exit_with_error(e),
+            }
+        }
+        cli::main_cli::EntityType::Export(export_cli) => {
+            match manifest_export::export_catalog(&export_cli.dir) {
+                Ok(report) => println!("{}", report),
+                Err(e) => exit_with_error(e),
+            }
+        }
+        cli::main_cli::EntityType::Completion(completion_cli) => {
+            let mut command = main_cli::KonnectCLIArgs::command();
+            print!(
+                "{}",
+                completion::completion_script(&mut command, completion_cli.shell.into())
+            );
+        }
+        cli::main_cli::EntityType::Complete(complete_cli) => {
+            completion::complete_values(&complete_cli.words)
+        }
     }
 }
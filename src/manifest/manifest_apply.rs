@@ -0,0 +1,375 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    io::{self, IsTerminal},
+    thread,
+};
+
+use log::{error, info};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    api::{
+        api_product_documents::{CreateAPIProductDocumentRequest, PatchAPIProductDocumentRequest},
+        api_product_version_spec::{
+            CreateAPIProductVersionSpecRequest, PatchAPIProductVersionSpecRequest,
+        },
+        api_product_versions::{APIProductVersionRequest, PatchAPIProductVersionRequest},
+        api_products::{APIProductRequest, PatchAPIProductRequest},
+        api_utils::{self, string_to_base64, Executable},
+    },
+    cli::{
+        cli_utils::confirm,
+        manifest_cli::{ApplyManifests, ManifestDiffFormat},
+    },
+    utils::konnect_utils::{dry_run, KonnectClient},
+};
+
+use super::{
+    manifest_diff::{render_deletions, render_plan},
+    manifest_live::LiveState,
+    manifest_plan::{plan_changes, Action, Change, Plan},
+    manifest_prune::plan_prune,
+    manifest_state::State,
+    manifest_types::{Kind, Manifest, ResourceKey},
+};
+
+#[derive(Debug, Default, Serialize)]
+pub struct ApplyReport {
+    pub created: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub deleted: usize,
+    pub failed: usize,
+    /// Changes not attempted because an earlier one failed
+    pub skipped: usize,
+    /// Objects created by this run and deleted again after a failure
+    pub rolled_back: usize,
+}
+
+impl ApplyReport {
+    pub fn is_success(&self) -> bool {
+        self.failed == 0
+    }
+}
+
+impl fmt::Display for ApplyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_success() {
+            return write!(
+                f,
+                "Apply complete: {} created, {} updated, {} unchanged, {} deleted",
+                self.created, self.updated, self.unchanged, self.deleted
+            );
+        }
+        write!(
+            f,
+            "Apply failed: {} created, {} updated, {} unchanged, {} deleted, {} failed, {} not attempted, {} created objects rolled back",
+            self.created,
+            self.updated,
+            self.unchanged,
+            self.deleted,
+            self.failed,
+            self.skipped,
+            self.rolled_back
+        )
+    }
+}
+
+/// Plans and applies `manifests`. The state file, when used, stays locked until this returns.
+pub fn apply_manifests(
+    manifests: &[Manifest],
+    apply_cli: &ApplyManifests,
+) -> Result<(), Box<dyn Error>> {
+    // A dry run does not change Konnect, so it neither locks nor saves the state
+    let dry_run = dry_run().is_some();
+    let mut state = match &apply_cli.source.state {
+        Some(path) if dry_run => Some(State::load(path)?),
+        Some(path) => Some(State::lock(path)?),
+        None => None,
+    };
+    let mut live = match &state {
+        Some(state) => LiveState::with_ids(state.ids().clone()),
+        None => LiveState::new(),
+    };
+    let mut plan = plan_changes(manifests, &mut live)?;
+    if let Some(selector) = &apply_cli.selector {
+        plan_prune(&mut plan, selector, &mut live)?;
+    }
+    // A dry run prints the plan, as diff does, the writes of apply depend on each other's ids
+    if dry_run {
+        if !io::stdout().is_terminal() {
+            colored::control::set_override(false);
+        }
+        let format = ManifestDiffFormat::Fields;
+        println!("{}", render_plan(&plan, format));
+        return Ok(());
+    }
+    let deletions = render_deletions(&plan);
+    if !deletions.is_empty() {
+        if !io::stdout().is_terminal() {
+            colored::control::set_override(false);
+        }
+        println!("The following objects are not declared and will be deleted:");
+        print!("{}", deletions);
+        if !apply_cli.yes && !confirm("Delete them?") {
+            return Err(
+                "Apply aborted, nothing was changed. Pass --yes to delete without confirmation"
+                    .into(),
+            );
+        }
+    }
+    let result = apply_changes(&plan, state.as_mut(), apply_cli.concurrency.into());
+    if let Some(state) = state.as_ref() {
+        state.save()?;
+    }
+    let report = result?;
+    if !report.is_success() {
+        return Err(report.to_string().into());
+    }
+    println!("{}", report);
+    Ok(())
+}
+
+/// Creates, patches and deletes objects as planned. Changes run wave by wave in dependency order
+/// (see `Plan::waves`), up to `concurrency` at a time within a wave. Unchanged resources are not
+/// written.
+///
+/// When a change fails, the wave it is in is finished, the later ones are skipped and the objects
+/// created by this run are deleted again, newest first. Updates and deletions are not undone.
+/// The ids of the objects found, created and deleted are recorded in `state` as they change.
+pub fn apply_changes(
+    plan: &Plan,
+    mut state: Option<&mut State>,
+    concurrency: usize,
+) -> Result<ApplyReport, Box<dyn Error>> {
+    let waves = plan.waves()?;
+    let mut ids = plan.ids.clone();
+    if let Some(state) = state.as_deref_mut() {
+        for (key, id) in &ids {
+            state.record(key, id);
+        }
+    }
+    let mut report = ApplyReport::default();
+    let mut created = Vec::new();
+    for (wave_number, wave) in waves.iter().enumerate() {
+        let mut results = Vec::new();
+        for batch in wave.chunks(concurrency.max(1)) {
+            let ids = &ids;
+            let batch_results: Vec<(usize, Result<Option<String>, String>)> =
+                thread::scope(|scope| {
+                    let handles: Vec<_> = batch
+                        .iter()
+                        .map(|&i| scope.spawn(move || (i, apply_change(&plan.changes[i], ids))))
+                        .collect();
+                    handles
+                        .into_iter()
+                        .map(|h| h.join().expect("an apply thread panicked"))
+                        .collect()
+                });
+            results.extend(batch_results);
+        }
+
+        for (i, result) in results {
+            let change = &plan.changes[i];
+            match (change.action, result) {
+                (_, Err(e)) => {
+                    report.failed += 1;
+                    error!("{}", e);
+                    println!("{} failed", change.key);
+                }
+                (Action::Unchanged, Ok(_)) => {
+                    report.unchanged += 1;
+                    println!("{} unchanged", change.key);
+                }
+                (Action::Create, Ok(id)) => {
+                    let id = id.unwrap_or_default();
+                    info!("Created {} with id {}", change.key, id);
+                    if let Some(state) = state.as_deref_mut() {
+                        state.record(&change.key, &id);
+                    }
+                    ids.insert(change.key.clone(), id);
+                    match object_path(&change.key, &ids) {
+                        Ok(path) => created.push((change.key.clone(), path)),
+                        Err(e) => error!("Unable to find {}, remove it by hand: {}", change.key, e),
+                    }
+                    report.created += 1;
+                    println!("{} created", change.key);
+                }
+                (Action::Update, Ok(_)) => {
+                    report.updated += 1;
+                    println!("{} configured", change.key);
+                }
+                (Action::Delete, Ok(_)) => {
+                    if let Some(state) = state.as_deref_mut() {
+                        state.forget(&change.key);
+                    }
+                    report.deleted += 1;
+                    println!("{} deleted", change.key);
+                }
+            }
+        }
+
+        if !report.is_success() {
+            report.skipped = waves[wave_number + 1..].iter().map(|w| w.len()).sum();
+            report.rolled_back = roll_back(&created, state.as_deref_mut());
+            break;
+        }
+    }
+    Ok(report)
+}
+
+/// Runs one change, returning the id of a created object.
+fn apply_change(
+    change: &Change,
+    ids: &HashMap<ResourceKey, String>,
+) -> Result<Option<String>, String> {
+    match change.action {
+        Action::Unchanged => Ok(None),
+        Action::Create => create(change, ids)
+            .map(Some)
+            .map_err(|e| format!("Unable to create {}: {}", change.key, e)),
+        Action::Update => update(change, ids)
+            .map(|_| None)
+            .map_err(|e| format!("Unable to update {}: {}", change.key, e)),
+        Action::Delete => object_path(&change.key, ids)
+            .and_then(|path| KonnectClient::new().delete(&path))
+            .map(|_| None)
+            .map_err(|e| format!("Unable to delete {}: {}", change.key, e)),
+    }
+}
+
+/// Deletes the objects of `created`, newest first, and returns how many were deleted.
+fn roll_back(created: &[(ResourceKey, String)], mut state: Option<&mut State>) -> usize {
+    if created.is_empty() {
+        return 0;
+    }
+    error!("Apply failed, deleting the objects created by this run");
+    let paths: Vec<String> = created.iter().map(|(_, path)| path.clone()).collect();
+    let deleted = api_utils::roll_back(&paths);
+    for (key, _) in created
+        .iter()
+        .rev()
+        .filter(|(_, path)| deleted.contains(path))
+    {
+        if let Some(state) = state.as_deref_mut() {
+            state.forget(key);
+        }
+        println!("{} rolled back", key);
+    }
+    deleted.len()
+}
+
+/// Path of the collection `key` lives in, built from the ids of its parents.
+pub fn collection_path(
+    key: &ResourceKey,
+    ids: &HashMap<ResourceKey, String>,
+) -> Result<String, Box<dyn Error>> {
+    let Some(parent) = key.parent() else {
+        return Ok("/api-products".to_string());
+    };
+    let parent_id = ids
+        .get(&parent)
+        .ok_or_else(|| format!("{} has no id yet", parent))?;
+    let parent_path = format!("{}/{}", collection_path(&parent, ids)?, parent_id);
+    Ok(match key.kind {
+        Kind::APIProductVersion => format!("{}/product-versions", parent_path),
+        Kind::APIProductDocument => format!("{}/documents", parent_path),
+        _ => format!("{}/specifications", parent_path),
+    })
+}
+
+/// Path of an existing object.
+fn object_path(
+    key: &ResourceKey,
+    ids: &HashMap<ResourceKey, String>,
+) -> Result<String, Box<dyn Error>> {
+    let id = ids.get(key).ok_or_else(|| format!("{} has no id", key))?;
+    Ok(format!("{}/{}", collection_path(key, ids)?, id))
+}
+
+fn create(change: &Change, ids: &HashMap<ResourceKey, String>) -> Result<String, Box<dyn Error>> {
+    let path = collection_path(&change.key, ids)?;
+    let desired = change.desired.clone();
+    Ok(match change.key.kind {
+        Kind::APIProduct => {
+            serde_json::from_value::<APIProductRequest>(desired)?
+                .create(&path)?
+                .id
+        }
+        Kind::APIProductVersion => {
+            serde_json::from_value::<APIProductVersionRequest>(desired)?
+                .create(&path)?
+                .id
+        }
+        Kind::APIProductVersionSpecification => {
+            let content = desired["content"].as_str().unwrap_or_default().to_string();
+            CreateAPIProductVersionSpecRequest::from_content(&change.key.name, content)
+                .create(&path)?
+                .id
+        }
+        Kind::APIProductDocument => {
+            let parent_document_id = parent_document_id(change, &desired["parent"], ids)?;
+            CreateAPIProductDocumentRequest {
+                parent_document_id: parent_document_id.as_str().map(|s| s.to_string()),
+                title: desired["title"].as_str().unwrap_or_default().to_string(),
+                slug: change.key.name.clone(),
+                status: desired["status"].as_str().unwrap_or_default().to_string(),
+                content: desired["content"].as_str().map(|s| s.to_string()),
+                metadata: None,
+            }
+            .create(&path)?
+            .id
+        }
+    })
+}
+
+/// Id of the document the `parent` slug names, null when there is no parent.
+fn parent_document_id(
+    change: &Change,
+    parent: &Value,
+    ids: &HashMap<ResourceKey, String>,
+) -> Result<Value, Box<dyn Error>> {
+    let Some(slug) = parent.as_str() else {
+        return Ok(Value::Null);
+    };
+    let key = ResourceKey::document(&change.key.product, slug);
+    ids.get(&key)
+        .map(|id| Value::String(id.clone()))
+        .ok_or_else(|| format!("parent document {} does not exist", slug).into())
+}
+
+fn update(change: &Change, ids: &HashMap<ResourceKey, String>) -> Result<(), Box<dyn Error>> {
+    let path = object_path(&change.key, ids)?;
+    let mut body = change.patch.clone();
+    info!(
+        "Patching {} with {:?}",
+        path,
+        body.keys().collect::<Vec<_>>()
+    );
+    match change.key.kind {
+        Kind::APIProduct => {
+            PatchAPIProductRequest::default().patch(&path, Value::Object(body))?;
+        }
+        Kind::APIProductVersion => {
+            PatchAPIProductVersionRequest::default().patch(&path, Value::Object(body))?;
+        }
+        Kind::APIProductVersionSpecification => {
+            if let Some(Value::String(content)) = body.get("content") {
+                let encoded = string_to_base64(content.clone());
+                body.insert("content".to_string(), Value::String(encoded));
+            }
+            PatchAPIProductVersionSpecRequest::default().patch(&path, Value::Object(body))?;
+        }
+        Kind::APIProductDocument => {
+            if let Some(parent) = body.remove("parent") {
+                let id = parent_document_id(change, &parent, ids)?;
+                body.insert("parent_document_id".to_string(), id);
+            }
+            PatchAPIProductDocumentRequest::default().patch(&path, Value::Object(body))?;
+        }
+    }
+    Ok(())
+}
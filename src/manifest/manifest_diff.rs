@@ -0,0 +1,158 @@
+use colored::Colorize;
+use serde_json::Value;
+use similar::TextDiff;
+
+use crate::cli::manifest_cli::ManifestDiffFormat;
+
+use super::manifest_plan::{Action, Change, Plan};
+
+/// Renders what applying `plan` would do: `+` for objects to create, `~` for objects to update and
+/// `-` for objects to delete.
+/// Unchanged objects are only counted in the summary.
+pub fn render_plan(plan: &Plan, format: ManifestDiffFormat) -> String {
+    let mut out = String::new();
+    for change in &plan.changes {
+        let body = match (change.action, format) {
+            (Action::Unchanged, _) => continue,
+            (Action::Create, _) => render_create(change),
+            (Action::Delete, _) => render_delete(change),
+            (Action::Update, ManifestDiffFormat::Unified) => render_unified(change),
+            (Action::Update, ManifestDiffFormat::Fields) => render_fields(change),
+        };
+        out.push_str(&header(change));
+        out.push_str(&body);
+        out.push('\n');
+    }
+    let count = |action: Action| plan.changes.iter().filter(|c| c.action == action).count();
+    out.push_str(&format!(
+        "Plan: {} to create, {} to update, {} to delete, {} unchanged",
+        count(Action::Create),
+        count(Action::Update),
+        count(Action::Delete),
+        count(Action::Unchanged)
+    ));
+    out
+}
+
+/// True when applying the plan would write anything.
+pub fn has_differences(plan: &Plan) -> bool {
+    plan.changes.iter().any(|c| c.action != Action::Unchanged)
+}
+
+fn header(change: &Change) -> String {
+    let line = match change.action {
+        Action::Create => format!("+ {} (create)", change.key).green(),
+        Action::Update => format!("~ {} (update)", change.key).yellow(),
+        Action::Delete => format!("- {} (delete)", change.key).red(),
+        Action::Unchanged => format!("  {} (unchanged)", change.key).normal(),
+    };
+    format!("{}\n", line.bold())
+}
+
+fn to_yaml(value: &Value) -> String {
+    serde_yaml::to_string(value).unwrap_or_else(|_| value.to_string())
+}
+
+fn render_create(change: &Change) -> String {
+    to_yaml(&change.desired)
+        .lines()
+        .map(|l| format!("{}\n", format!("+ {}", l).green()))
+        .collect()
+}
+
+fn render_delete(change: &Change) -> String {
+    let live = change
+        .live
+        .as_ref()
+        .map(|l| &l.state)
+        .unwrap_or(&Value::Null);
+    to_yaml(live)
+        .lines()
+        .map(|l| format!("{}\n", format!("- {}", l).red()))
+        .collect()
+}
+
+/// One line per object the plan deletes, shown before anything is deleted.
+pub fn render_deletions(plan: &Plan) -> String {
+    plan.changes
+        .iter()
+        .filter(|c| c.action == Action::Delete)
+        .map(|c| format!("{}\n", format!("- {}", c.key).red()))
+        .collect()
+}
+
+fn render_unified(change: &Change) -> String {
+    let live = change
+        .live
+        .as_ref()
+        .map(|l| to_yaml(&l.state))
+        .unwrap_or_default();
+    let desired = to_yaml(&change.desired);
+    let diff = TextDiff::from_lines(&live, &desired);
+    let unified = diff
+        .unified_diff()
+        .context_radius(3)
+        .header("live", "manifest")
+        .to_string();
+    unified
+        .lines()
+        .map(|l| {
+            let line = if l.starts_with("+++") || l.starts_with("---") {
+                l.bold()
+            } else if l.starts_with('+') {
+                l.green()
+            } else if l.starts_with('-') {
+                l.red()
+            } else if l.starts_with("@@") {
+                l.cyan()
+            } else {
+                l.normal()
+            };
+            format!("{}\n", line)
+        })
+        .collect()
+}
+
+fn render_fields(change: &Change) -> String {
+    let live = change.live.as_ref().map(|l| &l.state);
+    let mut out = String::new();
+    for (field, value) in &change.patch {
+        let current = live.and_then(|l| l.get(field)).unwrap_or(&Value::Null);
+        match (value, current) {
+            // The patch carries every label, only those that change are shown
+            (Value::Object(labels), _) if field == "labels" => {
+                for (name, label) in labels {
+                    let old = current.get(name).unwrap_or(&Value::Null);
+                    if old != label {
+                        out.push_str(&field_line(&format!("labels.{}", name), old, label));
+                    }
+                }
+            }
+            (Value::String(new), Value::String(old))
+                if new.contains('\n') || old.contains('\n') =>
+            {
+                out.push_str(&format!(
+                    "  {}: {} lines -> {} lines\n",
+                    field,
+                    old.lines().count(),
+                    new.lines().count()
+                ));
+            }
+            _ => out.push_str(&field_line(field, current, value)),
+        }
+    }
+    out
+}
+
+fn field_line(field: &str, old: &Value, new: &Value) -> String {
+    match (old, new) {
+        (Value::Null, _) => format!("  {}: {}\n", field, new.to_string().green()),
+        (_, Value::Null) => format!("  {}: {}\n", field, format!("{} (removed)", old).red()),
+        _ => format!(
+            "  {}: {} -> {}\n",
+            field,
+            old.to_string().red(),
+            new.to_string().green()
+        ),
+    }
+}
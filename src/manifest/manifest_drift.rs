@@ -0,0 +1,215 @@
+use std::fmt;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use super::{
+    manifest_plan::{Action, Change, Plan},
+    manifest_types::ResourceKey,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DriftStatus {
+    /// Konnect matches the manifest
+    InSync,
+    /// The object exists but differs from its manifest
+    Drifted,
+    /// The manifest declares an object that does not exist
+    Missing,
+    /// The object exists but no manifest declares it
+    Extra,
+}
+
+impl fmt::Display for DriftStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self {
+            DriftStatus::InSync => "in_sync",
+            DriftStatus::Drifted => "drifted",
+            DriftStatus::Missing => "missing",
+            DriftStatus::Extra => "extra",
+        };
+        write!(f, "{}", status)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct DriftedField {
+    pub field: String,
+    pub manifest: Value,
+    pub live: Value,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DriftEntry {
+    #[serde(flatten)]
+    pub key: ResourceKey,
+    pub status: DriftStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<DriftedField>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct DriftSummary {
+    pub in_sync: usize,
+    pub drifted: usize,
+    pub missing: usize,
+    pub extra: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DriftReport {
+    pub summary: DriftSummary,
+    pub objects: Vec<DriftEntry>,
+}
+
+/// Reads a plan as a drift report: updates are drifted objects, creations are missing ones and
+/// deletions (from pruning) are extra ones.
+pub fn drift_report(plan: &Plan) -> DriftReport {
+    let mut summary = DriftSummary::default();
+    let mut objects = Vec::new();
+    for change in &plan.changes {
+        let status = match change.action {
+            Action::Unchanged => DriftStatus::InSync,
+            Action::Update => DriftStatus::Drifted,
+            Action::Create => DriftStatus::Missing,
+            Action::Delete => DriftStatus::Extra,
+        };
+        match status {
+            DriftStatus::InSync => summary.in_sync += 1,
+            DriftStatus::Drifted => summary.drifted += 1,
+            DriftStatus::Missing => summary.missing += 1,
+            DriftStatus::Extra => summary.extra += 1,
+        }
+        objects.push(DriftEntry {
+            key: change.key.clone(),
+            status,
+            id: change.live.as_ref().map(|l| l.id.clone()),
+            fields: drifted_fields(change),
+        });
+    }
+    DriftReport { summary, objects }
+}
+
+/// Changed fields of an update, labels being reported one by one.
+fn drifted_fields(change: &Change) -> Vec<DriftedField> {
+    let live = change.live.as_ref().map(|l| &l.state);
+    let mut fields = Vec::new();
+    if change.action != Action::Update {
+        return fields;
+    }
+    for (field, value) in &change.patch {
+        let current = live.and_then(|l| l.get(field)).unwrap_or(&Value::Null);
+        match value {
+            Value::Object(labels) if field == "labels" => {
+                for (name, label) in labels {
+                    fields.push(DriftedField {
+                        field: format!("labels.{}", name),
+                        manifest: label.clone(),
+                        live: current.get(name).cloned().unwrap_or(Value::Null),
+                    });
+                }
+            }
+            _ => fields.push(DriftedField {
+                field: field.clone(),
+                manifest: value.clone(),
+                live: current.clone(),
+            }),
+        }
+    }
+    fields
+}
+
+impl DriftReport {
+    pub fn has_drift(&self) -> bool {
+        self.summary.drifted + self.summary.missing + self.summary.extra > 0
+    }
+
+    /// One test case per object, failing when the object drifted, is missing or is extra.
+    pub fn to_junit(&self) -> String {
+        let tests = self.objects.len();
+        let failures = tests - self.summary.in_sync;
+        let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        out.push_str(&format!(
+            "<testsuites name=\"konnectctl drift\" tests=\"{}\" failures=\"{}\">\n",
+            tests, failures
+        ));
+        out.push_str(&format!(
+            "  <testsuite name=\"konnectctl drift\" tests=\"{}\" failures=\"{}\">\n",
+            tests, failures
+        ));
+        for entry in &self.objects {
+            let testcase = format!(
+                "    <testcase classname=\"{}\" name=\"{}\"",
+                entry.key.kind,
+                xml_escape(&entry.key.path())
+            );
+            let (message, details) = match entry.status {
+                DriftStatus::InSync => {
+                    out.push_str(&testcase);
+                    out.push_str("/>\n");
+                    continue;
+                }
+                DriftStatus::Drifted => (
+                    format!(
+                        "differs from its manifest: {}",
+                        entry
+                            .fields
+                            .iter()
+                            .map(|f| f.field.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                    entry
+                        .fields
+                        .iter()
+                        .map(|f| {
+                            format!(
+                                "{}: {} -> {}\n",
+                                f.field,
+                                short(&f.live),
+                                short(&f.manifest)
+                            )
+                        })
+                        .collect::<String>(),
+                ),
+                DriftStatus::Missing => {
+                    ("is declared but does not exist".to_string(), String::new())
+                }
+                DriftStatus::Extra => (
+                    "exists but is not declared".to_string(),
+                    format!("id: {}\n", entry.id.as_deref().unwrap_or_default()),
+                ),
+            };
+            out.push_str(&testcase);
+            out.push_str(">\n");
+            out.push_str(&format!(
+                "      <failure type=\"{}\" message=\"{}\">{}</failure>\n",
+                entry.status,
+                xml_escape(&message),
+                xml_escape(&details)
+            ));
+            out.push_str("    </testcase>\n");
+        }
+        out.push_str("  </testsuite>\n</testsuites>\n");
+        out
+    }
+}
+
+/// A value on one line, multi-line text (spec content) being summarized.
+fn short(value: &Value) -> String {
+    match value {
+        Value::String(s) if s.contains('\n') => format!("{} lines", s.lines().count()),
+        _ => value.to_string(),
+    }
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
@@ -0,0 +1,228 @@
+use std::{
+    collections::HashSet,
+    error::Error,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use log::{info, warn};
+use serde_json::Value;
+
+use crate::utils::gateway_config::slugify;
+
+use super::{
+    manifest_live::{LiveObject, LiveState},
+    manifest_types::{
+        DocumentSpec, GatewayServiceRef, Manifest, Metadata, ProductSpec, SpecificationSpec,
+        VersionSpec,
+    },
+};
+
+#[derive(Debug, Default)]
+pub struct ExportReport {
+    pub dir: String,
+    pub products: usize,
+    pub versions: usize,
+    pub specifications: usize,
+    pub documents: usize,
+}
+
+impl fmt::Display for ExportReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Exported {} API products, {} versions, {} specifications and {} documents to {}",
+            self.products, self.versions, self.specifications, self.documents, self.dir
+        )
+    }
+}
+
+/// Writes one manifest per product, version, specification and document found in Konnect:
+///
+/// ```text
+/// <dir>/<product>/product.yaml
+/// <dir>/<product>/versions/<version>/version.yaml
+/// <dir>/<product>/versions/<version>/specifications/<spec>.yaml
+/// <dir>/<product>/versions/<version>/specifications/files/<spec file>
+/// <dir>/<product>/documents/<slug>.yaml
+/// <dir>/<product>/documents/files/<slug>.md
+/// ```
+///
+/// Ids are left out and parents are referenced by name, so the result can be applied as is.
+pub fn export_catalog(dir: &str) -> Result<ExportReport, Box<dyn Error>> {
+    let root = Path::new(dir);
+    if root.exists() && fs::read_dir(root)?.next().is_some() {
+        return Err(format!("{} is not empty, export into a new directory", dir).into());
+    }
+
+    let mut live = LiveState::new();
+    let mut report = ExportReport {
+        dir: dir.to_string(),
+        ..ExportReport::default()
+    };
+    let mut names = HashSet::new();
+    let mut dirs = HashSet::new();
+    for (name, product) in live.products()? {
+        if !names.insert(name.clone()) {
+            warn!(
+                "Skipping API product {} ({}), another product has the same name",
+                name, product.id
+            );
+            continue;
+        }
+        let product_dir = root.join(unique_dir_name(&name, &mut dirs));
+        export_product(&mut live, &name, &product, &product_dir, &mut report)?;
+        report.products += 1;
+    }
+    Ok(report)
+}
+
+/// Slug of `name` for a file or directory, suffixed when already taken.
+fn unique_dir_name(name: &str, taken: &mut HashSet<String>) -> String {
+    let base = match slugify(name) {
+        s if s.is_empty() => "unnamed".to_string(),
+        s => s,
+    };
+    let mut candidate = base.clone();
+    let mut n = 2;
+    while !taken.insert(candidate.clone()) {
+        candidate = format!("{}-{}", base, n);
+        n += 1;
+    }
+    candidate
+}
+
+fn text(state: &Value, field: &str) -> String {
+    state[field].as_str().unwrap_or_default().to_string()
+}
+
+fn write_manifest(path: &Path, manifest: &Manifest) -> Result<(), Box<dyn Error>> {
+    write_file(path, &serde_yaml::to_string(manifest)?)
+}
+
+fn write_file(path: &Path, content: &str) -> Result<(), Box<dyn Error>> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, content).map_err(|e| format!("Unable to write {}: {}", path.display(), e))?;
+    info!("Wrote {}", path.display());
+    Ok(())
+}
+
+fn export_product(
+    live: &mut LiveState,
+    name: &str,
+    product: &LiveObject,
+    product_dir: &Path,
+    report: &mut ExportReport,
+) -> Result<(), Box<dyn Error>> {
+    let state = &product.state;
+    let manifest = Manifest::APIProduct {
+        metadata: Metadata {
+            name: name.to_string(),
+            product: None,
+            version: None,
+        },
+        spec: ProductSpec {
+            description: text(state, "description"),
+            labels: serde_json::from_value(state["labels"].clone())?,
+            portal_ids: serde_json::from_value(state["portal_ids"].clone())?,
+        },
+    };
+    write_manifest(&product_dir.join("product.yaml"), &manifest)?;
+
+    let versions = live.versions(&product.id)?.to_vec();
+    let mut version_dirs = HashSet::new();
+    for (version_name, version) in versions {
+        let version_dir = product_dir
+            .join("versions")
+            .join(unique_dir_name(&version_name, &mut version_dirs));
+        let gateway_service = match &version.state["gateway_service"] {
+            Value::Null => None,
+            gs => Some(serde_json::from_value::<GatewayServiceRef>(gs.clone())?),
+        };
+        let manifest = Manifest::APIProductVersion {
+            metadata: Metadata {
+                name: version_name.clone(),
+                product: Some(name.to_string()),
+                version: None,
+            },
+            spec: VersionSpec {
+                publish_status: text(&version.state, "publish_status"),
+                deprecated: version.state["deprecated"].as_bool().unwrap_or(false),
+                gateway_service,
+            },
+        };
+        write_manifest(&version_dir.join("version.yaml"), &manifest)?;
+        report.versions += 1;
+
+        let specs = live.specifications(&product.id, &version.id)?.to_vec();
+        let mut spec_files = HashSet::new();
+        let mut spec_manifests = HashSet::new();
+        for (spec_name, spec) in specs {
+            let file_name = spec_file_name(&spec_name, &mut spec_files);
+            let spec_dir = version_dir.join("specifications");
+            write_file(
+                &spec_dir.join("files").join(&file_name),
+                &text(&spec.state, "content"),
+            )?;
+            let manifest = Manifest::APIProductVersionSpecification {
+                metadata: Metadata {
+                    name: spec_name.clone(),
+                    product: Some(name.to_string()),
+                    version: Some(version_name.clone()),
+                },
+                spec: SpecificationSpec {
+                    file: Some(format!("./files/{}", file_name)),
+                    content: None,
+                },
+            };
+            let manifest_name = unique_dir_name(&spec_name, &mut spec_manifests);
+            write_manifest(&spec_dir.join(format!("{}.yaml", manifest_name)), &manifest)?;
+            report.specifications += 1;
+        }
+    }
+
+    let documents = live.documents(&product.id)?.to_vec();
+    let documents_dir = product_dir.join("documents");
+    for (slug, document) in documents {
+        let file_name = format!("{}.md", slug);
+        write_file(
+            &documents_dir.join("files").join(&file_name),
+            &text(&document.state, "content"),
+        )?;
+        let manifest = Manifest::APIProductDocument {
+            metadata: Metadata {
+                name: slug.clone(),
+                product: Some(name.to_string()),
+                version: None,
+            },
+            spec: DocumentSpec {
+                title: text(&document.state, "title"),
+                status: text(&document.state, "status"),
+                parent: document.state["parent"].as_str().map(|p| p.to_string()),
+                file: Some(format!("./files/{}", file_name)),
+                content: None,
+            },
+        };
+        write_manifest(&documents_dir.join(format!("{}.yaml", slug)), &manifest)?;
+        report.documents += 1;
+    }
+    Ok(())
+}
+
+/// File the content of a specification is written to, named after the specification.
+fn spec_file_name(name: &str, taken: &mut HashSet<String>) -> String {
+    let base = PathBuf::from(name)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .map(|n| n.to_string())
+        .unwrap_or_else(|| slugify(name));
+    let mut candidate = base.clone();
+    let mut n = 2;
+    while !taken.insert(candidate.clone()) {
+        candidate = format!("{}-{}", n, base);
+        n += 1;
+    }
+    candidate
+}
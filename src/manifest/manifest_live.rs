@@ -0,0 +1,278 @@
+use std::{collections::HashMap, error::Error};
+
+use log::info;
+use serde_json::Value;
+
+use crate::{
+    api::{
+        api_product_documents::{GetAPIProductDocumentRequest, GetAllAPIProductDocumentsRequest},
+        api_product_version_spec::GetAllAPIProductVersionSpecRequest,
+        api_product_versions::GetAllApiProductVersionRequest,
+        api_products::{GetAPIProductRequest, GetAPIProductsArrayRequest},
+        api_utils::{base64_to_string, Executable},
+    },
+    cli::manifest_cli::ManifestSource,
+    utils::konnect_utils::FetchFilter,
+};
+
+use super::{
+    manifest_state::State,
+    manifest_types::{normalize_live, Kind, ResourceKey},
+};
+
+/// An object as it currently exists in Konnect.
+#[derive(Debug, Clone)]
+pub struct LiveObject {
+    pub id: String,
+    /// Normalized state, see `normalize_live`
+    pub state: Value,
+}
+
+/// Looks objects up in Konnect by name. Lists are fetched once per parent and cached, so a run
+/// over many manifests only reads each collection once.
+///
+/// Objects with a known id (from the state file) are looked up by id, and by name only when that
+/// id no longer exists.
+#[derive(Default)]
+pub struct LiveState {
+    known_ids: HashMap<ResourceKey, String>,
+    products: HashMap<String, Option<LiveObject>>,
+    versions: HashMap<String, Vec<(String, LiveObject)>>,
+    specifications: HashMap<(String, String), Vec<(String, LiveObject)>>,
+    documents: HashMap<String, Vec<(String, LiveObject)>>,
+}
+
+impl LiveState {
+    pub fn new() -> Self {
+        LiveState::default()
+    }
+
+    pub fn with_ids(known_ids: HashMap<ResourceKey, String>) -> Self {
+        LiveState {
+            known_ids,
+            ..LiveState::default()
+        }
+    }
+
+    /// Live state looking objects up by the ids of the state file, if one is used.
+    pub fn from_source(source: &ManifestSource) -> Result<Self, Box<dyn Error>> {
+        Ok(match &source.state {
+            Some(path) => LiveState::with_ids(State::load(path)?.ids().clone()),
+            None => LiveState::new(),
+        })
+    }
+
+    /// Finds the object `key` names. `parent_ids` holds the ids of the parent product and
+    /// version, which are only needed for versions and specifications.
+    pub fn find(
+        &mut self,
+        key: &ResourceKey,
+        parent_ids: &[&str],
+    ) -> Result<Option<LiveObject>, Box<dyn Error>> {
+        let known_id = self.known_ids.get(key).cloned();
+        let objects = match (key.kind, parent_ids) {
+            (Kind::APIProduct, _) => {
+                if let Some(id) = &known_id {
+                    if let Some(found) = self.find_product_by_id(id) {
+                        return Ok(Some(found));
+                    }
+                }
+                return self.find_product(&key.name);
+            }
+            (Kind::APIProductVersion, [product_id, ..]) => self.versions(product_id)?,
+            (Kind::APIProductVersionSpecification, [product_id, version_id, ..]) => {
+                self.specifications(product_id, version_id)?
+            }
+            (Kind::APIProductDocument, [product_id, ..]) => self.documents(product_id)?,
+            _ => return Err(format!("Missing parent ids to look up {}", key).into()),
+        };
+        if let Some(id) = &known_id {
+            if let Some((_, found)) = objects.iter().find(|(_, o)| &o.id == id) {
+                return Ok(Some(found.clone()));
+            }
+            info!("{} no longer has id {}, looking it up by name", key, id);
+        }
+        Ok(find_named(objects, &key.name, key)?)
+    }
+
+    fn find_product_by_id(&self, id: &str) -> Option<LiveObject> {
+        let request = GetAPIProductRequest {
+            id: id.to_string(),
+            name: String::new(),
+        };
+        let product = match request.fetch(&format!("/api-products/{}", id), FetchFilter::None) {
+            Ok(product) => product,
+            Err(e) => {
+                info!(
+                    "API product {} not found ({}), looking it up by name",
+                    id, e
+                );
+                return None;
+            }
+        };
+        let state = normalize_live(Kind::APIProduct, &serde_json::to_value(&product).ok()?);
+        Some(LiveObject {
+            id: product.id,
+            state,
+        })
+    }
+
+    /// Every API product keyed by name.
+    pub fn products(&self) -> Result<Vec<(String, LiveObject)>, Box<dyn Error>> {
+        let products =
+            GetAPIProductsArrayRequest {}.fetch_all("/api-products", FetchFilter::None)?;
+        let mut objects = Vec::new();
+        for product in &products {
+            let object = LiveObject {
+                id: product.id.clone(),
+                state: normalize_live(Kind::APIProduct, &serde_json::to_value(product)?),
+            };
+            objects.push((product.name.clone(), object));
+        }
+        Ok(objects)
+    }
+
+    fn find_product(&mut self, name: &str) -> Result<Option<LiveObject>, Box<dyn Error>> {
+        if let Some(found) = self.products.get(name) {
+            return Ok(found.clone());
+        }
+        let products = GetAPIProductsArrayRequest {}.fetch_all(
+            "/api-products",
+            FetchFilter::FieldName("name".to_string(), name.to_string()),
+        )?;
+        let matches: Vec<_> = products.iter().filter(|p| p.name == name).collect();
+        let found = match matches.as_slice() {
+            [] => None,
+            [product] => Some(LiveObject {
+                id: product.id.clone(),
+                state: normalize_live(Kind::APIProduct, &serde_json::to_value(product)?),
+            }),
+            _ => {
+                return Err(format!(
+                    "{} API products are named {}, manifests need names to be unique",
+                    matches.len(),
+                    name
+                )
+                .into())
+            }
+        };
+        self.products.insert(name.to_string(), found.clone());
+        Ok(found)
+    }
+
+    /// Versions of a product keyed by name.
+    pub fn versions(
+        &mut self,
+        product_id: &str,
+    ) -> Result<&[(String, LiveObject)], Box<dyn Error>> {
+        if !self.versions.contains_key(product_id) {
+            let versions = GetAllApiProductVersionRequest::new().fetch_all(
+                &format!("/api-products/{}/product-versions", product_id),
+                FetchFilter::None,
+            )?;
+            let mut objects = Vec::new();
+            for version in versions {
+                let state =
+                    normalize_live(Kind::APIProductVersion, &serde_json::to_value(&version)?);
+                objects.push((
+                    version.name,
+                    LiveObject {
+                        id: version.id,
+                        state,
+                    },
+                ));
+            }
+            self.versions.insert(product_id.to_string(), objects);
+        }
+        Ok(&self.versions[product_id])
+    }
+
+    /// Specifications of a product version keyed by name, with their content decoded.
+    pub fn specifications(
+        &mut self,
+        product_id: &str,
+        version_id: &str,
+    ) -> Result<&[(String, LiveObject)], Box<dyn Error>> {
+        let cache_key = (product_id.to_string(), version_id.to_string());
+        if !self.specifications.contains_key(&cache_key) {
+            let specs = GetAllAPIProductVersionSpecRequest::new().fetch_all(
+                &format!(
+                    "/api-products/{}/product-versions/{}/specifications",
+                    product_id, version_id
+                ),
+                FetchFilter::None,
+            )?;
+            let mut objects = Vec::new();
+            for mut spec in specs {
+                spec.content = base64_to_string(&spec.content);
+                let state = normalize_live(
+                    Kind::APIProductVersionSpecification,
+                    &serde_json::to_value(&spec)?,
+                );
+                objects.push((spec.name, LiveObject { id: spec.id, state }));
+            }
+            self.specifications.insert(cache_key.clone(), objects);
+        }
+        Ok(&self.specifications[&cache_key])
+    }
+
+    /// Documents of a product keyed by slug. Parent documents are referenced by slug as well.
+    pub fn documents(
+        &mut self,
+        product_id: &str,
+    ) -> Result<&[(String, LiveObject)], Box<dyn Error>> {
+        if !self.documents.contains_key(product_id) {
+            let path = format!("/api-products/{}/documents", product_id);
+            let summaries =
+                GetAllAPIProductDocumentsRequest::new().fetch_all(&path, FetchFilter::None)?;
+            let slugs: HashMap<&str, &str> = summaries
+                .iter()
+                .map(|d| (d.id.as_str(), d.slug.as_str()))
+                .collect();
+            let mut objects = Vec::new();
+            for summary in &summaries {
+                let document = GetAPIProductDocumentRequest::new()
+                    .fetch(&format!("{}/{}", path, summary.id), FetchFilter::None)?;
+                let mut value = serde_json::to_value(&document)?;
+                let parent = document
+                    .parent_document_id
+                    .as_deref()
+                    .and_then(|id| slugs.get(id))
+                    .map(|slug| Value::String(slug.to_string()))
+                    .unwrap_or(Value::Null);
+                value["parent"] = parent;
+                let state = normalize_live(Kind::APIProductDocument, &value);
+                objects.push((
+                    document.slug,
+                    LiveObject {
+                        id: document.id,
+                        state,
+                    },
+                ));
+            }
+            self.documents.insert(product_id.to_string(), objects);
+        }
+        Ok(&self.documents[product_id])
+    }
+}
+
+fn find_named(
+    objects: &[(String, LiveObject)],
+    name: &str,
+    key: &ResourceKey,
+) -> Result<Option<LiveObject>, String> {
+    let matches: Vec<&LiveObject> = objects
+        .iter()
+        .filter(|(n, _)| n == name)
+        .map(|(_, o)| o)
+        .collect();
+    match matches.as_slice() {
+        [] => Ok(None),
+        [object] => Ok(Some((*object).clone())),
+        _ => Err(format!(
+            "{} objects match {}, manifests need names to be unique",
+            matches.len(),
+            key
+        )),
+    }
+}
@@ -0,0 +1,197 @@
+use std::{
+    collections::HashSet,
+    error::Error,
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use log::info;
+use serde::Deserialize;
+
+use crate::cli::manifest_cli::ManifestFiles;
+
+use super::{
+    manifest_overlay::{self, Overlay, OVERLAY_FILE},
+    manifest_template::Variables,
+    manifest_types::Manifest,
+};
+
+const MANIFEST_EXTENSIONS: [&str; 3] = ["yaml", "yml", "json"];
+
+/// Reads every manifest found in `paths`, `-` being stdin. Directories are walked recursively in
+/// name order and files may hold several YAML documents. Documents without a top-level `kind`
+/// (such as the spec files manifests point at) are skipped. Relative `spec.file` paths are
+/// resolved against the directory of the manifest declaring them.
+///
+/// `${NAME}` variables of files holding manifests are substituted from `variables` before
+/// parsing, and a directory holding an `overlay.yaml` is read as an overlay instead of being
+/// walked.
+pub fn load_manifests(
+    paths: &[String],
+    variables: &Variables,
+) -> Result<Vec<Manifest>, Box<dyn Error>> {
+    let mut manifests = Vec::new();
+    for path in paths {
+        if path == "-" {
+            let mut content = String::new();
+            io::stdin()
+                .read_to_string(&mut content)
+                .map_err(|e| format!("Unable to read manifests from stdin: {}", e))?;
+            let content = variables.substitute(&content, "stdin")?;
+            manifests.extend(parse_manifests(&content, Path::new("stdin"))?);
+        } else {
+            manifests.extend(load_path(Path::new(path), variables)?);
+        }
+    }
+    check_unique(&manifests)?;
+    if manifests.is_empty() {
+        return Err(format!("No manifests found in {}", paths.join(", ")).into());
+    }
+    Ok(manifests)
+}
+
+/// Reads the manifests given with -f, their variables being read from the --values files.
+pub fn load_manifest_files(manifests: &ManifestFiles) -> Result<Vec<Manifest>, Box<dyn Error>> {
+    let variables = Variables::from_files(&manifests.values)?;
+    load_manifests(&manifests.files, &variables)
+}
+
+fn check_unique(manifests: &[Manifest]) -> Result<(), Box<dyn Error>> {
+    let mut seen = HashSet::new();
+    for manifest in manifests {
+        let key = manifest.key();
+        if !seen.insert(key.clone()) {
+            return Err(format!("{} is declared more than once", key).into());
+        }
+    }
+    Ok(())
+}
+
+fn load_path(path: &Path, variables: &Variables) -> Result<Vec<Manifest>, Box<dyn Error>> {
+    let metadata =
+        fs::metadata(path).map_err(|e| format!("Unable to read {}: {}", path.display(), e))?;
+    if !metadata.is_dir() {
+        if path.file_name().is_some_and(|n| n == OVERLAY_FILE) {
+            return load_overlay(path, variables);
+        }
+        return parse_manifest_file(path, variables);
+    }
+    let overlay = path.join(OVERLAY_FILE);
+    if overlay.is_file() {
+        return load_overlay(&overlay, variables);
+    }
+
+    let mut entries: Vec<PathBuf> = fs::read_dir(path)
+        .map_err(|e| format!("Unable to read directory {}: {}", path.display(), e))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .collect();
+    entries.sort();
+    let mut manifests = Vec::new();
+    for entry in entries {
+        if entry.is_dir() {
+            manifests.extend(load_path(&entry, variables)?);
+        } else if entry
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| MANIFEST_EXTENSIONS.contains(&e))
+        {
+            manifests.extend(parse_manifest_file(&entry, variables)?);
+        }
+    }
+    Ok(manifests)
+}
+
+fn read_file(file: &Path, variables: &Variables) -> Result<String, Box<dyn Error>> {
+    let content = fs::read_to_string(file)
+        .map_err(|e| format!("Unable to read manifest {}: {}", file.display(), e))?;
+    variables.substitute(&content, &file.display().to_string())
+}
+
+/// Files without a manifest, such as the spec files exported next to the manifests, are skipped
+/// before variables are substituted: specs may hold `${...}` of their own.
+fn parse_manifest_file(
+    file: &Path,
+    variables: &Variables,
+) -> Result<Vec<Manifest>, Box<dyn Error>> {
+    let content = fs::read_to_string(file)
+        .map_err(|e| format!("Unable to read manifest {}: {}", file.display(), e))?;
+    if !has_kind(&content) {
+        info!("Skipping {}, it has no kind", file.display());
+        return Ok(Vec::new());
+    }
+    let content = variables.substitute(&content, &file.display().to_string())?;
+    parse_manifests(&content, file)
+}
+
+/// True when a document of `content` has a top-level `kind`, or when `content` cannot be parsed
+/// before its variables are substituted.
+fn has_kind(content: &str) -> bool {
+    serde_yaml::Deserializer::from_str(content).any(|document| {
+        serde_yaml::Value::deserialize(document).map_or(true, |v| v.get("kind").is_some())
+    })
+}
+
+/// Loads the resources of an overlay, relative to its directory, and patches them.
+fn load_overlay(file: &Path, variables: &Variables) -> Result<Vec<Manifest>, Box<dyn Error>> {
+    let overlay: Overlay = serde_yaml::from_str(&read_file(file, variables)?)
+        .map_err(|e| format!("{} is not a valid overlay: {}", file.display(), e))?;
+    if overlay.resources.is_empty() {
+        return Err(format!("{} has no resources", file.display()).into());
+    }
+    let dir = file.parent().unwrap_or(Path::new("."));
+
+    let mut manifests = Vec::new();
+    for resource in &overlay.resources {
+        manifests.extend(load_path(&dir.join(resource), variables)?);
+    }
+    check_unique(&manifests)?;
+
+    for patch_file in &overlay.patches {
+        let patch_file = dir.join(patch_file);
+        let content = read_file(&patch_file, variables)?;
+        for (index, document) in serde_yaml::Deserializer::from_str(&content).enumerate() {
+            let location = format!("{} (document {})", patch_file.display(), index + 1);
+            let value = serde_json::Value::deserialize(document)
+                .map_err(|e| format!("{} could not be parsed: {}", location, e))?;
+            if !value.is_null() {
+                manifest_overlay::apply_patch(&mut manifests, value, &patch_file, &location)?;
+            }
+        }
+    }
+    info!(
+        "Rendered {} manifests from overlay {}",
+        manifests.len(),
+        file.display()
+    );
+    Ok(manifests)
+}
+
+/// Parses the YAML (or JSON) documents of `content`, `source` being the file they come from.
+pub fn parse_manifests(content: &str, source: &Path) -> Result<Vec<Manifest>, Box<dyn Error>> {
+    let base_dir = source.parent().unwrap_or(Path::new("."));
+    let mut manifests = Vec::new();
+    for (index, document) in serde_yaml::Deserializer::from_str(content).enumerate() {
+        let location = format!("{} (document {})", source.display(), index + 1);
+        let value = serde_yaml::Value::deserialize(document)
+            .map_err(|e| format!("{} could not be parsed: {}", location, e))?;
+        if value.get("kind").is_none() {
+            if !value.is_null() {
+                info!("Skipping {}, it has no kind", location);
+            }
+            continue;
+        }
+        let mut manifest: Manifest = serde_yaml::from_value(value)
+            .map_err(|e| format!("{} is not a valid manifest: {}", location, e))?;
+        manifest
+            .validate()
+            .map_err(|e| format!("{} is not a valid manifest: {}", location, e))?;
+        if let Some(file) = manifest.file_mut() {
+            if Path::new(file.as_str()).is_relative() {
+                *file = base_dir.join(file.as_str()).display().to_string();
+            }
+        }
+        manifests.push(manifest);
+    }
+    Ok(manifests)
+}
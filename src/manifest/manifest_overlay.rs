@@ -0,0 +1,104 @@
+use std::{error::Error, path::Path};
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use super::manifest_types::{Manifest, Metadata};
+
+/// File marking a directory as an overlay.
+pub const OVERLAY_FILE: &str = "overlay.yaml";
+
+/// Content of an `overlay.yaml`: the manifests it starts from and the patches applied to them.
+///
+/// ```yaml
+/// resources:
+///   - ../../base
+///   - extra-version.yaml
+/// patches:
+///   - portals.yaml
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Overlay {
+    pub resources: Vec<String>,
+
+    #[serde(default)]
+    pub patches: Vec<String>,
+}
+
+/// A document of a patch file. It targets the manifest with the same kind and metadata, its spec
+/// is merged into the manifest's spec as a JSON merge patch: objects are merged, `null` removes a
+/// field and anything else replaces it.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Patch {
+    kind: String,
+    metadata: Metadata,
+    #[serde(default)]
+    spec: Value,
+}
+
+/// Applies the patch document `value`, read from `source`, to the manifest it targets.
+pub fn apply_patch(
+    manifests: &mut [Manifest],
+    value: Value,
+    source: &Path,
+    location: &str,
+) -> Result<(), Box<dyn Error>> {
+    let patch: Patch = serde_json::from_value(value)
+        .map_err(|e| format!("{} is not a valid patch: {}", location, e))?;
+    let target = manifests
+        .iter_mut()
+        .find(|m| {
+            let metadata = m.metadata();
+            m.kind().to_string() == patch.kind
+                && metadata.name == patch.metadata.name
+                && metadata.product == patch.metadata.product
+                && metadata.version == patch.metadata.version
+        })
+        .ok_or_else(|| {
+            format!(
+                "{} patches {} {} which is not declared in the overlay resources",
+                location, patch.kind, patch.metadata.name
+            )
+        })?;
+
+    let mut value = serde_json::to_value(&*target)?;
+    merge(&mut value["spec"], &patch.spec);
+    let mut patched: Manifest = serde_json::from_value(value)
+        .map_err(|e| format!("{} does not patch into a valid manifest: {}", location, e))?;
+    patched
+        .validate()
+        .map_err(|e| format!("{} does not patch into a valid manifest: {}", location, e))?;
+    // A file set by the patch is relative to the patch, not to the manifest it patches
+    if let (Some(file), Some(patched_file)) = (
+        patch.spec.get("file").and_then(|f| f.as_str()),
+        patched.file_mut(),
+    ) {
+        if Path::new(file).is_relative() {
+            let base_dir = source.parent().unwrap_or(Path::new("."));
+            *patched_file = base_dir.join(file).display().to_string();
+        }
+    }
+    *target = patched;
+    Ok(())
+}
+
+/// JSON merge patch (RFC 7386) of `patch` into `target`.
+fn merge(target: &mut Value, patch: &Value) {
+    let Value::Object(fields) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    let target = target.as_object_mut().expect("an object");
+    for (name, value) in fields {
+        if value.is_null() {
+            target.remove(name);
+        } else {
+            merge(target.entry(name.clone()).or_insert(Value::Null), value);
+        }
+    }
+}
@@ -0,0 +1,279 @@
+use std::{collections::HashMap, error::Error, fmt};
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use super::{
+    manifest_live::{LiveObject, LiveState},
+    manifest_types::{Kind, Manifest, ResourceKey},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    Create,
+    Update,
+    Unchanged,
+    Delete,
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verb = match self {
+            Action::Create => "create",
+            Action::Update => "update",
+            Action::Unchanged => "unchanged",
+            Action::Delete => "delete",
+        };
+        write!(f, "{}", verb)
+    }
+}
+
+/// What has to happen to one resource for Konnect to match its manifest.
+#[derive(Debug, Clone)]
+pub struct Change {
+    pub key: ResourceKey,
+    pub action: Action,
+    pub desired: Value,
+    pub live: Option<LiveObject>,
+    /// Fields to send when updating, removed labels are set to null
+    pub patch: Map<String, Value>,
+}
+
+/// The changes needed for Konnect to match a set of manifests, along with the ids of every object
+/// that already exists, including parents that are referenced but not declared.
+#[derive(Debug, Default)]
+pub struct Plan {
+    pub changes: Vec<Change>,
+    pub ids: HashMap<ResourceKey, String>,
+}
+
+/// Compares every manifest with what exists in Konnect. Manifests are planned parents first so
+/// the children of a product or version that does not exist yet are known to be created.
+pub fn plan_changes(manifests: &[Manifest], live: &mut LiveState) -> Result<Plan, Box<dyn Error>> {
+    let mut ordered: Vec<&Manifest> = manifests.iter().collect();
+    ordered.sort_by_key(|m| (m.kind(), document_depth(m, manifests)));
+
+    let mut planner = Planner {
+        live,
+        found: HashMap::new(),
+    };
+    let mut changes = Vec::new();
+    for manifest in ordered {
+        let key = manifest.key();
+        let desired = manifest.desired_state()?;
+        let found = planner.lookup(&key)?;
+        // Declared objects that do not exist yet are created by this plan
+        planner
+            .found
+            .insert(key.clone(), found.as_ref().map(|f| f.id.clone()));
+
+        let (action, patch) = match &found {
+            None => (Action::Create, Map::new()),
+            Some(object) => {
+                let patch = patch_fields(&desired, &object.state);
+                match patch.is_empty() {
+                    true => (Action::Unchanged, patch),
+                    false => (Action::Update, patch),
+                }
+            }
+        };
+        changes.push(Change {
+            key,
+            action,
+            desired,
+            live: found,
+            patch,
+        });
+    }
+    let declared: Vec<&ResourceKey> = changes.iter().map(|c| &c.key).collect();
+    for (key, id) in &planner.found {
+        if id.is_none() && !declared.contains(&key) {
+            return Err(format!("{} is neither declared nor found in Konnect", key).into());
+        }
+    }
+    let ids = planner
+        .found
+        .into_iter()
+        .filter_map(|(key, id)| id.map(|id| (key, id)))
+        .collect();
+    Ok(Plan { changes, ids })
+}
+
+impl Change {
+    /// Key of the document this document is nested under, if any.
+    fn parent_document(&self) -> Option<ResourceKey> {
+        if self.key.kind != Kind::APIProductDocument {
+            return None;
+        }
+        let parent = match self.action {
+            Action::Delete => &self.live.as_ref()?.state["parent"],
+            _ => &self.desired["parent"],
+        };
+        parent
+            .as_str()
+            .map(|slug| ResourceKey::document(&self.key.product, slug))
+    }
+}
+
+impl Plan {
+    /// Indices of the changes grouped in waves that can run in parallel, each wave only
+    /// depending on the ones before it. Objects are created after the parent they are created in,
+    /// and deleted before the parent they are deleted with. Deletions come last.
+    pub fn waves(&self) -> Result<Vec<Vec<usize>>, Box<dyn Error>> {
+        let index: HashMap<&ResourceKey, usize> = self
+            .changes
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (&c.key, i))
+            .collect();
+        // dependencies[i] holds the changes that must be done before change i
+        let mut dependencies = vec![Vec::new(); self.changes.len()];
+        for (i, change) in self.changes.iter().enumerate() {
+            let parents = change
+                .key
+                .parent()
+                .into_iter()
+                .chain(change.parent_document());
+            for parent in parents {
+                let Some(&p) = index.get(&parent) else {
+                    continue;
+                };
+                match (change.action, self.changes[p].action) {
+                    (Action::Delete, Action::Delete) => dependencies[p].push(i),
+                    (Action::Delete, _) | (_, Action::Delete) => {}
+                    (_, Action::Create) => dependencies[i].push(p),
+                    _ => {}
+                }
+            }
+        }
+
+        let mut level: Vec<Option<usize>> = vec![None; self.changes.len()];
+        let mut remaining = self.changes.len();
+        while remaining > 0 {
+            let ready: Vec<usize> = (0..self.changes.len())
+                .filter(|&i| level[i].is_none())
+                .filter(|&i| dependencies[i].iter().all(|&d| level[d].is_some()))
+                .collect();
+            if ready.is_empty() {
+                let cycle: Vec<String> = (0..self.changes.len())
+                    .filter(|&i| level[i].is_none())
+                    .map(|i| self.changes[i].key.to_string())
+                    .collect();
+                return Err(format!("Dependency cycle between {}", cycle.join(", ")).into());
+            }
+            for i in ready {
+                let after = dependencies[i]
+                    .iter()
+                    .filter_map(|&d| level[d])
+                    .max()
+                    .map_or(0, |l| l + 1);
+                level[i] = Some(after);
+                remaining -= 1;
+            }
+        }
+
+        let mut waves: Vec<Vec<usize>> = Vec::new();
+        let deletions_start = level
+            .iter()
+            .zip(&self.changes)
+            .filter(|(_, c)| c.action != Action::Delete)
+            .filter_map(|(l, _)| *l)
+            .max()
+            .map_or(0, |l| l + 1);
+        for (i, change) in self.changes.iter().enumerate() {
+            let mut wave = level[i].expect("a level for every change");
+            if change.action == Action::Delete {
+                wave += deletions_start;
+            }
+            if waves.len() <= wave {
+                waves.resize(wave + 1, Vec::new());
+            }
+            waves[wave].push(i);
+        }
+        waves.retain(|w| !w.is_empty());
+        Ok(waves)
+    }
+}
+
+/// Number of declared ancestors of a document, so parent documents are created first.
+fn document_depth(manifest: &Manifest, manifests: &[Manifest]) -> usize {
+    let mut depth = 0;
+    let mut current = manifest;
+    while let Manifest::APIProductDocument { metadata, spec } = current {
+        let parent = manifests.iter().find(|m| {
+            m.kind() == Kind::APIProductDocument
+                && m.metadata().product == metadata.product
+                && Some(&m.metadata().name) == spec.parent.as_ref()
+        });
+        match parent {
+            // A cycle never ends, stop once every document has been visited
+            Some(p) if depth < manifests.len() => {
+                depth += 1;
+                current = p;
+            }
+            _ => break,
+        }
+    }
+    depth
+}
+
+struct Planner<'a> {
+    live: &'a mut LiveState,
+    /// Id of every object looked up so far, `None` when it does not exist
+    found: HashMap<ResourceKey, Option<String>>,
+}
+
+impl Planner<'_> {
+    /// Finds `key` in Konnect, looking its parents up first. `None` when it or one of its
+    /// parents does not exist.
+    fn lookup(&mut self, key: &ResourceKey) -> Result<Option<LiveObject>, Box<dyn Error>> {
+        let mut parent_ids = Vec::new();
+        let mut parent = key.parent();
+        while let Some(p) = parent {
+            let id = match self.found.get(&p) {
+                Some(id) => id.clone(),
+                None => {
+                    let id = self.lookup(&p)?.map(|o| o.id);
+                    self.found.insert(p.clone(), id.clone());
+                    id
+                }
+            };
+            match id {
+                Some(id) => parent_ids.insert(0, id),
+                // The parent is about to be created, so this cannot exist yet
+                None => return Ok(None),
+            }
+            parent = p.parent();
+        }
+        let ids: Vec<&str> = parent_ids.iter().map(|s| s.as_str()).collect();
+        self.live.find(key, &ids)
+    }
+}
+
+/// Top-level fields of `desired` that differ from `live`. Labels are compared key by key and the
+/// ones missing from `desired` are set to null so Konnect removes them.
+pub fn patch_fields(desired: &Value, live: &Value) -> Map<String, Value> {
+    let mut patch = Map::new();
+    let Some(desired) = desired.as_object() else {
+        return patch;
+    };
+    for (field, value) in desired {
+        let current = live.get(field).unwrap_or(&Value::Null);
+        if value == current {
+            continue;
+        }
+        if field == "labels" {
+            let mut labels = value.as_object().cloned().unwrap_or_default();
+            if let Some(current) = current.as_object() {
+                for name in current.keys() {
+                    labels.entry(name.clone()).or_insert(Value::Null);
+                }
+            }
+            patch.insert(field.clone(), Value::Object(labels));
+        } else {
+            patch.insert(field.clone(), value.clone());
+        }
+    }
+    patch
+}
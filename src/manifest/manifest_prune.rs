@@ -0,0 +1,153 @@
+use std::{collections::HashSet, error::Error};
+
+use log::warn;
+use serde_json::{Map, Value};
+
+use crate::utils::label_selector::LabelSelector;
+
+use super::{
+    manifest_live::{LiveObject, LiveState},
+    manifest_plan::{Action, Change, Plan},
+    manifest_types::{Kind, ResourceKey},
+};
+
+/// Adds deletions to `plan` for objects owned by the manifests (the products matching
+/// `selector`) that the manifests no longer declare. Products matching the selector that are not
+/// declared are deleted with everything they hold. Inside declared products matching the
+/// selector, undeclared versions, specifications and documents are deleted. Children are
+/// always deleted before their parents.
+pub fn plan_prune(
+    plan: &mut Plan,
+    selector: &LabelSelector,
+    live: &mut LiveState,
+) -> Result<(), Box<dyn Error>> {
+    let declared: HashSet<ResourceKey> = plan.changes.iter().map(|c| c.key.clone()).collect();
+    let managed: HashSet<ResourceKey> = plan
+        .changes
+        .iter()
+        .filter(|c| c.key.kind == Kind::APIProduct && selector.matches(&c.desired["labels"]))
+        .map(|c| c.key.clone())
+        .collect();
+
+    let products = live.products()?;
+    let mut seen = HashSet::new();
+    let mut duplicated = HashSet::new();
+    for (name, _) in &products {
+        if !seen.insert(name.clone()) {
+            duplicated.insert(name.clone());
+        }
+    }
+
+    let mut pruner = Pruner {
+        plan,
+        declared: &declared,
+        live,
+    };
+    for (name, product) in &products {
+        let key = ResourceKey::product(name);
+        if declared.contains(&key) {
+            if managed.contains(&key) {
+                pruner.prune_children(name, product, false)?;
+            }
+        } else if selector.matches(&product.state["labels"]) {
+            if duplicated.contains(name) {
+                warn!(
+                    "Not pruning API product {} ({}), several products have this name",
+                    name, product.id
+                );
+                continue;
+            }
+            pruner.prune_children(name, product, true)?;
+            pruner.delete(key, product);
+        }
+    }
+    Ok(())
+}
+
+struct Pruner<'a> {
+    plan: &'a mut Plan,
+    declared: &'a HashSet<ResourceKey>,
+    live: &'a mut LiveState,
+}
+
+impl Pruner<'_> {
+    /// Deletes the children of a product that are not declared, or all of them with `all`.
+    fn prune_children(
+        &mut self,
+        product_name: &str,
+        product: &LiveObject,
+        all: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        self.plan
+            .ids
+            .insert(ResourceKey::product(product_name), product.id.clone());
+
+        let versions = self.live.versions(&product.id)?.to_vec();
+        for (version_name, version) in versions {
+            let version_key = ResourceKey::version(product_name, &version_name);
+            let delete_version = all || !self.declared.contains(&version_key);
+            self.plan
+                .ids
+                .insert(version_key.clone(), version.id.clone());
+            let specs = self.live.specifications(&product.id, &version.id)?.to_vec();
+            for (spec_name, spec) in specs {
+                let key = ResourceKey::specification(product_name, &version_name, &spec_name);
+                if delete_version || !self.declared.contains(&key) {
+                    self.delete(key, &spec);
+                }
+            }
+            if delete_version {
+                self.delete(version_key, &version);
+            }
+        }
+
+        let mut documents: Vec<(String, LiveObject)> = self
+            .live
+            .documents(&product.id)?
+            .iter()
+            .filter(|(slug, _)| {
+                all || !self
+                    .declared
+                    .contains(&ResourceKey::document(product_name, slug))
+            })
+            .cloned()
+            .collect();
+        // Deepest documents first so a parent is never deleted before its children
+        let depth = |slug: &str, documents: &[(String, LiveObject)]| {
+            let mut depth = 0;
+            let mut current = slug.to_string();
+            while let Some((_, d)) = documents.iter().find(|(s, _)| *s == current) {
+                match d.state["parent"].as_str() {
+                    Some(parent) if depth < documents.len() => {
+                        depth += 1;
+                        current = parent.to_string();
+                    }
+                    _ => break,
+                }
+            }
+            depth
+        };
+        let depths: Vec<usize> = documents
+            .iter()
+            .map(|(slug, _)| depth(slug, &documents))
+            .collect();
+        let mut ordered: Vec<(usize, (String, LiveObject))> =
+            depths.into_iter().zip(documents.drain(..)).collect();
+        ordered.sort_by_key(|(depth, _)| std::cmp::Reverse(*depth));
+        for (_, (slug, document)) in ordered {
+            self.delete(ResourceKey::document(product_name, &slug), &document);
+        }
+        Ok(())
+    }
+
+    fn delete(&mut self, key: ResourceKey, object: &LiveObject) {
+        self.plan.ids.insert(key.clone(), object.id.clone());
+        self.plan.changes.push(Change {
+            key,
+            action: Action::Delete,
+            desired: Value::Null,
+            live: Some(object.clone()),
+            patch: Map::new(),
+        });
+    }
+}
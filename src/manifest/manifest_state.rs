@@ -0,0 +1,304 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::cli::main_cli::StateSubCommand;
+
+use super::{
+    manifest_live::LiveState,
+    manifest_loader::load_manifest_files,
+    manifest_plan::{plan_changes, Action},
+    manifest_types::{Kind, ResourceKey},
+};
+
+const STATE_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StateEntry {
+    #[serde(flatten)]
+    key: ResourceKey,
+    id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StateContent {
+    version: u32,
+    objects: Vec<StateEntry>,
+}
+
+/// Konnect ids of the objects the manifests declare, keyed by their manifest identity, so objects
+/// are found by id rather than looked up by name and can be renamed without being recreated.
+///
+/// A state opened with `lock` holds `<file>.lock` until dropped, so two runs never write the same
+/// state at once.
+#[derive(Debug)]
+pub struct State {
+    path: PathBuf,
+    ids: HashMap<ResourceKey, String>,
+    lock: Option<PathBuf>,
+}
+
+impl State {
+    /// Reads the state at `path`, empty when the file does not exist yet.
+    pub fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+        let ids = match fs::read_to_string(path) {
+            Ok(content) => {
+                let content: StateContent = serde_json::from_str(&content)
+                    .map_err(|e| format!("{} is not a valid state file: {}", path, e))?;
+                if content.version != STATE_VERSION {
+                    return Err(format!(
+                        "{} has version {}, this konnectctl only reads version {}",
+                        path, content.version, STATE_VERSION
+                    )
+                    .into());
+                }
+                content.objects.into_iter().map(|e| (e.key, e.id)).collect()
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(format!("Unable to read state file {}: {}", path, e).into()),
+        };
+        Ok(State {
+            path: PathBuf::from(path),
+            ids,
+            lock: None,
+        })
+    }
+
+    /// Locks the state at `path` for this run and reads it.
+    pub fn lock(path: &str) -> Result<Self, Box<dyn Error>> {
+        let lock = PathBuf::from(format!("{}.lock", path));
+        if let Some(dir) = lock.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Unable to create {}: {}", dir.display(), e))?;
+        }
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&lock)
+        {
+            Ok(_) => {
+                fs::write(&lock, std::process::id().to_string())?;
+            }
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                let owner = fs::read_to_string(&lock).unwrap_or_default();
+                return Err(format!(
+                    "{} is locked by another run (process {}), remove {} if no other run is in progress",
+                    path,
+                    owner.trim(),
+                    lock.display()
+                )
+                .into());
+            }
+            Err(e) => {
+                return Err(format!("Unable to lock {}: {}", lock.display(), e).into());
+            }
+        }
+        let mut state = State::load(path).inspect_err(|_| {
+            fs::remove_file(&lock).ok();
+        })?;
+        state.lock = Some(lock);
+        Ok(state)
+    }
+
+    pub fn ids(&self) -> &HashMap<ResourceKey, String> {
+        &self.ids
+    }
+
+    pub fn record(&mut self, key: &ResourceKey, id: &str) {
+        self.ids.insert(key.clone(), id.to_string());
+    }
+
+    pub fn forget(&mut self, key: &ResourceKey) {
+        self.ids.remove(key);
+    }
+
+    /// Replaces every recorded id with `ids`.
+    pub fn replace(&mut self, ids: HashMap<ResourceKey, String>) {
+        self.ids = ids;
+    }
+
+    /// Records that the object `from` names is now named `to`, along with its children. Returns
+    /// how many entries were renamed.
+    pub fn rename(&mut self, from: &ResourceKey, to: &ResourceKey) -> Result<usize, String> {
+        if from.kind != to.kind {
+            return Err("an object cannot change kind".to_string());
+        }
+        if from.parent() != to.parent() {
+            return Err(format!(
+                "{} and {} have different parents, only the name of an object can change",
+                from, to
+            ));
+        }
+        if !self.ids.contains_key(from) {
+            return Err(format!("{} is not in the state", from));
+        }
+        if self.ids.contains_key(to) {
+            return Err(format!("{} is already in the state", to));
+        }
+        let renamed: Vec<(ResourceKey, ResourceKey)> = self
+            .ids
+            .keys()
+            .filter_map(|key| renamed_key(key, from, to).map(|new| (key.clone(), new)))
+            .collect();
+        for (old, new) in &renamed {
+            let id = self.ids.remove(old).expect("a recorded key");
+            self.ids.insert(new.clone(), id);
+        }
+        Ok(renamed.len())
+    }
+
+    /// Entries in a stable order, for listing.
+    pub fn entries(&self) -> Vec<(&ResourceKey, &String)> {
+        let mut entries: Vec<_> = self.ids.iter().collect();
+        entries.sort();
+        entries
+    }
+
+    /// Writes the state, through a temporary file so a failed run never leaves half a state.
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        if let Some(dir) = self.path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Unable to create {}: {}", dir.display(), e))?;
+        }
+        let objects = self
+            .entries()
+            .into_iter()
+            .map(|(key, id)| StateEntry {
+                key: key.clone(),
+                id: id.clone(),
+            })
+            .collect();
+        let content = StateContent {
+            version: STATE_VERSION,
+            objects,
+        };
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_string_pretty(&content)?)
+            .map_err(|e| format!("Unable to write {}: {}", tmp.display(), e))?;
+        fs::rename(&tmp, &self.path)
+            .map_err(|e| format!("Unable to write {}: {}", self.path.display(), e))?;
+        info!("Saved {} ids to {}", self.ids.len(), self.path.display());
+        Ok(())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for State {
+    fn drop(&mut self) {
+        if let Some(lock) = &self.lock {
+            fs::remove_file(lock).ok();
+        }
+    }
+}
+
+/// `key` once `from` is renamed to `to`: `to` itself for `from`, and the same child under its new
+/// parent name for children of `from`.
+fn renamed_key(key: &ResourceKey, from: &ResourceKey, to: &ResourceKey) -> Option<ResourceKey> {
+    if key == from {
+        return Some(to.clone());
+    }
+    if key.product != from.product {
+        return None;
+    }
+    let mut renamed = key.clone();
+    match (from.kind, key.kind) {
+        (Kind::APIProduct, _) => renamed.product = to.product.clone(),
+        (Kind::APIProductVersion, Kind::APIProductVersionSpecification)
+            if key.version.as_deref() == Some(from.name.as_str()) =>
+        {
+            renamed.version = Some(to.name.clone())
+        }
+        _ => return None,
+    }
+    Some(renamed)
+}
+
+/// Parses the identity of an object as written by `state list`: its kind and its path, e.g.
+/// `APIProductVersionSpecification payments-api/v1/openapi.yaml`.
+pub fn parse_key(kind: &str, path: &str) -> Result<ResourceKey, String> {
+    let kind: Kind = serde_json::from_value(serde_json::Value::String(kind.to_string()))
+        .map_err(|_| {
+            format!(
+                "Unknown kind {}, expected APIProduct, APIProductVersion, APIProductVersionSpecification or APIProductDocument",
+                kind
+            )
+        })?;
+    let parts: Vec<&str> = match kind {
+        Kind::APIProduct => vec![path],
+        Kind::APIProductVersion | Kind::APIProductDocument => path.splitn(2, '/').collect(),
+        Kind::APIProductVersionSpecification => path.splitn(3, '/').collect(),
+    };
+    if parts.iter().any(|p| p.is_empty()) {
+        return Err(format!("Invalid path {} for {}", path, kind));
+    }
+    match (kind, parts.as_slice()) {
+        (Kind::APIProduct, [name]) => Ok(ResourceKey::product(name)),
+        (Kind::APIProductVersion, [product, name]) => Ok(ResourceKey::version(product, name)),
+        (Kind::APIProductDocument, [product, slug]) => Ok(ResourceKey::document(product, slug)),
+        (Kind::APIProductVersionSpecification, [product, version, name]) => {
+            Ok(ResourceKey::specification(product, version, name))
+        }
+        _ => Err(format!(
+            "Invalid path {} for {}, expected {}",
+            path,
+            kind,
+            match kind {
+                Kind::APIProductVersionSpecification => "<product>/<version>/<name>",
+                _ => "<product>/<name>",
+            }
+        )),
+    }
+}
+
+/// Runs `state list`, `state refresh` or `state mv`.
+pub fn run_state_command(command: StateSubCommand) -> Result<(), Box<dyn Error>> {
+    match command {
+        StateSubCommand::List(list_cli) => {
+            let state = State::load(&list_cli.state)?;
+            for (key, id) in state.entries() {
+                println!("{}\t{}\t{}", key.kind, key.path(), id);
+            }
+        }
+        StateSubCommand::Refresh(refresh_cli) => {
+            let manifests = load_manifest_files(&refresh_cli.manifests)?;
+            let mut state = State::lock(&refresh_cli.state)?;
+            let mut live = LiveState::with_ids(state.ids().clone());
+            let plan = plan_changes(&manifests, &mut live)?;
+            let missing = plan
+                .changes
+                .iter()
+                .filter(|c| c.action == Action::Create)
+                .count();
+            state.replace(plan.ids);
+            state.save()?;
+            println!(
+                "Recorded {} ids in {}, {} declared objects do not exist yet",
+                state.ids().len(),
+                state.path().display(),
+                missing
+            );
+        }
+        StateSubCommand::Mv(mv_cli) => {
+            let from = parse_key(&mv_cli.kind, &mv_cli.from)?;
+            let to = parse_key(&mv_cli.kind, &mv_cli.to)?;
+            let mut state = State::lock(&mv_cli.state)?;
+            let renamed = state.rename(&from, &to)?;
+            state.save()?;
+            println!(
+                "{} is now {}, {} entries updated. Rename it in the manifests as well",
+                from, to, renamed
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,135 @@
+use std::{collections::HashMap, env, error::Error, fs};
+
+use serde_yaml::Value;
+
+use super::manifest_types::Manifest;
+
+/// Writes `manifests` as a single multi-document YAML stream that apply and diff accept, e.g.
+/// from stdin. Spec files are referenced by absolute path so the output can be stored anywhere.
+pub fn render_manifests(manifests: &mut [Manifest]) -> Result<String, Box<dyn Error>> {
+    let mut documents = Vec::new();
+    for manifest in manifests.iter_mut() {
+        if let Some(file) = manifest.file_mut() {
+            *file = fs::canonicalize(file.as_str())
+                .map_err(|e| format!("Unable to read {}: {}", file, e))?
+                .display()
+                .to_string();
+        }
+        documents.push(serde_yaml::to_string(manifest)?);
+    }
+    Ok(documents.join("---\n"))
+}
+
+/// Values of the `${NAME}` variables used in manifests. Values files are read in order, a later
+/// file overriding an earlier one, and variables they do not define are read from the
+/// environment.
+#[derive(Debug, Default)]
+pub struct Variables {
+    values: HashMap<String, String>,
+}
+
+impl Variables {
+    pub fn from_files(files: &[String]) -> Result<Self, Box<dyn Error>> {
+        let mut values = HashMap::new();
+        for file in files {
+            let content = fs::read_to_string(file)
+                .map_err(|e| format!("Unable to read values file {}: {}", file, e))?;
+            let mapping: HashMap<String, Value> = match serde_yaml::from_str(&content)
+                .map_err(|e| format!("{} is not a valid values file: {}", file, e))?
+            {
+                Some(mapping) => mapping,
+                None => continue,
+            };
+            for (name, value) in mapping {
+                let value = match value {
+                    Value::String(s) => s,
+                    Value::Number(n) => n.to_string(),
+                    Value::Bool(b) => b.to_string(),
+                    _ => {
+                        return Err(format!(
+                            "{}: the value of {} must be a string, a number or a boolean",
+                            file, name
+                        )
+                        .into())
+                    }
+                };
+                values.insert(name, value);
+            }
+        }
+        Ok(Variables { values })
+    }
+
+    fn get(&self, name: &str) -> Option<String> {
+        self.values
+            .get(name)
+            .cloned()
+            .or_else(|| env::var(name).ok())
+    }
+
+    /// Replaces `${NAME}` with the value of NAME in `content`, or with `default` for
+    /// `${NAME:-default}` when NAME is not defined. `$${` is written as a literal `${`.
+    /// `location` names the content in errors.
+    pub fn substitute(&self, content: &str, location: &str) -> Result<String, Box<dyn Error>> {
+        let mut rendered = String::with_capacity(content.len());
+        let mut undefined = Vec::new();
+        let mut rest = content;
+        while let Some(start) = rest.find('$') {
+            rendered.push_str(&rest[..start]);
+            let tail = &rest[start..];
+            if let Some(after) = tail.strip_prefix("$${") {
+                rendered.push_str("${");
+                rest = after;
+                continue;
+            }
+            let Some(expression) = tail.strip_prefix("${") else {
+                rendered.push('$');
+                rest = &tail[1..];
+                continue;
+            };
+            let end = expression.find('}').ok_or_else(|| {
+                format!("{}: unterminated variable {}", location, first_line(tail))
+            })?;
+            let (name, default) = match expression[..end].split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (&expression[..end], None),
+            };
+            if !is_variable_name(name) {
+                return Err(
+                    format!("{}: invalid variable ${{{}}}", location, &expression[..end]).into(),
+                );
+            }
+            match self.get(name).or(default.map(|d| d.to_string())) {
+                Some(value) => rendered.push_str(&value),
+                None => {
+                    if !undefined.contains(&name) {
+                        undefined.push(name);
+                    }
+                }
+            }
+            rest = &expression[end + 1..];
+        }
+        rendered.push_str(rest);
+
+        if !undefined.is_empty() {
+            return Err(format!(
+                "{} uses undefined variables: {}, pass them with --values or set them in the environment",
+                location,
+                undefined.join(", ")
+            )
+            .into());
+        }
+        Ok(rendered)
+    }
+}
+
+fn is_variable_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn first_line(s: &str) -> &str {
+    s.lines().next().unwrap_or(s)
+}
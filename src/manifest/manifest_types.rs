@@ -0,0 +1,433 @@
+use std::{collections::BTreeMap, error::Error, fmt, fs};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    api::api_product_version_spec::read_spec_for_upload_content,
+    cli::api_product_versions_cli::PublishStatus,
+};
+
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Kind {
+    APIProduct,
+    APIProductVersion,
+    APIProductVersionSpecification,
+    APIProductDocument,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Kind::APIProduct => "APIProduct",
+            Kind::APIProductVersion => "APIProductVersion",
+            Kind::APIProductVersionSpecification => "APIProductVersionSpecification",
+            Kind::APIProductDocument => "APIProductDocument",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Identity of a resource in manifests: its kind, its own name and the names of its parents.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ResourceKey {
+    pub kind: Kind,
+    pub product: String,
+    pub version: Option<String>,
+    pub name: String,
+}
+
+impl ResourceKey {
+    pub fn product(name: &str) -> Self {
+        ResourceKey {
+            kind: Kind::APIProduct,
+            product: name.to_string(),
+            version: None,
+            name: name.to_string(),
+        }
+    }
+
+    pub fn version(product: &str, name: &str) -> Self {
+        ResourceKey {
+            kind: Kind::APIProductVersion,
+            product: product.to_string(),
+            version: None,
+            name: name.to_string(),
+        }
+    }
+
+    pub fn specification(product: &str, version: &str, name: &str) -> Self {
+        ResourceKey {
+            kind: Kind::APIProductVersionSpecification,
+            product: product.to_string(),
+            version: Some(version.to_string()),
+            name: name.to_string(),
+        }
+    }
+
+    /// Documents are identified by their slug.
+    pub fn document(product: &str, slug: &str) -> Self {
+        ResourceKey {
+            kind: Kind::APIProductDocument,
+            product: product.to_string(),
+            version: None,
+            name: slug.to_string(),
+        }
+    }
+
+    /// Names of the resource and its parents, e.g. `payments-api/v1/openapi.yaml`.
+    pub fn path(&self) -> String {
+        match self.kind {
+            Kind::APIProduct => self.name.clone(),
+            Kind::APIProductVersion | Kind::APIProductDocument => {
+                format!("{}/{}", self.product, self.name)
+            }
+            Kind::APIProductVersionSpecification => format!(
+                "{}/{}/{}",
+                self.product,
+                self.version.as_deref().unwrap_or_default(),
+                self.name
+            ),
+        }
+    }
+
+    /// Key of the resource this one belongs to, `None` for products.
+    pub fn parent(&self) -> Option<ResourceKey> {
+        match self.kind {
+            Kind::APIProduct => None,
+            Kind::APIProductVersion | Kind::APIProductDocument => {
+                Some(ResourceKey::product(&self.product))
+            }
+            Kind::APIProductVersionSpecification => Some(ResourceKey::version(
+                &self.product,
+                self.version.as_deref().unwrap_or_default(),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ResourceKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.kind, self.path())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Metadata {
+    pub name: String,
+
+    /// Name of the API product a version or specification belongs to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub product: Option<String>,
+
+    /// Name of the API product version a specification belongs to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProductSpec {
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+    #[serde(default)]
+    pub portal_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VersionSpec {
+    #[serde(default = "default_publish_status")]
+    pub publish_status: String,
+    #[serde(default)]
+    pub deprecated: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gateway_service: Option<GatewayServiceRef>,
+}
+
+fn default_publish_status() -> String {
+    "unpublished".to_string()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GatewayServiceRef {
+    pub control_plane_id: String,
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpecificationSpec {
+    /// Path to the spec file, relative to the manifest it is declared in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+
+    /// Inline spec content, used when no file is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DocumentSpec {
+    pub title: String,
+    #[serde(default = "default_publish_status")]
+    pub status: String,
+
+    /// Slug of the parent document
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+
+    /// Path to a markdown file, relative to the manifest it is declared in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+
+    /// Inline markdown content, used when no file is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+}
+
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum Manifest {
+    APIProduct {
+        metadata: Metadata,
+        #[serde(default)]
+        spec: ProductSpec,
+    },
+    APIProductVersion {
+        metadata: Metadata,
+        spec: VersionSpec,
+    },
+    APIProductVersionSpecification {
+        metadata: Metadata,
+        spec: SpecificationSpec,
+    },
+    /// metadata.name is the document slug
+    APIProductDocument {
+        metadata: Metadata,
+        spec: DocumentSpec,
+    },
+}
+
+impl Manifest {
+    pub fn kind(&self) -> Kind {
+        match self {
+            Manifest::APIProduct { .. } => Kind::APIProduct,
+            Manifest::APIProductVersion { .. } => Kind::APIProductVersion,
+            Manifest::APIProductVersionSpecification { .. } => Kind::APIProductVersionSpecification,
+            Manifest::APIProductDocument { .. } => Kind::APIProductDocument,
+        }
+    }
+
+    pub fn metadata(&self) -> &Metadata {
+        match self {
+            Manifest::APIProduct { metadata, .. }
+            | Manifest::APIProductVersion { metadata, .. }
+            | Manifest::APIProductVersionSpecification { metadata, .. }
+            | Manifest::APIProductDocument { metadata, .. } => metadata,
+        }
+    }
+
+    /// Checks the fields serde cannot: parent references and allowed values.
+    pub fn validate(&self) -> Result<(), String> {
+        let metadata = self.metadata();
+        if metadata.name.is_empty() {
+            return Err(format!("{} has an empty metadata.name", self.kind()));
+        }
+        let needs_product = self.kind() != Kind::APIProduct;
+        let needs_version = self.kind() == Kind::APIProductVersionSpecification;
+        if needs_product && metadata.product.is_none() {
+            return Err(format!(
+                "{} {} requires metadata.product",
+                self.kind(),
+                metadata.name
+            ));
+        }
+        if needs_version && metadata.version.is_none() {
+            return Err(format!(
+                "{} {} requires metadata.version",
+                self.kind(),
+                metadata.name
+            ));
+        }
+        match self {
+            Manifest::APIProductVersion { spec, .. } => {
+                <PublishStatus as clap::ValueEnum>::from_str(&spec.publish_status, false).map_err(
+                    |_| {
+                        format!(
+                            "{} {} has an invalid publish_status {}, expected published or unpublished",
+                            self.kind(),
+                            metadata.name,
+                            spec.publish_status
+                        )
+                    },
+                )?;
+            }
+            Manifest::APIProductVersionSpecification { spec, .. } => {
+                if spec.file.is_some() == spec.content.is_some() {
+                    return Err(format!(
+                        "{} {} requires exactly one of spec.file or spec.content",
+                        self.kind(),
+                        metadata.name
+                    ));
+                }
+            }
+            Manifest::APIProductDocument { spec, .. } => {
+                <PublishStatus as clap::ValueEnum>::from_str(&spec.status, false).map_err(
+                    |_| {
+                        format!(
+                            "{} {} has an invalid status {}, expected published or unpublished",
+                            self.kind(),
+                            metadata.name,
+                            spec.status
+                        )
+                    },
+                )?;
+                if spec.file.is_some() && spec.content.is_some() {
+                    return Err(format!(
+                        "{} {} accepts only one of spec.file or spec.content",
+                        self.kind(),
+                        metadata.name
+                    ));
+                }
+            }
+            Manifest::APIProduct { .. } => {}
+        }
+        Ok(())
+    }
+
+    pub fn key(&self) -> ResourceKey {
+        let metadata = self.metadata();
+        let product = metadata.product.clone().unwrap_or_default();
+        match self {
+            Manifest::APIProduct { .. } => ResourceKey::product(&metadata.name),
+            Manifest::APIProductVersion { .. } => ResourceKey::version(&product, &metadata.name),
+            Manifest::APIProductVersionSpecification { .. } => ResourceKey::specification(
+                &product,
+                metadata.version.as_deref().unwrap_or_default(),
+                &metadata.name,
+            ),
+            Manifest::APIProductDocument { .. } => ResourceKey::document(&product, &metadata.name),
+        }
+    }
+
+    /// Mutable paths to the files a manifest points at, so they can be resolved against the
+    /// manifest location.
+    pub fn file_mut(&mut self) -> Option<&mut String> {
+        match self {
+            Manifest::APIProductVersionSpecification { spec, .. } => spec.file.as_mut(),
+            Manifest::APIProductDocument { spec, .. } => spec.file.as_mut(),
+            _ => None,
+        }
+    }
+
+    /// The state Konnect should end up in, in the same shape as `normalize_live` produces so
+    /// that both can be compared field by field. Spec files are bundled and validated here.
+    pub fn desired_state(&self) -> Result<Value, Box<dyn Error>> {
+        let name = &self.metadata().name;
+        Ok(match self {
+            Manifest::APIProduct { spec, .. } => {
+                let mut portal_ids = spec.portal_ids.clone();
+                portal_ids.sort();
+                json!({
+                    "name": name,
+                    "description": spec.description,
+                    "labels": spec.labels,
+                    "portal_ids": portal_ids,
+                })
+            }
+            Manifest::APIProductVersion { spec, .. } => json!({
+                "name": name,
+                "publish_status": spec.publish_status,
+                "deprecated": spec.deprecated,
+                "gateway_service": spec.gateway_service,
+            }),
+            Manifest::APIProductVersionSpecification { spec, .. } => {
+                let content = match (&spec.file, &spec.content) {
+                    (Some(file), _) => read_spec_for_upload_content(file, false)?,
+                    (None, Some(content)) => content.clone(),
+                    (None, None) => String::new(),
+                };
+                json!({
+                    "name": name,
+                    "content": content,
+                })
+            }
+            Manifest::APIProductDocument { spec, .. } => {
+                let content = match (&spec.file, &spec.content) {
+                    (Some(file), _) => fs::read_to_string(file)
+                        .map_err(|e| format!("Unable to read document file {}: {}", file, e))?,
+                    (None, Some(content)) => content.clone(),
+                    (None, None) => String::new(),
+                };
+                json!({
+                    "slug": name,
+                    "title": spec.title,
+                    "status": spec.status,
+                    "parent": spec.parent,
+                    "content": content,
+                })
+            }
+        })
+    }
+}
+
+/// Reduces an object returned by Konnect to the fields manifests manage, in the same shape as
+/// `Manifest::desired_state`. Server managed fields such as `id`, `created_at` and
+/// `updated_at` are dropped.
+pub fn normalize_live(kind: Kind, live: &Value) -> Value {
+    let field = |name: &str| live.get(name).cloned().unwrap_or(Value::Null);
+    match kind {
+        Kind::APIProduct => {
+            let mut portal_ids: Vec<String> = live
+                .get("portal_ids")
+                .and_then(|p| serde_json::from_value(p.clone()).ok())
+                .unwrap_or_default();
+            portal_ids.sort();
+            let labels: BTreeMap<String, String> = live
+                .get("labels")
+                .and_then(|l| serde_json::from_value(l.clone()).ok())
+                .unwrap_or_default();
+            json!({
+                "name": field("name"),
+                "description": live.get("description").and_then(|d| d.as_str()).unwrap_or_default(),
+                "labels": labels,
+                "portal_ids": portal_ids,
+            })
+        }
+        Kind::APIProductVersion => {
+            let gateway_service = match live.get("gateway_service") {
+                Some(gs) if gs.is_object() => json!({
+                    "control_plane_id": gs.get("control_plane_id").cloned().unwrap_or(Value::Null),
+                    "id": gs.get("id").cloned().unwrap_or(Value::Null),
+                }),
+                _ => Value::Null,
+            };
+            json!({
+                "name": field("name"),
+                "publish_status": field("publish_status"),
+                "deprecated": live.get("deprecated").and_then(|d| d.as_bool()).unwrap_or(false),
+                "gateway_service": gateway_service,
+            })
+        }
+        Kind::APIProductVersionSpecification => json!({
+            "name": field("name"),
+            "content": field("content"),
+        }),
+        // `parent` holds the slug of the parent document, set when documents are listed
+        Kind::APIProductDocument => json!({
+            "slug": field("slug"),
+            "title": field("title"),
+            "status": field("status"),
+            "parent": field("parent"),
+            "content": live.get("content").and_then(|c| c.as_str()).unwrap_or_default(),
+        }),
+    }
+}
@@ -0,0 +1,12 @@
+pub mod manifest_apply;
+pub mod manifest_diff;
+pub mod manifest_drift;
+pub mod manifest_export;
+pub mod manifest_live;
+pub mod manifest_loader;
+pub mod manifest_overlay;
+pub mod manifest_plan;
+pub mod manifest_prune;
+pub mod manifest_state;
+pub mod manifest_template;
+pub mod manifest_types;
@@ -0,0 +1,308 @@
+use std::{
+    env,
+    error::Error,
+    fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use clap::Command;
+use clap_complete::Shell;
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::{
+        api_product_version_spec::GetAllAPIProductVersionSpecRequest,
+        api_product_versions::GetAllApiProductVersionRequest,
+        api_products::GetAPIProductsArrayRequest, api_utils::Executable,
+    },
+    utils::konnect_utils::{FetchFilter, KonnectClient},
+};
+
+/// How long fetched names are used before they are fetched again.
+const CACHE_TTL: Duration = Duration::from_secs(60);
+
+const PRODUCT_FLAGS: &[&str] = &["-p", "--product-id", "--product"];
+const VERSION_FLAGS: &[&str] = &["-v", "--product-version-id", "--version"];
+const AGAINST_VERSION_FLAGS: &[&str] = &["--against-version-id", "--against-version"];
+const ENTITIES: &[&str] = &[
+    "api-product",
+    "api-product-version",
+    "api-product-version-specification",
+];
+
+/// What the word being completed names.
+#[derive(Debug, PartialEq)]
+pub enum Candidates {
+    Products,
+    Versions { product: String },
+    Specifications { product: String, version: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Candidate {
+    pub name: String,
+    pub id: String,
+}
+
+/// What the last of `words` names given the flag before it, e.g. the versions of the product
+/// given with -p after -v. None when it is not an id or when the objects it depends on are not
+/// given yet.
+pub fn candidates_for(words: &[String]) -> Option<Candidates> {
+    let [earlier @ .., previous, _] = words else {
+        return None;
+    };
+    let entity = words
+        .iter()
+        .find_map(|w| ENTITIES.iter().find(|e| *e == w))
+        .copied();
+    let product = || flag_value(earlier, PRODUCT_FLAGS);
+    let previous = previous.as_str();
+    match previous {
+        // -p of create and patch api-product takes portal ids
+        "-p" if entity == Some("api-product") => None,
+        _ if PRODUCT_FLAGS.contains(&previous) => Some(Candidates::Products),
+        _ if VERSION_FLAGS.contains(&previous) || AGAINST_VERSION_FLAGS.contains(&previous) => {
+            Some(Candidates::Versions {
+                product: product()?,
+            })
+        }
+        "--against-id" => Some(Candidates::Specifications {
+            product: product()?,
+            version: flag_value(earlier, AGAINST_VERSION_FLAGS)?,
+        }),
+        "-i" | "--id" => match entity? {
+            "api-product" => Some(Candidates::Products),
+            "api-product-version" => Some(Candidates::Versions {
+                product: product()?,
+            }),
+            _ => Some(Candidates::Specifications {
+                product: product()?,
+                version: flag_value(earlier, VERSION_FLAGS)?,
+            }),
+        },
+        _ => None,
+    }
+}
+
+/// Value of the last of `flags` given in `words`.
+fn flag_value(words: &[String], flags: &[&str]) -> Option<String> {
+    words
+        .windows(2)
+        .rev()
+        .find(|pair| flags.contains(&pair[0].as_str()))
+        .map(|pair| pair[1].clone())
+}
+
+/// Names of `candidates` starting with `prefix`, and their ids as well once something is typed
+/// since ids are accepted too.
+pub fn matching<'a>(candidates: &'a [Candidate], prefix: &str) -> Vec<&'a str> {
+    let names = candidates
+        .iter()
+        .map(|c| c.name.as_str())
+        .filter(|name| name.starts_with(prefix));
+    let ids = candidates
+        .iter()
+        .map(|c| c.id.as_str())
+        .filter(|id| !prefix.is_empty() && id.starts_with(prefix));
+    let mut matching: Vec<&str> = names.chain(ids).collect();
+    matching.sort();
+    matching.dedup();
+    matching
+}
+
+/// Id of the candidate named or identified by `id_or_name`.
+pub fn id_of(candidates: &[Candidate], id_or_name: &str) -> Option<String> {
+    candidates
+        .iter()
+        .find(|c| c.id == id_or_name || c.name == id_or_name)
+        .map(|c| c.id.clone())
+}
+
+/// Candidates of the list endpoint `path`, read from the cache when it was fetched less than
+/// CACHE_TTL ago. A stale cache is still used when fetching fails.
+pub fn cached(
+    path: &str,
+    fetch: impl FnOnce() -> Result<Vec<Candidate>, Box<dyn Error>>,
+) -> Result<Vec<Candidate>, Box<dyn Error>> {
+    let Some(file) = cache_file(path) else {
+        return fetch();
+    };
+    let cached: Option<Vec<Candidate>> = fs::read_to_string(&file)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok());
+    let fresh = fs::metadata(&file)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age < CACHE_TTL);
+    if let (true, Some(candidates)) = (fresh, &cached) {
+        return Ok(candidates.clone());
+    }
+    match fetch() {
+        Ok(candidates) => {
+            if let Some(dir) = file.parent() {
+                fs::create_dir_all(dir).ok();
+            }
+            if let Err(e) = fs::write(&file, serde_json::to_string(&candidates)?) {
+                info!("Unable to write {}: {}", file.display(), e);
+            }
+            Ok(candidates)
+        }
+        Err(e) => cached.ok_or(e),
+    }
+}
+
+/// `<cache dir>/completion/<region>-<token hash>/<path>.json`, the cache dir being
+/// KONNECTCTL_CACHE_DIR, $XDG_CACHE_HOME/konnectctl or ~/.cache/konnectctl. Tokens of different
+/// organizations in a region get different caches, and the token itself is not written.
+fn cache_file(path: &str) -> Option<PathBuf> {
+    dotenv::dotenv().ok();
+    let dir = env::var_os("KONNECTCTL_CACHE_DIR")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("XDG_CACHE_HOME").map(|d| PathBuf::from(d).join("konnectctl")))
+        .or_else(|| env::var_os("HOME").map(|d| PathBuf::from(d).join(".cache/konnectctl")))?;
+    let region = env::var("KONNECT_REGION").ok()?;
+    let token = env::var("KONNECT_PAT").ok()?;
+    let mut hasher = DefaultHasher::new();
+    token.hash(&mut hasher);
+    let account = format!("{}-{:016x}", region, hasher.finish());
+    let name = format!("{}.json", path.trim_matches('/').replace('/', "_"));
+    Some(dir.join("completion").join(account).join(name))
+}
+
+/// Prints the product, version or specification names completing the last of `words`. Nothing
+/// is printed when the word is not one of those or when Konnect cannot be reached.
+pub fn complete_values(words: &[String]) {
+    let Some(candidates) = candidates_for(words) else {
+        return;
+    };
+    let current = words.last().map(String::as_str).unwrap_or_default();
+    // Every request would exit on missing credentials, printing the error on each Tab
+    if let Err(e) = KonnectClient::try_new() {
+        info!("Unable to complete {}: {}", current, e);
+        return;
+    }
+    match completion_candidates(candidates) {
+        Ok(candidates) => {
+            for value in matching(&candidates, current) {
+                println!("{}", value);
+            }
+        }
+        Err(e) => info!("Unable to complete {}: {}", current, e),
+    }
+}
+
+/// Names and ids of the products, versions or specifications `candidates` asks for.
+fn completion_candidates(candidates: Candidates) -> Result<Vec<Candidate>, Box<dyn Error>> {
+    let products = cached("/api-products", || {
+        let products =
+            GetAPIProductsArrayRequest {}.fetch_all("/api-products", FetchFilter::None)?;
+        Ok(products
+            .into_iter()
+            .map(|p| Candidate {
+                name: p.name,
+                id: p.id,
+            })
+            .collect())
+    })?;
+    let (product, version) = match candidates {
+        Candidates::Products => return Ok(products),
+        Candidates::Versions { product } => (product, None),
+        Candidates::Specifications { product, version } => (product, Some(version)),
+    };
+    let product_id =
+        id_of(&products, &product).ok_or_else(|| format!("No API product named {}", product))?;
+    let versions_path = format!("/api-products/{}/product-versions", product_id);
+    let versions = cached(&versions_path, || {
+        let versions =
+            GetAllApiProductVersionRequest::new().fetch_all(&versions_path, FetchFilter::None)?;
+        Ok(versions
+            .into_iter()
+            .map(|v| Candidate {
+                name: v.name,
+                id: v.id,
+            })
+            .collect())
+    })?;
+    let Some(version) = version else {
+        return Ok(versions);
+    };
+    let version_id = id_of(&versions, &version)
+        .ok_or_else(|| format!("No API product version named {}", version))?;
+    let specs_path = format!("{}/{}/specifications", versions_path, version_id);
+    cached(&specs_path, || {
+        let specs =
+            GetAllAPIProductVersionSpecRequest::new().fetch_all(&specs_path, FetchFilter::None)?;
+        Ok(specs
+            .into_iter()
+            .map(|s| Candidate {
+                name: s.name,
+                id: s.id,
+            })
+            .collect())
+    })
+}
+
+/// Completion script for `shell` generated from `command`. Bash, zsh and fish scripts also ask
+/// `konnectctl __complete` for product, version and specification names.
+pub fn completion_script(command: &mut Command, shell: Shell) -> String {
+    let name = command.get_name().to_string();
+    let mut script = Vec::new();
+    clap_complete::generate(shell, command, &name, &mut script);
+    let mut script = String::from_utf8_lossy(&script).into_owned();
+    script.push_str(match shell {
+        Shell::Bash => BASH_DYNAMIC,
+        Shell::Zsh => ZSH_DYNAMIC,
+        Shell::Fish => FISH_DYNAMIC,
+        _ => "",
+    });
+    script
+}
+
+const BASH_DYNAMIC: &str = r#"
+_konnectctl_dynamic() {
+    local candidates
+    candidates=$(konnectctl __complete -- "${COMP_WORDS[@]:1:COMP_CWORD}" 2>/dev/null)
+    if [[ -n "${candidates}" ]]; then
+        local IFS=$'\n'
+        COMPREPLY=($(compgen -W "${candidates}" -- "${COMP_WORDS[COMP_CWORD]}"))
+        return 0
+    fi
+    _konnectctl "$@"
+}
+
+if [[ "${BASH_VERSINFO[0]}" -eq 4 && "${BASH_VERSINFO[1]}" -ge 4 || "${BASH_VERSINFO[0]}" -gt 4 ]]; then
+    complete -F _konnectctl_dynamic -o nosort -o bashdefault -o default konnectctl
+else
+    complete -F _konnectctl_dynamic -o bashdefault -o default konnectctl
+fi
+"#;
+
+const ZSH_DYNAMIC: &str = r#"
+_konnectctl_dynamic() {
+    local -a candidates
+    candidates=("${(@f)$(konnectctl __complete -- "${(@)words[2,$CURRENT]}" 2>/dev/null)}")
+    if [[ -n "${candidates[1]}" ]]; then
+        compadd -a candidates
+        return
+    fi
+    _konnectctl "$@"
+}
+
+compdef _konnectctl_dynamic konnectctl
+"#;
+
+const FISH_DYNAMIC: &str = r#"
+function __konnectctl_names
+    konnectctl __complete -- (commandline -opc)[2..-1] (commandline -ct) 2>/dev/null
+end
+
+complete -c konnectctl -s p -l product-id -l product -f -a '(__konnectctl_names)'
+complete -c konnectctl -s v -l product-version-id -l version -f -a '(__konnectctl_names)'
+complete -c konnectctl -l against-version-id -l against-version -f -a '(__konnectctl_names)'
+complete -c konnectctl -l against-id -f -a '(__konnectctl_names)'
+complete -c konnectctl -s i -l id -f -a '(__konnectctl_names)'
+"#;
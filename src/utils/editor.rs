@@ -0,0 +1,124 @@
+use std::{env, error::Error, fs, path::Path, process::Command};
+
+use serde_json::{Map, Value};
+
+/// The editor to open, as a shell command the file path is appended to.
+fn editor_command() -> String {
+    ["KONNECTCTL_EDITOR", "EDITOR"]
+        .iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string())
+}
+
+/// Opens `path` in the editor and waits for it to exit. The editor goes through the shell so it
+/// can take arguments, e.g. EDITOR="code --wait".
+fn open_in_editor(path: &Path) -> Result<(), Box<dyn Error>> {
+    let editor = editor_command();
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$1\"", editor))
+        .arg("sh")
+        .arg(path)
+        .status()
+        .map_err(|e| format!("Unable to start the editor {}: {}", editor, e))?;
+    if !status.success() {
+        return Err(format!("The editor {} exited with {}", editor, status).into());
+    }
+    Ok(())
+}
+
+/// `content` without its comment lines, those starting with #.
+fn strip_comments(content: &str) -> String {
+    content
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .map(|line| format!("{}\n", line))
+        .collect()
+}
+
+/// The JSON merge patch (RFC 7396) turning `original` into `edited`: changed fields with their
+/// new value and removed fields set to null, objects compared field by field.
+pub fn merge_patch(original: &Value, edited: &Value) -> Value {
+    let (Value::Object(original), Value::Object(edited)) = (original, edited) else {
+        return edited.clone();
+    };
+    let mut patch = Map::new();
+    for (key, value) in edited {
+        match original.get(key) {
+            Some(old) if old == value => {}
+            Some(old) => {
+                patch.insert(key.clone(), merge_patch(old, value));
+            }
+            None => {
+                patch.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    for key in original.keys().filter(|key| !edited.contains_key(*key)) {
+        patch.insert(key.clone(), Value::Null);
+    }
+    Value::Object(patch)
+}
+
+/// Opens `original` as YAML in the editor under the `header` comment and hands what changed to
+/// `save` as a merge patch. When the file cannot be parsed or `save` fails, the editor is opened
+/// again with the error on top of the edited file. Returns false when the edit was cancelled by
+/// emptying the file or by changing nothing.
+pub fn edit_yaml(
+    header: &str,
+    original: &Value,
+    mut save: impl FnMut(Value) -> Result<(), Box<dyn Error>>,
+) -> Result<bool, Box<dyn Error>> {
+    let path = env::temp_dir().join(format!("konnectctl-edit-{}.yaml", std::process::id()));
+    let mut body = serde_yaml::to_string(original)?;
+    let mut error: Option<String> = None;
+    loop {
+        let mut content: String = header.lines().map(|l| format!("# {}\n", l)).collect();
+        if let Some(error) = &error {
+            content.push_str("#\n");
+            content.extend(error.lines().map(|l| format!("# {}\n", l)));
+        }
+        content.push_str(&body);
+        fs::write(&path, content)
+            .map_err(|e| format!("Unable to write {}: {}", path.display(), e))?;
+        open_in_editor(&path)?;
+        let edited = strip_comments(
+            &fs::read_to_string(&path)
+                .map_err(|e| format!("Unable to read {}: {}", path.display(), e))?,
+        );
+
+        if edited.trim().is_empty() {
+            fs::remove_file(&path).ok();
+            return Ok(false);
+        }
+        if let Some(error) = error.as_ref().filter(|_| edited == body) {
+            return Err(format!(
+                "Edit failed, nothing was changed: {}\nYour changes are kept in {}",
+                error,
+                path.display()
+            )
+            .into());
+        }
+        body = edited;
+        let value: Value = match serde_yaml::from_str(&body) {
+            Ok(value) => value,
+            Err(e) => {
+                error = Some(format!("The file is not valid YAML: {}", e));
+                continue;
+            }
+        };
+        let patch = merge_patch(original, &value);
+        if patch.as_object().is_some_and(Map::is_empty) {
+            fs::remove_file(&path).ok();
+            return Ok(false);
+        }
+        match save(patch) {
+            Ok(()) => {
+                fs::remove_file(&path).ok();
+                return Ok(true);
+            }
+            Err(e) => error = Some(e.to_string()),
+        }
+    }
+}
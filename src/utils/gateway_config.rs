@@ -0,0 +1,272 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+const OPENAPI_METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+const PLUGIN_PREFIX: &str = "x-kong-plugin-";
+
+/// Kong declarative configuration, as read by decK.
+#[derive(Debug, Serialize)]
+pub struct DeclarativeConfig {
+    #[serde(rename = "_format_version")]
+    pub format_version: String,
+    pub services: Vec<ServiceConfig>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ServiceConfig {
+    pub name: String,
+    pub url: String,
+    /// Fields taken from `x-kong-service-defaults`
+    #[serde(flatten)]
+    pub defaults: Map<String, Value>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub plugins: Vec<PluginConfig>,
+    pub routes: Vec<RouteConfig>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RouteConfig {
+    pub name: String,
+    pub methods: Vec<String>,
+    pub paths: Vec<String>,
+    pub strip_path: bool,
+    /// Fields taken from `x-kong-route-defaults`
+    #[serde(flatten)]
+    pub defaults: Map<String, Value>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub plugins: Vec<PluginConfig>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginConfig {
+    pub name: String,
+    #[serde(flatten)]
+    pub fields: Map<String, Value>,
+}
+
+#[derive(Debug, Default)]
+pub struct GenerateOptions {
+    pub service_name: Option<String>,
+    pub upstream_url: Option<String>,
+    pub with_plugins: bool,
+}
+
+/// Turns an OpenAPI document into a Kong service with one route per operation. `x-kong-name`,
+/// `x-kong-service-defaults`, `x-kong-route-defaults` and `x-kong-plugin-<name>` extensions are
+/// honoured at the document, path and operation level, the deepest one winning.
+pub fn generate_gateway_config(
+    document: &Value,
+    options: &GenerateOptions,
+) -> Result<DeclarativeConfig, String> {
+    if document.get("openapi").is_none() && document.get("swagger").is_none() {
+        return Err("gateway configuration can only be generated from OpenAPI specs".to_string());
+    }
+    let service_name = match &options.service_name {
+        Some(n) => slugify(n),
+        None => document
+            .get("x-kong-name")
+            .and_then(|n| n.as_str())
+            .or_else(|| document.pointer("/info/title").and_then(|t| t.as_str()))
+            .map(slugify)
+            .ok_or("the spec has no x-kong-name or info.title to name the service after")?,
+    };
+    let url = match &options.upstream_url {
+        Some(u) => u.clone(),
+        None => upstream_url(document)?,
+    };
+
+    let mut routes = Vec::new();
+    if let Some(paths) = document.get("paths").and_then(|p| p.as_object()) {
+        for (path, item) in paths {
+            for method in OPENAPI_METHODS {
+                let op = match item.get(method) {
+                    Some(op) => op,
+                    None => continue,
+                };
+                let route_name = match extension_name(op)
+                    .or_else(|| op.get("operationId").and_then(|o| o.as_str()).map(slugify))
+                {
+                    Some(n) => format!("{}_{}", service_name, n),
+                    None => format!(
+                        "{}_{}{}",
+                        service_name,
+                        method,
+                        slugify(path).replace('-', "_")
+                    ),
+                };
+                let mut defaults = object_extension(document, "x-kong-route-defaults");
+                defaults.extend(object_extension(item, "x-kong-route-defaults"));
+                defaults.extend(object_extension(op, "x-kong-route-defaults"));
+                let strip_path = defaults
+                    .remove("strip_path")
+                    .and_then(|s| s.as_bool())
+                    .unwrap_or(false);
+                let plugins = if options.with_plugins {
+                    merge_plugins(&[item, op])
+                } else {
+                    Vec::new()
+                };
+                routes.push(RouteConfig {
+                    name: route_name,
+                    methods: vec![method.to_uppercase()],
+                    paths: vec![path_to_route_regex(path)],
+                    strip_path,
+                    defaults,
+                    plugins,
+                });
+            }
+        }
+    }
+    if routes.is_empty() {
+        return Err("the spec does not declare any operation to create routes for".to_string());
+    }
+
+    let plugins = if options.with_plugins {
+        merge_plugins(&[document])
+    } else {
+        Vec::new()
+    };
+    Ok(DeclarativeConfig {
+        format_version: "3.0".to_string(),
+        services: vec![ServiceConfig {
+            name: service_name,
+            url,
+            defaults: object_extension(document, "x-kong-service-defaults"),
+            plugins,
+            routes,
+        }],
+    })
+}
+
+fn extension_name(value: &Value) -> Option<String> {
+    value
+        .get("x-kong-name")
+        .and_then(|n| n.as_str())
+        .map(slugify)
+}
+
+fn object_extension(value: &Value, name: &str) -> Map<String, Value> {
+    value
+        .get(name)
+        .and_then(|v| v.as_object())
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Collects `x-kong-plugin-<name>` extensions, later levels overriding earlier ones.
+fn merge_plugins(levels: &[&Value]) -> Vec<PluginConfig> {
+    let mut plugins: Vec<PluginConfig> = Vec::new();
+    for level in levels {
+        let Some(map) = level.as_object() else {
+            continue;
+        };
+        for (key, value) in map {
+            let Some(name) = key.strip_prefix(PLUGIN_PREFIX) else {
+                continue;
+            };
+            let mut fields = value.as_object().cloned().unwrap_or_default();
+            fields.remove("name");
+            let plugin = PluginConfig {
+                name: name.to_string(),
+                fields,
+            };
+            match plugins.iter_mut().find(|p| p.name == name) {
+                Some(existing) => *existing = plugin,
+                None => plugins.push(plugin),
+            }
+        }
+    }
+    plugins
+}
+
+/// Works out the upstream url from `servers` (OpenAPI 3) or `host`/`basePath` (Swagger 2).
+fn upstream_url(document: &Value) -> Result<String, String> {
+    if let Some(server) = document.pointer("/servers/0") {
+        let mut url = server
+            .get("url")
+            .and_then(|u| u.as_str())
+            .unwrap_or_default()
+            .to_string();
+        if let Some(vars) = server.get("variables").and_then(|v| v.as_object()) {
+            for (name, var) in vars {
+                if let Some(default) = var.get("default").and_then(|d| d.as_str()) {
+                    url = url.replace(&format!("{{{}}}", name), default);
+                }
+            }
+        }
+        if url.starts_with("http://") || url.starts_with("https://") {
+            return Ok(url);
+        }
+        return Err(format!(
+            "servers[0].url {} is not an absolute url, pass --upstream-url",
+            url
+        ));
+    }
+    if let Some(host) = document.get("host").and_then(|h| h.as_str()) {
+        let scheme = document
+            .pointer("/schemes/0")
+            .and_then(|s| s.as_str())
+            .unwrap_or("https");
+        let base_path = document
+            .get("basePath")
+            .and_then(|b| b.as_str())
+            .unwrap_or_default();
+        return Ok(format!("{}://{}{}", scheme, host, base_path));
+    }
+    Err(
+        "the spec has no servers (or host) to take the upstream url from, pass --upstream-url"
+            .to_string(),
+    )
+}
+
+/// Converts an OpenAPI path template into a Kong regex route path, e.g. `/pets/{petId}` becomes
+/// `~/pets/(?<petId>[^#?/]+)$`.
+pub fn path_to_route_regex(path: &str) -> String {
+    let mut regex = String::from("~");
+    let mut rest = path;
+    while let Some(start) = rest.find('{') {
+        regex.push_str(&escape_regex(&rest[..start]));
+        match rest[start..].find('}') {
+            Some(end) => {
+                let name: String = rest[start + 1..start + end]
+                    .chars()
+                    .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                    .collect();
+                regex.push_str(&format!("(?<{}>[^#?/]+)", name));
+                rest = &rest[start + end + 1..];
+            }
+            None => {
+                regex.push_str(&escape_regex(&rest[start..]));
+                rest = "";
+            }
+        }
+    }
+    regex.push_str(&escape_regex(rest));
+    regex.push('$');
+    regex
+}
+
+fn escape_regex(s: &str) -> String {
+    let mut escaped = String::new();
+    for c in s.chars() {
+        if ".+*?()|[]{}^$\\".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+pub fn slugify(s: &str) -> String {
+    let mut slug = String::new();
+    for c in s.trim().chars() {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_matches('-').to_string()
+}
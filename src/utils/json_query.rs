@@ -0,0 +1,400 @@
+use std::{str::FromStr, sync::OnceLock};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::api::api_utils::ToJson;
+
+static QUERY: OnceLock<Query> = OnceLock::new();
+
+/// Sets the query applied to JSON output, once at startup.
+pub fn set_query(query: Query) {
+    QUERY.set(query).ok();
+}
+
+pub fn query() -> Option<&'static Query> {
+    QUERY.get()
+}
+
+/// Prints `value` as JSON, or what the query set with `set_query` selects from it.
+pub fn print<T: Serialize>(value: &T) -> Result<(), String> {
+    let Some(query) = query() else {
+        println!("{}", value.to_json());
+        return Ok(());
+    };
+    let selected = serde_json::to_value(value)
+        .map_err(|e| e.to_string())
+        .and_then(|value| query.apply(&value))
+        .map_err(|e| format!("Unable to apply the query: {}", e))?;
+    print!("{}", render(&selected));
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    Field(String),
+    Index(i64),
+    Iterate,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Stage {
+    Path(Vec<Step>),
+    Length,
+    Keys,
+    Select {
+        path: Vec<Step>,
+        equals: bool,
+        value: Value,
+    },
+}
+
+/// A subset of jq: paths such as `.data[0].id`, `.data[]` and `.["a key"]`, `length`, `keys` and
+/// `select(<path> == <value>)` or `!=`, joined with `|`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    stages: Vec<Stage>,
+}
+
+impl FromStr for Query {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            chars: s.chars().collect(),
+            pos: 0,
+        };
+        let mut stages = vec![parser.stage()?];
+        loop {
+            parser.skip_spaces();
+            match parser.next() {
+                None => return Ok(Query { stages }),
+                Some('|') => stages.push(parser.stage()?),
+                Some(_) => {
+                    parser.pos -= 1;
+                    return Err(parser.error("| or the end of the query"));
+                }
+            }
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.pos += 1;
+        c
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn rest(&self) -> String {
+        self.chars[self.pos.min(self.chars.len())..]
+            .iter()
+            .collect()
+    }
+
+    fn error(&self, expected: &str) -> String {
+        match self.peek() {
+            Some(_) => format!(
+                "expected {} at {}, found {}",
+                expected,
+                self.pos,
+                self.rest()
+            ),
+            None => format!("expected {} at the end", expected),
+        }
+    }
+
+    fn word(&mut self) -> String {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '-')
+        {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    fn stage(&mut self) -> Result<Stage, String> {
+        self.skip_spaces();
+        if self.peek() == Some('.') {
+            return Ok(Stage::Path(self.path()?));
+        }
+        match self.word().as_str() {
+            "length" => Ok(Stage::Length),
+            "keys" => Ok(Stage::Keys),
+            "select" => self.select(),
+            "" => Err(self.error("a path, length, keys or select")),
+            other => Err(format!(
+                "{} is not supported, use a path, length, keys or select",
+                other
+            )),
+        }
+    }
+
+    fn select(&mut self) -> Result<Stage, String> {
+        self.skip_spaces();
+        if self.next() != Some('(') {
+            return Err(self.error("( after select"));
+        }
+        self.skip_spaces();
+        let path = self.path()?;
+        self.skip_spaces();
+        let operator = self.pos;
+        let equals = match (self.next(), self.next()) {
+            (Some('='), Some('=')) => true,
+            (Some('!'), Some('=')) => false,
+            _ => {
+                self.pos = operator;
+                return Err(self.error("== or != in select"));
+            }
+        };
+        self.skip_spaces();
+        let value = self.literal()?;
+        self.skip_spaces();
+        if self.next() != Some(')') {
+            return Err(self.error(") closing select"));
+        }
+        Ok(Stage::Select {
+            path,
+            equals,
+            value,
+        })
+    }
+
+    /// A JSON string, number, true, false or null.
+    fn literal(&mut self) -> Result<Value, String> {
+        let start = self.pos;
+        if self.peek() == Some('"') {
+            self.string()?;
+        } else {
+            while self
+                .peek()
+                .is_some_and(|c| c.is_alphanumeric() || c == '.' || c == '-' || c == '+')
+            {
+                self.pos += 1;
+            }
+        }
+        let literal: String = self.chars[start..self.pos].iter().collect();
+        serde_json::from_str(&literal).map_err(|_| {
+            self.pos = start;
+            self.error("a string, number, true, false or null")
+        })
+    }
+
+    /// A double quoted string, with JSON escapes.
+    fn string(&mut self) -> Result<String, String> {
+        let start = self.pos;
+        self.pos += 1;
+        loop {
+            match self.next() {
+                Some('\\') => self.pos += 1,
+                Some('"') => break,
+                Some(_) => {}
+                None => return Err(format!("unterminated string at {}", start)),
+            }
+        }
+        let quoted: String = self.chars[start..self.pos].iter().collect();
+        serde_json::from_str(&quoted).map_err(|e| format!("invalid string at {}: {}", start, e))
+    }
+
+    fn path(&mut self) -> Result<Vec<Step>, String> {
+        if self.next() != Some('.') {
+            self.pos -= 1;
+            return Err(self.error("a path starting with ."));
+        }
+        let mut steps = Vec::new();
+        match self.peek() {
+            Some('"') => steps.push(Step::Field(self.string()?)),
+            Some(c) if c.is_alphabetic() || c == '_' => steps.push(Step::Field(self.word())),
+            _ => {}
+        }
+        loop {
+            match self.peek() {
+                Some('.') => {
+                    self.pos += 1;
+                    match self.peek() {
+                        Some('"') => steps.push(Step::Field(self.string()?)),
+                        Some('[') => {}
+                        _ => match self.word() {
+                            field if field.is_empty() => return Err(self.error("a field name")),
+                            field => steps.push(Step::Field(field)),
+                        },
+                    }
+                }
+                Some('[') => {
+                    self.pos += 1;
+                    self.skip_spaces();
+                    let step = match self.peek() {
+                        Some(']') => Step::Iterate,
+                        Some('"') => Step::Field(self.string()?),
+                        None => return Err(self.error("an index or ]")),
+                        _ => {
+                            let index = self.literal()?;
+                            Step::Index(index.as_i64().ok_or_else(|| {
+                                format!("{} is not a valid index, use a whole number", index)
+                            })?)
+                        }
+                    };
+                    self.skip_spaces();
+                    if self.next() != Some(']') {
+                        self.pos -= 1;
+                        return Err(self.error("]"));
+                    }
+                    steps.push(step);
+                }
+                _ => return Ok(steps),
+            }
+        }
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn apply_step(step: &Step, value: &Value) -> Result<Vec<Value>, String> {
+    match (step, value) {
+        (Step::Field(_) | Step::Index(_), Value::Null) => Ok(vec![Value::Null]),
+        (Step::Field(field), Value::Object(map)) => {
+            Ok(vec![map.get(field).cloned().unwrap_or(Value::Null)])
+        }
+        (Step::Index(index), Value::Array(items)) => {
+            let position = if *index < 0 {
+                items.len() as i64 + index
+            } else {
+                *index
+            };
+            Ok(vec![usize::try_from(position)
+                .ok()
+                .and_then(|p| items.get(p))
+                .cloned()
+                .unwrap_or(Value::Null)])
+        }
+        (Step::Iterate, Value::Array(items)) => Ok(items.clone()),
+        (Step::Iterate, Value::Object(map)) => Ok(map.values().cloned().collect()),
+        (Step::Field(field), _) => Err(format!(
+            "Cannot index {} with \"{}\"",
+            type_name(value),
+            field
+        )),
+        (Step::Index(_), _) => Err(format!("Cannot index {} with a number", type_name(value))),
+        (Step::Iterate, _) => Err(format!("Cannot iterate over {}", type_name(value))),
+    }
+}
+
+fn apply_path(steps: &[Step], value: &Value) -> Result<Vec<Value>, String> {
+    let mut values = vec![value.clone()];
+    for step in steps {
+        let mut next = Vec::new();
+        for value in &values {
+            next.extend(apply_step(step, value)?);
+        }
+        values = next;
+    }
+    Ok(values)
+}
+
+fn apply_stage(stage: &Stage, value: &Value) -> Result<Vec<Value>, String> {
+    match stage {
+        Stage::Path(steps) => apply_path(steps, value),
+        Stage::Length => {
+            let length = match value {
+                Value::Null => 0.into(),
+                Value::String(s) => s.chars().count().into(),
+                Value::Array(items) => items.len().into(),
+                Value::Object(map) => map.len().into(),
+                Value::Number(n) => n.as_f64().map(f64::abs).into(),
+                Value::Bool(_) => return Err("boolean has no length".to_string()),
+            };
+            Ok(vec![length])
+        }
+        Stage::Keys => match value {
+            Value::Object(map) => {
+                let mut keys: Vec<String> = map.keys().cloned().collect();
+                keys.sort();
+                Ok(vec![keys.into()])
+            }
+            Value::Array(items) => Ok(vec![(0..items.len()).collect::<Vec<_>>().into()]),
+            _ => Err(format!("{} has no keys", type_name(value))),
+        },
+        Stage::Select {
+            path,
+            equals,
+            value: expected,
+        } => {
+            let selected = apply_path(path, value)?
+                .iter()
+                .any(|v| (v == expected) == *equals);
+            Ok(if selected {
+                vec![value.clone()]
+            } else {
+                vec![]
+            })
+        }
+    }
+}
+
+impl Query {
+    /// The values the query selects from `value`, in order.
+    pub fn apply(&self, value: &Value) -> Result<Vec<Value>, String> {
+        let mut values = vec![value.clone()];
+        for stage in &self.stages {
+            let mut next = Vec::new();
+            for value in &values {
+                next.extend(apply_stage(stage, value)?);
+            }
+            values = next;
+        }
+        Ok(values)
+    }
+}
+
+/// The query given with --query applied to `value`, several results are returned as an array.
+/// `value` is returned as is without a query.
+pub fn select(value: Value) -> Result<Value, String> {
+    let Some(query) = query() else {
+        return Ok(value);
+    };
+    let mut values = query.apply(&value)?;
+    Ok(match values.len() {
+        1 => values.remove(0),
+        _ => Value::Array(values),
+    })
+}
+
+/// One value per line, strings without quotes and anything else as pretty JSON.
+pub fn render(values: &[Value]) -> String {
+    values
+        .iter()
+        .map(|value| match value {
+            Value::String(s) => format!("{}\n", s),
+            other => format!(
+                "{}\n",
+                serde_json::to_string_pretty(other).unwrap_or_default()
+            ),
+        })
+        .collect()
+}
@@ -1,7 +1,10 @@
+use clap::ValueEnum;
 use dotenv::dotenv;
 use log::{error, info};
 use serde_json::Value;
-use std::{env, error::Error};
+use std::{env, error::Error, fmt, sync::OnceLock};
+
+use super::label_selector::{LabelSelector, Requirement};
 
 #[derive(Debug)]
 pub struct KonnectClient {
@@ -10,11 +13,68 @@ pub struct KonnectClient {
     client: reqwest::blocking::Client,
 }
 
+/// How requests that would change Konnect are printed instead of being sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DryRun {
+    Http,
+    Curl,
+}
+
+static DRY_RUN: OnceLock<DryRun> = OnceLock::new();
+
+/// Makes every create, patch and delete of this run print its request instead of sending it.
+pub fn set_dry_run(dry_run: DryRun) {
+    DRY_RUN.set(dry_run).ok();
+}
+
+pub fn dry_run() -> Option<DryRun> {
+    DRY_RUN.get().copied()
+}
+
+/// The error of a create or patch that --dry-run printed instead of sending. There is no
+/// response to go on with, commands that need one print what they would do instead.
+#[derive(Debug)]
+pub struct NotSent;
+
+impl fmt::Display for NotSent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the request was not sent, this is a dry run")
+    }
+}
+
+impl Error for NotSent {}
+
+#[derive(Clone)]
 pub enum FetchFilter {
     FieldName(String, String),
+    /// Equality clauses are sent as `filter[labels.<name>]`, the others have to be checked by
+    /// the caller
+    Labels(LabelSelector),
     None,
 }
 
+impl FetchFilter {
+    pub fn to_query(&self) -> Vec<(String, String)> {
+        match self {
+            FetchFilter::FieldName(filter_field_name, field_value) => vec![(
+                format!("filter[{}]", filter_field_name),
+                field_value.to_string(),
+            )],
+            FetchFilter::Labels(selector) => selector
+                .requirements
+                .iter()
+                .filter_map(|r| match r {
+                    Requirement::Equals(name, value) => {
+                        Some((format!("filter[labels.{}]", name), value.to_string()))
+                    }
+                    _ => None,
+                })
+                .collect(),
+            FetchFilter::None => Vec::new(),
+        }
+    }
+}
+
 pub fn filter_empty_fields(value: Value) -> Value {
     match value {
         Value::Object(map) => {
@@ -40,41 +100,98 @@ pub fn filter_empty_fields(value: Value) -> Value {
 }
 
 impl KonnectClient {
+    /// A client for the token and region of the environment, exiting when they are not set.
     pub fn new() -> Self {
+        Self::try_new().unwrap_or_else(|e| {
+            error!("{}", e);
+            std::process::exit(1)
+        })
+    }
+
+    /// A client for the token and region of the environment, or why there is none.
+    pub fn try_new() -> Result<Self, Box<dyn Error>> {
         dotenv().ok();
 
-        let auth_token = env::var("KONNECT_PAT").expect("KONNECT_PAT must be set");
-        let konnect_region = env::var("KONNECT_REGION");
-        let region = match konnect_region {
+        let auth_token = env::var("KONNECT_PAT")
+            .map_err(|e| format!("Cant find env variable KONNECT_PAT: {}", e))?;
+        let region = match env::var("KONNECT_REGION") {
+            Ok(r) if ["us", "au", "eu"].contains(&r.as_str()) => {
+                info!("Region: {}", &r);
+                r
+            }
             Ok(r) => {
-                if ["us", "au", "eu"].contains(&r.as_str()) {
-                    info!("Region: {}", &r);
+                return Err(format!(
+                    "Invalid region: {}, it can can only 'eu' or 'au', or 'us'",
                     r
-                } else {
-                    error!(
-                        "Invalid region: {}, it can can only 'eu' or 'au', or 'us'",
-                        r
-                    );
-                    std::process::exit(1);
-                }
+                )
+                .into())
             }
             Err(e) => {
-                error!("Cant find env variable KONNECT_REGION: {}, it can can only 'eu' or 'au', or 'us'", e);
-                std::process::exit(1)
+                return Err(format!(
+                "Cant find env variable KONNECT_REGION: {}, it can can only 'eu' or 'au', or 'us'",
+                e
+            )
+                .into())
             }
         };
 
-        let url = format!("https://{}.api.konghq.com/v2", &region);
+        // KONNECT_API_URL points the client elsewhere, e.g. at a proxy or a test server
+        let url = env::var("KONNECT_API_URL")
+            .map(|url| url.trim_end_matches('/').to_string())
+            .unwrap_or_else(|_| format!("https://{}.api.konghq.com/v2", &region));
         let client = reqwest::blocking::Client::new();
 
-        Self {
+        Ok(Self {
             url,
             auth_token,
             client,
+        })
+    }
+
+    /// Prints the request as --dry-run asked, the token being left out.
+    fn print_request(&self, dry_run: DryRun, method: &str, path: &str, body: Option<Value>) {
+        let url = format!("{}{}", self.url, path);
+        let mut request = String::new();
+        match dry_run {
+            DryRun::Http => {
+                request.push_str(&format!("{} {}\n", method, url));
+                request.push_str("authorization: Bearer <redacted>\n");
+                if let Some(body) = &body {
+                    request.push_str("content-type: application/json\n\n");
+                    request.push_str(&serde_json::to_string_pretty(body).unwrap());
+                    request.push('\n');
+                }
+            }
+            DryRun::Curl => {
+                request.push_str(&format!("curl -X {} {}", method, shell_quote(&url)));
+                request.push_str(" \\\n  -H \"authorization: Bearer ${KONNECT_PAT}\"");
+                if let Some(body) = &body {
+                    request.push_str(" \\\n  -H 'content-type: application/json'");
+                    request.push_str(&format!(
+                        " \\\n  --data-raw {}",
+                        shell_quote(&body.to_string())
+                    ));
+                }
+                request.push('\n');
+            }
         }
+        println!("{}", request);
+    }
+
+    /// Under --dry-run, prints a create or patch and returns true, the request is not sent.
+    fn dry_run_write<T: serde::Serialize>(&self, method: &str, path: &str, body: &T) -> bool {
+        let Some(dry_run) = dry_run() else {
+            return false;
+        };
+        self.print_request(dry_run, method, path, Some(serde_json::json!(body)));
+        true
     }
 
     pub fn delete(&self, path: &str) -> Result<String, Box<dyn Error>> {
+        if let Some(dry_run) = dry_run() {
+            self.print_request(dry_run, "DELETE", path, None);
+            return Ok("not sent (dry run)".to_string());
+        }
         let response = self
             .client
             .delete(format!("{}{}", self.url, path))
@@ -85,26 +202,25 @@ impl KonnectClient {
             let data = response.status().as_str().to_string();
             Ok(data)
         } else {
-            Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                response.text().unwrap(),
-            )))
+            Err(Box::new(std::io::Error::other(response.text().unwrap())))
         }
     }
 
     pub fn fetch(&self, path: &str, filter: FetchFilter) -> Result<String, Box<dyn Error>> {
+        self.fetch_with_query(path, &filter.to_query())
+    }
+
+    pub fn fetch_with_query(
+        &self,
+        path: &str,
+        query: &[(String, String)],
+    ) -> Result<String, Box<dyn Error>> {
         let response = self
             .client
             .get(format!("{}{}", self.url, path))
-            .header("authorization", format!("Bearer {}", self.auth_token));
-
-        let response = match filter {
-            FetchFilter::FieldName(filter_field_name, field_value) => {
-                response.query(&[(&format!("filter[{}]", filter_field_name), field_value)])
-            }
-            FetchFilter::None => response,
-        }
-        .send()?;
+            .header("authorization", format!("Bearer {}", self.auth_token))
+            .query(query)
+            .send()?;
 
         info!("Response status: {}", response.status());
 
@@ -112,10 +228,7 @@ impl KonnectClient {
             let data = response.text().unwrap();
             Ok(data)
         } else {
-            Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                response.text().unwrap(),
-            )))
+            Err(Box::new(std::io::Error::other(response.text().unwrap())))
         }
     }
 
@@ -125,6 +238,9 @@ impl KonnectClient {
         body: T,
     ) -> Result<String, Box<dyn Error>> {
         info!("request body: {}", serde_json::json!(&body));
+        if self.dry_run_write("POST", path, &body) {
+            return Err(Box::new(NotSent));
+        }
         let response = self
             .client
             .post(format!("{}{}", self.url, path))
@@ -136,10 +252,7 @@ impl KonnectClient {
             return Ok(data);
         }
 
-        Err(Box::new(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            response.text().unwrap(),
-        )))
+        Err(Box::new(std::io::Error::other(response.text().unwrap())))
     }
 
     pub fn patch<T: serde::Serialize>(
@@ -148,6 +261,9 @@ impl KonnectClient {
         body: T,
     ) -> Result<String, Box<dyn Error>> {
         info!("request body: {}", serde_json::json!(&body));
+        if self.dry_run_write("PATCH", path, &body) {
+            return Err(Box::new(NotSent));
+        }
         let response = self
             .client
             .patch(format!("{}{}", self.url, path))
@@ -159,9 +275,11 @@ impl KonnectClient {
             return Ok(data);
         }
 
-        Err(Box::new(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            response.text().unwrap(),
-        )))
+        Err(Box::new(std::io::Error::other(response.text().unwrap())))
     }
 }
+
+/// `value` quoted for a POSIX shell.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
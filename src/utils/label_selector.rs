@@ -0,0 +1,92 @@
+use std::{fmt, str::FromStr};
+
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Requirement {
+    Equals(String, String),
+    NotEquals(String, String),
+    Exists(String),
+    NotExists(String),
+}
+
+/// Kubernetes style label selector, e.g. `team=payments,env!=prod,!deprecated`. Every clause must
+/// match, so the default selector without clauses matches everything.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LabelSelector {
+    pub requirements: Vec<Requirement>,
+}
+
+impl FromStr for LabelSelector {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut requirements = Vec::new();
+        for clause in s.split(',').map(|c| c.trim()) {
+            if clause.is_empty() {
+                return Err(format!("Invalid selector {}, it has an empty clause", s));
+            }
+            let requirement = if let Some((k, v)) = clause.split_once("!=") {
+                Requirement::NotEquals(k.trim().to_string(), v.trim().to_string())
+            } else if let Some((k, v)) = clause.split_once("==").or_else(|| clause.split_once('='))
+            {
+                Requirement::Equals(k.trim().to_string(), v.trim().to_string())
+            } else if let Some(k) = clause.strip_prefix('!') {
+                Requirement::NotExists(k.trim().to_string())
+            } else {
+                Requirement::Exists(clause.to_string())
+            };
+            let key = match &requirement {
+                Requirement::Equals(k, _)
+                | Requirement::NotEquals(k, _)
+                | Requirement::Exists(k)
+                | Requirement::NotExists(k) => k,
+            };
+            if key.is_empty() {
+                return Err(format!(
+                    "Invalid selector clause {}, the label name is empty",
+                    clause
+                ));
+            }
+            requirements.push(requirement);
+        }
+        Ok(LabelSelector { requirements })
+    }
+}
+
+impl fmt::Display for LabelSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let clauses: Vec<String> = self
+            .requirements
+            .iter()
+            .map(|r| match r {
+                Requirement::Equals(k, v) => format!("{}={}", k, v),
+                Requirement::NotEquals(k, v) => format!("{}!={}", k, v),
+                Requirement::Exists(k) => k.clone(),
+                Requirement::NotExists(k) => format!("!{}", k),
+            })
+            .collect();
+        write!(f, "{}", clauses.join(","))
+    }
+}
+
+impl LabelSelector {
+    /// True when only objects carrying a label can match: at least one clause is `key=value` or
+    /// `key`. Selectors made of `!=` and `!key` clauses alone also match unlabelled objects.
+    pub fn requires_a_label(&self) -> bool {
+        self.requirements
+            .iter()
+            .any(|r| matches!(r, Requirement::Equals(..) | Requirement::Exists(_)))
+    }
+
+    /// Checks the selector against a JSON object of labels. Missing labels never equal a value.
+    pub fn matches(&self, labels: &Value) -> bool {
+        let label = |k: &str| labels.get(k).and_then(|v| v.as_str());
+        self.requirements.iter().all(|r| match r {
+            Requirement::Equals(k, v) => label(k) == Some(v.as_str()),
+            Requirement::NotEquals(k, v) => label(k) != Some(v.as_str()),
+            Requirement::Exists(k) => label(k).is_some(),
+            Requirement::NotExists(k) => label(k).is_none(),
+        })
+    }
+}
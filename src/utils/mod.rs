@@ -1 +1,11 @@
-pub mod konnect_utils;
\ No newline at end of file
+pub mod completion;
+pub mod editor;
+pub mod gateway_config;
+pub mod json_query;
+pub mod konnect_utils;
+pub mod label_selector;
+pub mod spec_bundler;
+pub mod spec_diff;
+pub mod spec_validator;
+pub mod table;
+pub mod watch;
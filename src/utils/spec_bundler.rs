@@ -0,0 +1,356 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use log::info;
+use serde_json::{Map, Value};
+
+use super::spec_validator::{escape_pointer_token, parse_spec};
+
+/// Where a `$ref` was found, used to decide which `components` section a hoisted value goes to.
+/// The plural variants are the lists or maps holding parameters, responses and request bodies.
+#[derive(Clone, Copy, PartialEq)]
+enum Context {
+    Schema,
+    Parameter,
+    Parameters,
+    Response,
+    Responses,
+    RequestBody,
+    RequestBodies,
+    Other,
+}
+
+type RefKey = (PathBuf, String);
+
+struct Bundler {
+    root_file: PathBuf,
+    swagger2: bool,
+    documents: HashMap<PathBuf, Value>,
+    hoisted: HashMap<RefKey, String>,
+    taken_names: HashSet<(String, String)>,
+    components: Vec<(String, String, Value)>,
+    stack: Vec<RefKey>,
+    external_refs: usize,
+}
+
+#[derive(Debug)]
+pub struct BundledSpec {
+    pub content: String,
+    pub external_refs: usize,
+}
+
+/// Resolves every external `$ref` of the spec at `path` relative to the file it appears in.
+/// Referenced schemas, parameters, responses and request bodies are hoisted into `components`
+/// (`definitions`, `parameters` and `responses` for Swagger 2), anything else is inlined.
+/// Returns `None` when the spec has no external references so it can be uploaded untouched.
+pub fn bundle_spec(path: &str, content: &str) -> Result<Option<BundledSpec>, Box<dyn Error>> {
+    let root: Value =
+        parse_spec(content).map_err(|issue| format!("{} could not be parsed, {}", path, issue))?;
+    let root_file =
+        fs::canonicalize(path).map_err(|e| format!("Unable to read spec file {}: {}", path, e))?;
+
+    let mut bundler = Bundler {
+        root_file: root_file.clone(),
+        swagger2: root.get("swagger").is_some(),
+        documents: HashMap::new(),
+        hoisted: HashMap::new(),
+        taken_names: HashSet::new(),
+        components: Vec::new(),
+        stack: Vec::new(),
+        external_refs: 0,
+    };
+    bundler.reserve_existing_names(&root);
+    let mut bundled = bundler.walk(root, &root_file, Context::Other)?;
+    if bundler.external_refs == 0 {
+        return Ok(None);
+    }
+    info!(
+        "Resolved {} external reference(s) in {}",
+        bundler.external_refs, path
+    );
+    bundler.insert_components(&mut bundled);
+
+    let content = if content.trim_start().starts_with('{') {
+        serde_json::to_string_pretty(&bundled)?
+    } else {
+        serde_yaml::to_string(&bundled)?
+    };
+    Ok(Some(BundledSpec {
+        content,
+        external_refs: bundler.external_refs,
+    }))
+}
+
+fn section_of(context: Context, swagger2: bool) -> Option<&'static str> {
+    match (context, swagger2) {
+        (Context::Schema, false) => Some("schemas"),
+        (Context::Schema, true) => Some("definitions"),
+        (Context::Parameter, _) => Some("parameters"),
+        (Context::Response, _) => Some("responses"),
+        (Context::RequestBody, false) => Some("requestBodies"),
+        _ => None,
+    }
+}
+
+/// Returns the context of the value found under `key` of a value in `context`.
+fn child_context(context: Context, key: &str) -> Context {
+    match context {
+        // Everything below a schema (properties, allOf, items, ...) is a schema as well
+        Context::Schema => Context::Schema,
+        Context::Parameters => Context::Parameter,
+        Context::Responses => Context::Response,
+        Context::RequestBodies => Context::RequestBody,
+        _ => match key {
+            "schema" | "schemas" | "definitions" | "items" | "payload" => Context::Schema,
+            "parameters" => Context::Parameters,
+            "responses" => Context::Responses,
+            "requestBody" => Context::RequestBody,
+            "requestBodies" => Context::RequestBodies,
+            _ => Context::Other,
+        },
+    }
+}
+
+/// Context of the elements of an array found in `context`.
+fn element_context(context: Context) -> Context {
+    match context {
+        Context::Parameters => Context::Parameter,
+        other => other,
+    }
+}
+
+impl Bundler {
+    fn reserve_existing_names(&mut self, root: &Value) {
+        let sections = if self.swagger2 {
+            root.as_object()
+                .map(|m| {
+                    ["definitions", "parameters", "responses"]
+                        .iter()
+                        .filter_map(|s| m.get(*s).map(|v| (s.to_string(), v)))
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default()
+        } else {
+            root.get("components")
+                .and_then(|c| c.as_object())
+                .map(|m| m.iter().map(|(k, v)| (k.clone(), v)).collect())
+                .unwrap_or_default()
+        };
+        for (section, values) in sections {
+            if let Some(map) = values.as_object() {
+                for name in map.keys() {
+                    self.taken_names.insert((section.clone(), name.clone()));
+                }
+            }
+        }
+    }
+
+    fn load(&mut self, file: &Path) -> Result<Value, Box<dyn Error>> {
+        if let Some(doc) = self.documents.get(file) {
+            return Ok(doc.clone());
+        }
+        let content = fs::read_to_string(file)
+            .map_err(|e| format!("Unable to read referenced file {}: {}", file.display(), e))?;
+        let doc = parse_spec(&content)
+            .map_err(|issue| format!("{} could not be parsed, {}", file.display(), issue))?;
+        self.documents.insert(file.to_path_buf(), doc.clone());
+        Ok(doc)
+    }
+
+    fn unique_name(&mut self, section: &str, name: &str) -> String {
+        let base: String = name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        let mut candidate = base.clone();
+        let mut n = 2;
+        while self
+            .taken_names
+            .contains(&(section.to_string(), candidate.clone()))
+        {
+            candidate = format!("{}_{}", base, n);
+            n += 1;
+        }
+        self.taken_names
+            .insert((section.to_string(), candidate.clone()));
+        candidate
+    }
+
+    fn walk(
+        &mut self,
+        value: Value,
+        file: &Path,
+        context: Context,
+    ) -> Result<Value, Box<dyn Error>> {
+        match value {
+            Value::Object(map) => {
+                if let Some(Value::String(reference)) = map.get("$ref") {
+                    return self.resolve_ref(reference.clone(), map, file, context);
+                }
+                let mut walked = Map::new();
+                for (k, v) in map {
+                    let c = child_context(context, &k);
+                    walked.insert(k, self.walk(v, file, c)?);
+                }
+                Ok(Value::Object(walked))
+            }
+            Value::Array(items) => items
+                .into_iter()
+                .map(|v| self.walk(v, file, element_context(context)))
+                .collect::<Result<Vec<_>, _>>()
+                .map(Value::Array),
+            other => Ok(other),
+        }
+    }
+
+    fn local_ref(&self, section: &str, name: &str) -> String {
+        if self.swagger2 {
+            format!("#/{}/{}", section, escape_pointer_token(name))
+        } else {
+            format!("#/components/{}/{}", section, escape_pointer_token(name))
+        }
+    }
+
+    fn resolve_ref(
+        &mut self,
+        reference: String,
+        map: Map<String, Value>,
+        file: &Path,
+        context: Context,
+    ) -> Result<Value, Box<dyn Error>> {
+        if reference.starts_with("http://") || reference.starts_with("https://") {
+            return Ok(Value::Object(map));
+        }
+        let (file_part, fragment) = match reference.split_once('#') {
+            Some((f, p)) => (f.to_string(), p.to_string()),
+            None => (reference.clone(), String::new()),
+        };
+        let target_file = if file_part.is_empty() {
+            file.to_path_buf()
+        } else {
+            let joined = file.parent().unwrap_or(Path::new(".")).join(&file_part);
+            fs::canonicalize(&joined).map_err(|e| {
+                format!(
+                    "Unable to resolve $ref {} in {}: {}",
+                    reference,
+                    file.display(),
+                    e
+                )
+            })?
+        };
+        if target_file == self.root_file {
+            // References back into the root document stay local
+            let mut local = map;
+            local.insert("$ref".to_string(), Value::String(format!("#{}", fragment)));
+            return Ok(Value::Object(local));
+        }
+
+        self.external_refs += 1;
+        let key = (target_file.clone(), fragment.clone());
+        if let Some(local) = self.hoisted.get(&key) {
+            return Ok(Value::Object(ref_object(local)));
+        }
+
+        let document = self.load(&target_file)?;
+        let target = document
+            .pointer(&fragment)
+            .cloned()
+            .ok_or_else(|| format!("$ref {} in {} does not exist", reference, file.display()))?;
+
+        let tokens: Vec<&str> = fragment.split('/').skip(1).collect();
+        let hoist_to = match tokens.as_slice() {
+            ["components", section, name] => Some((section.to_string(), name.to_string())),
+            ["definitions" | "parameters" | "responses", name] if self.swagger2 => {
+                Some((tokens[0].to_string(), name.to_string()))
+            }
+            _ => section_of(context, self.swagger2).map(|section| {
+                let name = tokens.last().map(|t| t.to_string()).unwrap_or_else(|| {
+                    target_file
+                        .file_stem()
+                        .and_then(|s| s.to_str())
+                        .unwrap_or("component")
+                        .to_string()
+                });
+                (section.to_string(), name)
+            }),
+        };
+        let target_context = match &hoist_to {
+            Some((section, _)) => match section.as_str() {
+                "schemas" | "definitions" => Context::Schema,
+                "parameters" => Context::Parameter,
+                "responses" => Context::Response,
+                "requestBodies" => Context::RequestBody,
+                _ => Context::Other,
+            },
+            None => context,
+        };
+
+        match hoist_to {
+            Some((section, name)) => {
+                let name = self.unique_name(&section, &name);
+                let local = self.local_ref(&section, &name);
+                // Registered before walking so that recursive schemas point at themselves
+                self.hoisted.insert(key, local.clone());
+                let walked = self.walk(target, &target_file, target_context)?;
+                self.components.push((section, name, walked));
+                Ok(Value::Object(ref_object(&local)))
+            }
+            None => {
+                if self.stack.contains(&key) {
+                    let chain: Vec<String> = self
+                        .stack
+                        .iter()
+                        .chain(std::iter::once(&key))
+                        .map(|(f, p)| match p.is_empty() {
+                            true => f.display().to_string(),
+                            false => format!("{}#{}", f.display(), p),
+                        })
+                        .collect();
+                    return Err(format!("circular $ref detected: {}", chain.join(" -> ")).into());
+                }
+                self.stack.push(key);
+                let walked = self.walk(target, &target_file, target_context);
+                self.stack.pop();
+                walked
+            }
+        }
+    }
+
+    fn insert_components(&mut self, root: &mut Value) {
+        let Some(root) = root.as_object_mut() else {
+            return;
+        };
+        for (section, name, value) in self.components.drain(..) {
+            let container = if self.swagger2 {
+                root.entry(section)
+                    .or_insert_with(|| Value::Object(Map::new()))
+            } else {
+                root.entry("components")
+                    .or_insert_with(|| Value::Object(Map::new()))
+                    .as_object_mut()
+                    .unwrap()
+                    .entry(section)
+                    .or_insert_with(|| Value::Object(Map::new()))
+            };
+            if let Some(m) = container.as_object_mut() {
+                m.insert(name, value);
+            }
+        }
+    }
+}
+
+fn ref_object(local: &str) -> Map<String, Value> {
+    let mut m = Map::new();
+    m.insert("$ref".to_string(), Value::String(local.to_string()));
+    m
+}
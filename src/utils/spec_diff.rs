@@ -0,0 +1,527 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use serde::Serialize;
+use serde_json::Value;
+
+const OPENAPI_METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// Schemas nested deeper than this are not compared, it also protects against recursive `$ref`s.
+const MAX_SCHEMA_DEPTH: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Severity {
+    Breaking,
+    NonBreaking,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Breaking => write!(f, "BREAKING"),
+            Severity::NonBreaking => write!(f, "non-breaking"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SpecChange {
+    pub severity: Severity,
+    pub location: String,
+    pub message: String,
+}
+
+impl fmt::Display for SpecChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:<13} {}: {}",
+            self.severity.to_string(),
+            self.location,
+            self.message
+        )
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SpecDiffReport {
+    pub breaking: usize,
+    pub non_breaking: usize,
+    pub changes: Vec<SpecChange>,
+}
+
+impl SpecDiffReport {
+    pub fn has_breaking_changes(&self) -> bool {
+        self.breaking > 0
+    }
+}
+
+impl fmt::Display for SpecDiffReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.changes {
+            writeln!(f, "{}", change)?;
+        }
+        write!(
+            f,
+            "{} breaking, {} non-breaking change(s)",
+            self.breaking, self.non_breaking
+        )
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Direction {
+    Request,
+    Response,
+}
+
+struct Differ<'a> {
+    base_root: &'a Value,
+    revision_root: &'a Value,
+    changes: Vec<SpecChange>,
+}
+
+/// Compares two parsed spec documents and classifies every change found. Breaking changes are
+/// the ones that can fail an existing client, e.g. removed operations or new required inputs.
+pub fn diff_specs(base: &Value, revision: &Value) -> SpecDiffReport {
+    let mut differ = Differ {
+        base_root: base,
+        revision_root: revision,
+        changes: Vec::new(),
+    };
+    if base.get("asyncapi").is_some() || revision.get("asyncapi").is_some() {
+        differ.diff_asyncapi();
+    } else {
+        differ.diff_paths();
+    }
+    let mut changes = differ.changes;
+    changes.sort_by_key(|c| c.severity);
+    let breaking = changes
+        .iter()
+        .filter(|c| c.severity == Severity::Breaking)
+        .count();
+    SpecDiffReport {
+        breaking,
+        non_breaking: changes.len() - breaking,
+        changes,
+    }
+}
+
+fn object_keys(value: Option<&Value>) -> BTreeSet<String> {
+    value
+        .and_then(|v| v.as_object())
+        .map(|m| m.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+fn type_of(schema: &Value) -> Option<String> {
+    match schema.get("type") {
+        Some(Value::String(s)) => Some(s.clone()),
+        Some(Value::Array(types)) => Some(
+            types
+                .iter()
+                .filter_map(|t| t.as_str())
+                .collect::<Vec<_>>()
+                .join("|"),
+        ),
+        _ => None,
+    }
+}
+
+fn string_set(value: Option<&Value>) -> BTreeSet<String> {
+    value
+        .and_then(|v| v.as_array())
+        .map(|a| {
+            a.iter()
+                .map(|v| match v {
+                    Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn is_required(value: &Value) -> bool {
+    value.get("required") == Some(&Value::Bool(true))
+}
+
+impl<'a> Differ<'a> {
+    fn push(&mut self, severity: Severity, location: &str, message: impl Into<String>) {
+        self.changes.push(SpecChange {
+            severity,
+            location: location.to_string(),
+            message: message.into(),
+        });
+    }
+
+    fn resolve(root: &'a Value, value: &'a Value) -> &'a Value {
+        let mut current = value;
+        for _ in 0..MAX_SCHEMA_DEPTH {
+            match current.get("$ref").and_then(|r| r.as_str()) {
+                Some(r) if r.starts_with('#') => match root.pointer(&r[1..]) {
+                    Some(target) => current = target,
+                    None => return current,
+                },
+                _ => return current,
+            }
+        }
+        current
+    }
+
+    fn diff_paths(&mut self) {
+        let base_paths = self.base_root.get("paths");
+        let revision_paths = self.revision_root.get("paths");
+        let base_keys = object_keys(base_paths);
+        let revision_keys = object_keys(revision_paths);
+
+        for path in base_keys.difference(&revision_keys) {
+            self.push(Severity::Breaking, path, "path removed");
+        }
+        for path in revision_keys.difference(&base_keys) {
+            self.push(Severity::NonBreaking, path, "path added");
+        }
+        for path in base_keys.intersection(&revision_keys) {
+            let base_item = Self::resolve(self.base_root, &base_paths.unwrap()[path]);
+            let revision_item = Self::resolve(self.revision_root, &revision_paths.unwrap()[path]);
+            for method in OPENAPI_METHODS {
+                let location = format!("{} {}", method.to_uppercase(), path);
+                match (base_item.get(method), revision_item.get(method)) {
+                    (Some(_), None) => {
+                        self.push(Severity::Breaking, &location, "operation removed")
+                    }
+                    (None, Some(_)) => {
+                        self.push(Severity::NonBreaking, &location, "operation added")
+                    }
+                    (Some(b), Some(r)) => {
+                        self.diff_operation(&location, base_item, b, revision_item, r)
+                    }
+                    (None, None) => {}
+                }
+            }
+        }
+    }
+
+    /// Parameters of an operation keyed by `in:name`, path level parameters included.
+    fn parameters(root: &'a Value, item: &'a Value, op: &'a Value) -> BTreeMap<String, &'a Value> {
+        let mut params = BTreeMap::new();
+        for source in [item, op] {
+            if let Some(list) = source.get("parameters").and_then(|p| p.as_array()) {
+                for p in list {
+                    let p = Self::resolve(root, p);
+                    let name = p.get("name").and_then(|n| n.as_str()).unwrap_or_default();
+                    let location = p.get("in").and_then(|n| n.as_str()).unwrap_or_default();
+                    params.insert(format!("{}:{}", location, name), p);
+                }
+            }
+        }
+        params
+    }
+
+    fn diff_operation(
+        &mut self,
+        location: &str,
+        base_item: &'a Value,
+        base: &'a Value,
+        revision_item: &'a Value,
+        revision: &'a Value,
+    ) {
+        let base_params = Self::parameters(self.base_root, base_item, base);
+        let revision_params = Self::parameters(self.revision_root, revision_item, revision);
+        for (key, param) in &base_params {
+            let (param_in, name) = key.split_once(':').unwrap_or_default();
+            let label = format!("{} parameter {}", param_in, name);
+            match revision_params.get(key) {
+                None => self.push(Severity::Breaking, location, format!("{} removed", label)),
+                Some(rev) => {
+                    match (is_required(param), is_required(rev)) {
+                        (false, true) => self.push(
+                            Severity::Breaking,
+                            location,
+                            format!("{} became required", label),
+                        ),
+                        (true, false) => self.push(
+                            Severity::NonBreaking,
+                            location,
+                            format!("{} became optional", label),
+                        ),
+                        _ => {}
+                    }
+                    let base_schema = param.get("schema").unwrap_or(param);
+                    let revision_schema = rev.get("schema").unwrap_or(rev);
+                    self.diff_schema(
+                        &format!("{} ({})", location, label),
+                        base_schema,
+                        revision_schema,
+                        Direction::Request,
+                        0,
+                    );
+                }
+            }
+        }
+        for (key, rev) in &revision_params {
+            if base_params.contains_key(key) {
+                continue;
+            }
+            let (param_in, name) = key.split_once(':').unwrap_or_default();
+            if is_required(rev) {
+                self.push(
+                    Severity::Breaking,
+                    location,
+                    format!("required {} parameter {} added", param_in, name),
+                );
+            } else {
+                self.push(
+                    Severity::NonBreaking,
+                    location,
+                    format!("optional {} parameter {} added", param_in, name),
+                );
+            }
+        }
+
+        self.diff_request_body(location, base, revision);
+        self.diff_responses(location, base, revision);
+
+        let base_security = object_keys_of_requirements(base.get("security"));
+        let revision_security = object_keys_of_requirements(revision.get("security"));
+        for scheme in revision_security.difference(&base_security) {
+            self.push(
+                Severity::Breaking,
+                location,
+                format!("security requirement {} added", scheme),
+            );
+        }
+    }
+
+    fn diff_request_body(&mut self, location: &str, base: &'a Value, revision: &'a Value) {
+        let base_body = base
+            .get("requestBody")
+            .map(|b| Self::resolve(self.base_root, b));
+        let revision_body = revision
+            .get("requestBody")
+            .map(|b| Self::resolve(self.revision_root, b));
+        match (base_body, revision_body) {
+            (None, Some(r)) if is_required(r) => {
+                self.push(Severity::Breaking, location, "required request body added")
+            }
+            (None, Some(_)) => self.push(Severity::NonBreaking, location, "request body added"),
+            (Some(_), None) => self.push(Severity::NonBreaking, location, "request body removed"),
+            (Some(b), Some(r)) => {
+                if !is_required(b) && is_required(r) {
+                    self.push(Severity::Breaking, location, "request body became required");
+                }
+                let label = format!("{} request body", location);
+                self.diff_content(&label, b, r, Direction::Request);
+            }
+            (None, None) => {}
+        }
+    }
+
+    fn diff_responses(&mut self, location: &str, base: &'a Value, revision: &'a Value) {
+        let base_responses = base.get("responses");
+        let revision_responses = revision.get("responses");
+        let base_codes = object_keys(base_responses);
+        let revision_codes = object_keys(revision_responses);
+        for code in base_codes.difference(&revision_codes) {
+            let severity = if code.starts_with('2') || code == "default" {
+                Severity::Breaking
+            } else {
+                Severity::NonBreaking
+            };
+            self.push(severity, location, format!("response {} removed", code));
+        }
+        for code in revision_codes.difference(&base_codes) {
+            self.push(
+                Severity::NonBreaking,
+                location,
+                format!("response {} added", code),
+            );
+        }
+        for code in base_codes.intersection(&revision_codes) {
+            let b = Self::resolve(self.base_root, &base_responses.unwrap()[code]);
+            let r = Self::resolve(self.revision_root, &revision_responses.unwrap()[code]);
+            let label = format!("{} response {}", location, code);
+            if b.get("schema").is_some() || r.get("schema").is_some() {
+                // OpenAPI 2.0 keeps the schema directly on the response
+                if let (Some(bs), Some(rs)) = (b.get("schema"), r.get("schema")) {
+                    self.diff_schema(&label, bs, rs, Direction::Response, 0);
+                } else if b.get("schema").is_some() {
+                    self.push(Severity::Breaking, &label, "response body removed");
+                }
+            } else {
+                self.diff_content(&label, b, r, Direction::Response);
+            }
+        }
+    }
+
+    fn diff_content(&mut self, label: &str, base: &'a Value, revision: &'a Value, d: Direction) {
+        let base_content = base.get("content");
+        let revision_content = revision.get("content");
+        let base_types = object_keys(base_content);
+        let revision_types = object_keys(revision_content);
+        for media in base_types.difference(&revision_types) {
+            self.push(
+                Severity::Breaking,
+                label,
+                format!("media type {} removed", media),
+            );
+        }
+        for media in revision_types.difference(&base_types) {
+            self.push(
+                Severity::NonBreaking,
+                label,
+                format!("media type {} added", media),
+            );
+        }
+        for media in base_types.intersection(&revision_types) {
+            let bs = base_content.unwrap()[media].get("schema");
+            let rs = revision_content.unwrap()[media].get("schema");
+            if let (Some(bs), Some(rs)) = (bs, rs) {
+                self.diff_schema(&format!("{} {}", label, media), bs, rs, d, 0);
+            }
+        }
+    }
+
+    fn diff_schema(
+        &mut self,
+        location: &str,
+        base: &'a Value,
+        revision: &'a Value,
+        direction: Direction,
+        depth: usize,
+    ) {
+        if depth > MAX_SCHEMA_DEPTH {
+            return;
+        }
+        let base = Self::resolve(self.base_root, base);
+        let revision = Self::resolve(self.revision_root, revision);
+
+        if let (Some(bt), Some(rt)) = (type_of(base), type_of(revision)) {
+            if bt != rt {
+                self.push(
+                    Severity::Breaking,
+                    location,
+                    format!("type changed from {} to {}", bt, rt),
+                );
+                return;
+            }
+        }
+
+        let base_enum = string_set(base.get("enum"));
+        let revision_enum = string_set(revision.get("enum"));
+        if !base_enum.is_empty() || !revision_enum.is_empty() {
+            let removed: Vec<_> = base_enum.difference(&revision_enum).cloned().collect();
+            let added: Vec<_> = revision_enum.difference(&base_enum).cloned().collect();
+            // A request accepting fewer values, or a response returning new ones, can break clients
+            let (removed_severity, added_severity) = match direction {
+                Direction::Request => (Severity::Breaking, Severity::NonBreaking),
+                Direction::Response => (Severity::NonBreaking, Severity::Breaking),
+            };
+            if !removed.is_empty() {
+                self.push(
+                    removed_severity,
+                    location,
+                    format!("enum values removed: {}", removed.join(", ")),
+                );
+            }
+            if !added.is_empty() {
+                self.push(
+                    added_severity,
+                    location,
+                    format!("enum values added: {}", added.join(", ")),
+                );
+            }
+        }
+
+        let base_props = base.get("properties");
+        let revision_props = revision.get("properties");
+        let base_names = object_keys(base_props);
+        let revision_names = object_keys(revision_props);
+        let base_required = string_set(base.get("required"));
+        let revision_required = string_set(revision.get("required"));
+
+        for name in base_names.difference(&revision_names) {
+            let severity = match direction {
+                Direction::Response => Severity::Breaking,
+                Direction::Request => Severity::NonBreaking,
+            };
+            self.push(severity, location, format!("property {} removed", name));
+        }
+        for name in revision_names.difference(&base_names) {
+            if direction == Direction::Request && revision_required.contains(name) {
+                self.push(
+                    Severity::Breaking,
+                    location,
+                    format!("required property {} added", name),
+                );
+            } else {
+                self.push(
+                    Severity::NonBreaking,
+                    location,
+                    format!("property {} added", name),
+                );
+            }
+        }
+        for name in base_names.intersection(&revision_names) {
+            match (
+                direction,
+                base_required.contains(name),
+                revision_required.contains(name),
+            ) {
+                (Direction::Request, false, true) => self.push(
+                    Severity::Breaking,
+                    location,
+                    format!("property {} became required", name),
+                ),
+                (Direction::Response, true, false) => self.push(
+                    Severity::Breaking,
+                    location,
+                    format!("property {} is no longer required", name),
+                ),
+                _ => {}
+            }
+            self.diff_schema(
+                &format!("{}.{}", location, name),
+                &base_props.unwrap()[name],
+                &revision_props.unwrap()[name],
+                direction,
+                depth + 1,
+            );
+        }
+
+        if let (Some(bi), Some(ri)) = (base.get("items"), revision.get("items")) {
+            self.diff_schema(&format!("{}[]", location), bi, ri, direction, depth + 1);
+        }
+    }
+
+    fn diff_asyncapi(&mut self) {
+        let base_channels = object_keys(self.base_root.get("channels"));
+        let revision_channels = object_keys(self.revision_root.get("channels"));
+        for channel in base_channels.difference(&revision_channels) {
+            self.push(Severity::Breaking, channel, "channel removed");
+        }
+        for channel in revision_channels.difference(&base_channels) {
+            self.push(Severity::NonBreaking, channel, "channel added");
+        }
+        let base_ops = object_keys(self.base_root.get("operations"));
+        let revision_ops = object_keys(self.revision_root.get("operations"));
+        for op in base_ops.difference(&revision_ops) {
+            self.push(Severity::Breaking, op, "operation removed");
+        }
+        for op in revision_ops.difference(&base_ops) {
+            self.push(Severity::NonBreaking, op, "operation added");
+        }
+    }
+}
+
+fn object_keys_of_requirements(value: Option<&Value>) -> BTreeSet<String> {
+    value
+        .and_then(|v| v.as_array())
+        .map(|reqs| reqs.iter().flat_map(|r| object_keys(Some(r))).collect())
+        .unwrap_or_default()
+}
@@ -0,0 +1,525 @@
+use std::{collections::HashSet, error::Error, fmt, fs};
+
+use jsonschema::error::ValidationErrorKind;
+use log::info;
+use serde_json::{Map, Value};
+
+const OPENAPI_METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// The JSON Schemas published with the OpenAPI specification, for 2.0, 3.0 and 3.1 documents.
+const OPENAPI_2_0_SCHEMA: &str = include_str!("schemas/openapi-2.0.json");
+const OPENAPI_3_0_SCHEMA: &str = include_str!("schemas/openapi-3.0.json");
+const OPENAPI_3_1_SCHEMA: &str = include_str!("schemas/openapi-3.1.json");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpecKind {
+    Swagger2,
+    OpenApi30,
+    OpenApi31,
+    AsyncApi2,
+    AsyncApi3,
+}
+
+impl fmt::Display for SpecKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SpecKind::Swagger2 => "OpenAPI 2.0 (Swagger)",
+            SpecKind::OpenApi30 => "OpenAPI 3.0",
+            SpecKind::OpenApi31 => "OpenAPI 3.1",
+            SpecKind::AsyncApi2 => "AsyncAPI 2.x",
+            SpecKind::AsyncApi3 => "AsyncAPI 3.x",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A single problem found in a spec document. `pointer` is a JSON pointer into the document and
+/// `line` is the best guess of where that pointer lives in the source file.
+#[derive(Debug)]
+pub struct SpecIssue {
+    pub pointer: String,
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for SpecIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(l) => write!(f, "line {}: ", l)?,
+            None => write!(f, "line ?: ")?,
+        }
+        if self.pointer.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.pointer, self.message)
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct SpecValidationError {
+    pub path: String,
+    pub issues: Vec<SpecIssue>,
+}
+
+impl fmt::Display for SpecValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} is not a valid specification ({} error(s))",
+            self.path,
+            self.issues.len()
+        )?;
+        for issue in &self.issues {
+            write!(f, "\n  {}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for SpecValidationError {}
+
+#[derive(Debug)]
+pub struct ValidatedSpec {
+    pub kind: SpecKind,
+    pub content: String,
+    pub document: Value,
+}
+
+/// Reads a spec file, failing with the file name in the message so callers can bail out before
+/// anything is sent to Konnect.
+pub fn read_spec_file(path: &str) -> Result<String, Box<dyn Error>> {
+    fs::read_to_string(path).map_err(|e| format!("Unable to read spec file {}: {}", path, e).into())
+}
+
+/// Parses YAML or JSON content into a JSON value. JSON is tried first when the document looks
+/// like an object so that JSON syntax errors are reported with JSON line numbers.
+pub fn parse_spec(content: &str) -> Result<Value, SpecIssue> {
+    if content.trim_start().starts_with('{') {
+        return serde_json::from_str(content).map_err(|e| SpecIssue {
+            pointer: String::new(),
+            line: Some(e.line()),
+            message: format!("invalid JSON: {}", e),
+        });
+    }
+    let yaml: serde_yaml::Value = serde_yaml::from_str(content).map_err(|e| SpecIssue {
+        pointer: String::new(),
+        line: e.location().map(|l| l.line()),
+        message: format!("invalid YAML: {}", e),
+    })?;
+    Ok(yaml_to_json(yaml))
+}
+
+/// Converts a YAML value into JSON. Non string keys (e.g. unquoted `200:` response codes) are
+/// turned into strings, which is what OpenAPI expects them to be.
+pub fn yaml_to_json(value: serde_yaml::Value) -> Value {
+    match value {
+        serde_yaml::Value::Null => Value::Null,
+        serde_yaml::Value::Bool(b) => Value::Bool(b),
+        serde_yaml::Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                Value::from(i)
+            } else if let Some(u) = n.as_u64() {
+                Value::from(u)
+            } else {
+                n.as_f64().map(Value::from).unwrap_or(Value::Null)
+            }
+        }
+        serde_yaml::Value::String(s) => Value::String(s),
+        serde_yaml::Value::Sequence(seq) => {
+            Value::Array(seq.into_iter().map(yaml_to_json).collect())
+        }
+        serde_yaml::Value::Mapping(map) => {
+            let mut m = Map::new();
+            for (k, v) in map {
+                let key = match k {
+                    serde_yaml::Value::String(s) => s,
+                    other => match yaml_to_json(other) {
+                        Value::String(s) => s,
+                        v => v.to_string(),
+                    },
+                };
+                m.insert(key, yaml_to_json(v));
+            }
+            Value::Object(m)
+        }
+        serde_yaml::Value::Tagged(tagged) => yaml_to_json(tagged.value),
+    }
+}
+
+pub fn detect_kind(document: &Value) -> Result<SpecKind, String> {
+    if let Some(v) = document.get("openapi") {
+        let version = v
+            .as_str()
+            .ok_or("openapi must be a string such as \"3.0.3\"")?;
+        return if version.starts_with("3.0") {
+            Ok(SpecKind::OpenApi30)
+        } else if version.starts_with("3.1") {
+            Ok(SpecKind::OpenApi31)
+        } else {
+            Err(format!("unsupported OpenAPI version {}", version))
+        };
+    }
+    if let Some(v) = document.get("swagger") {
+        return match v.as_str() {
+            Some("2.0") => Ok(SpecKind::Swagger2),
+            _ => Err(format!("unsupported swagger version {}", v)),
+        };
+    }
+    if let Some(v) = document.get("asyncapi") {
+        let version = v
+            .as_str()
+            .ok_or("asyncapi must be a string such as \"2.6.0\"")?;
+        return if version.starts_with("2.") {
+            Ok(SpecKind::AsyncApi2)
+        } else if version.starts_with("3.") {
+            Ok(SpecKind::AsyncApi3)
+        } else {
+            Err(format!("unsupported AsyncAPI version {}", version))
+        };
+    }
+    Err("document is not an OpenAPI or AsyncAPI specification (no openapi, swagger or asyncapi field)".to_string())
+}
+
+/// Reads, parses and validates a spec file. Every problem found is returned in one
+/// `SpecValidationError` so users can fix them in a single pass.
+pub fn validate_spec_file(path: &str) -> Result<ValidatedSpec, Box<dyn Error>> {
+    let content = read_spec_file(path)?;
+    validate_spec_content(path, content)
+}
+
+pub fn validate_spec_content(path: &str, content: String) -> Result<ValidatedSpec, Box<dyn Error>> {
+    let invalid = |issues: Vec<SpecIssue>| SpecValidationError {
+        path: path.to_string(),
+        issues,
+    };
+    let document = parse_spec(&content).map_err(|issue| invalid(vec![issue]))?;
+    let kind = detect_kind(&document).map_err(|message| {
+        invalid(vec![SpecIssue {
+            pointer: String::new(),
+            line: Some(1),
+            message,
+        }])
+    })?;
+    info!("Validating {} as {}", path, kind);
+
+    let mut checker = Checker::default();
+    match schema_of(kind) {
+        Some(schema) => {
+            checker.check_schema(schema, &document)?;
+            // Rules of the specification a JSON Schema cannot express
+            checker.check_openapi(&document);
+        }
+        None => {
+            checker.check_info(&document);
+            checker.check_asyncapi(kind, &document);
+        }
+    }
+    if !checker.issues.is_empty() {
+        let issues = checker
+            .issues
+            .into_iter()
+            .map(|(pointer, message)| SpecIssue {
+                line: locate_pointer(&content, &pointer),
+                pointer,
+                message,
+            })
+            .collect();
+        return Err(Box::new(invalid(issues)));
+    }
+    Ok(ValidatedSpec {
+        kind,
+        content,
+        document,
+    })
+}
+
+pub fn escape_pointer_token(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+fn unescape_pointer_token(token: &str) -> String {
+    token.replace("~1", "/").replace("~0", "~")
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+fn line_has_key(line: &str, key: &str) -> bool {
+    let t = line.trim_start().trim_start_matches("- ");
+    [
+        format!("{}:", key),
+        format!("\"{}\":", key),
+        format!("'{}':", key),
+        format!("\"{}\" :", key),
+    ]
+    .iter()
+    .any(|k| t.starts_with(k.as_str()))
+}
+
+/// Best effort lookup of the source line (1 based) for a JSON pointer. It walks the file keeping
+/// track of indentation, so it works for block style YAML and pretty printed JSON. The line of
+/// the deepest part of the pointer found is returned, None when not even the first part is.
+pub fn locate_pointer(source: &str, pointer: &str) -> Option<usize> {
+    let lines: Vec<&str> = source.lines().collect();
+    let mut found: Option<usize> = None;
+    let mut start = 0;
+    let mut parent_indent: Option<usize> = None;
+
+    for token in pointer.split('/').skip(1).map(unescape_pointer_token) {
+        let deeper = |l: &str| parent_indent.is_none_or(|p| indent_of(l) > p);
+        let in_scope = |l: &&str| l.trim().is_empty() || deeper(l);
+        let next = if let Ok(index) = token.parse::<usize>() {
+            let mut item_indent = None;
+            let mut count = 0;
+            let mut hit = None;
+            for (i, l) in lines.iter().enumerate().skip(start) {
+                if !in_scope(l) {
+                    break;
+                }
+                if !l.trim_start().starts_with("- ") && l.trim() != "-" {
+                    continue;
+                }
+                let ind = indent_of(l);
+                if *item_indent.get_or_insert(ind) != ind {
+                    continue;
+                }
+                if count == index {
+                    hit = Some(i);
+                    break;
+                }
+                count += 1;
+            }
+            hit
+        } else {
+            lines
+                .iter()
+                .enumerate()
+                .skip(start)
+                .take_while(|(_, l)| in_scope(l))
+                .find(|(_, l)| line_has_key(l, &token))
+                .map(|(i, _)| i)
+        };
+        match next {
+            Some(i) => {
+                found = Some(i + 1);
+                start = i + 1;
+                let l = lines[i];
+                parent_indent = Some(if l.trim_start().starts_with("- ") {
+                    indent_of(l) + 1
+                } else {
+                    indent_of(l)
+                });
+            }
+            None => break,
+        }
+    }
+    found
+}
+
+/// The JSON Schema documents of `kind` are validated against. There is none for AsyncAPI yet,
+/// those documents only go through the checks of `Checker::check_asyncapi`.
+fn schema_of(kind: SpecKind) -> Option<&'static str> {
+    match kind {
+        SpecKind::Swagger2 => Some(OPENAPI_2_0_SCHEMA),
+        SpecKind::OpenApi30 => Some(OPENAPI_3_0_SCHEMA),
+        SpecKind::OpenApi31 => Some(OPENAPI_3_1_SCHEMA),
+        SpecKind::AsyncApi2 | SpecKind::AsyncApi3 => None,
+    }
+}
+
+#[derive(Default)]
+struct Checker {
+    issues: Vec<(String, String)>,
+    operation_ids: HashSet<String>,
+}
+
+impl Checker {
+    fn issue(&mut self, pointer: &str, message: impl Into<String>) {
+        self.issues.push((pointer.to_string(), message.into()));
+    }
+
+    fn require_object<'a>(
+        &mut self,
+        parent: &'a Value,
+        pointer: &str,
+        field: &str,
+    ) -> Option<&'a Map<String, Value>> {
+        match parent.get(field) {
+            Some(Value::Object(m)) => Some(m),
+            Some(_) => {
+                self.issue(&format!("{}/{}", pointer, field), "must be an object");
+                None
+            }
+            None => {
+                self.issue(pointer, format!("{} is required", field));
+                None
+            }
+        }
+    }
+
+    fn require_string(&mut self, parent: &Value, pointer: &str, field: &str) {
+        match parent.get(field) {
+            Some(Value::String(_)) => {}
+            Some(_) => self.issue(&format!("{}/{}", pointer, field), "must be a string"),
+            None => self.issue(pointer, format!("{} is required", field)),
+        }
+    }
+
+    fn check_info(&mut self, document: &Value) {
+        if let Some(info) = document.get("info") {
+            if !info.is_object() {
+                self.issue("/info", "must be an object");
+                return;
+            }
+            self.require_string(info, "/info", "title");
+            self.require_string(info, "/info", "version");
+        } else {
+            self.issue("", "info is required");
+        }
+    }
+
+    /// Reports where `document` does not match the JSON Schema `schema`. An object holding an
+    /// invalid field is also reported as having unevaluated properties, those reports are left
+    /// out when the error below them is reported.
+    fn check_schema(&mut self, schema: &str, document: &Value) -> Result<(), Box<dyn Error>> {
+        let schema: Value = serde_json::from_str(schema)?;
+        let validator = jsonschema::validator_for(&schema)
+            .map_err(|e| format!("Unable to load the specification schema: {}", e))?;
+        let errors: Vec<_> = validator.iter_errors(document).collect();
+        let is_below = |pointer: &str, other: &str| other.starts_with(&format!("{}/", pointer));
+        for error in &errors {
+            let pointer = error.instance_path.as_str();
+            let unevaluated = matches!(
+                error.kind,
+                ValidationErrorKind::UnevaluatedProperties { .. }
+            );
+            if unevaluated
+                && errors
+                    .iter()
+                    .any(|other| is_below(pointer, other.instance_path.as_str()))
+            {
+                continue;
+            }
+            self.issue(pointer, error.masked().to_string());
+        }
+        Ok(())
+    }
+
+    fn check_openapi(&mut self, document: &Value) {
+        let Some(paths) = document.get("paths").and_then(Value::as_object) else {
+            return;
+        };
+        for (path, item) in paths {
+            if item.get("$ref").is_some() {
+                continue;
+            }
+            let pointer = format!("/paths/{}", escape_pointer_token(path));
+            let shared = path_parameters(item);
+            for method in OPENAPI_METHODS {
+                if let Some(op) = item.get(method) {
+                    let op_pointer = format!("{}/{}", pointer, method);
+                    self.check_operation(path, op, &op_pointer, &shared);
+                }
+            }
+        }
+    }
+
+    fn check_operation(
+        &mut self,
+        path: &str,
+        op: &Value,
+        pointer: &str,
+        shared: &Option<HashSet<String>>,
+    ) {
+        if let Some(id) = op.get("operationId").and_then(|v| v.as_str()) {
+            if !self.operation_ids.insert(id.to_string()) {
+                self.issue(
+                    &format!("{}/operationId", pointer),
+                    format!("duplicate operationId {}", id),
+                );
+            }
+        }
+        if let (Some(shared), Some(own)) = (shared, path_parameters(op)) {
+            for name in path_template_params(path) {
+                if !shared.contains(&name) && !own.contains(&name) {
+                    self.issue(
+                        pointer,
+                        format!("path parameter {{{}}} is not declared", name),
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_asyncapi(&mut self, kind: SpecKind, document: &Value) {
+        let channels = if kind == SpecKind::AsyncApi2 {
+            self.require_object(document, "", "channels")
+        } else {
+            match document.get("channels") {
+                Some(Value::Object(m)) => Some(m),
+                Some(_) => {
+                    self.issue("/channels", "must be an object");
+                    None
+                }
+                None => None,
+            }
+        };
+        if let Some(channels) = channels {
+            for (name, channel) in channels {
+                if !channel.is_object() {
+                    self.issue(
+                        &format!("/channels/{}", escape_pointer_token(name)),
+                        "channel must be an object",
+                    );
+                }
+            }
+        }
+        if kind == SpecKind::AsyncApi3 {
+            if let Some(ops) = document.get("operations").and_then(|o| o.as_object()) {
+                for (name, op) in ops {
+                    let pointer = format!("/operations/{}", escape_pointer_token(name));
+                    match op.get("action").and_then(|a| a.as_str()) {
+                        Some("send") | Some("receive") => {}
+                        _ => self.issue(&pointer, "action must be either send or receive"),
+                    }
+                    if op.get("channel").is_none() {
+                        self.issue(&pointer, "channel is required");
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Names of the path parameters declared by a path item or an operation. `None` means the list
+/// could not be fully resolved (it uses `$ref`s).
+fn path_parameters(parent: &Value) -> Option<HashSet<String>> {
+    let mut names = HashSet::new();
+    for param in parent
+        .get("parameters")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        if param.get("$ref").is_some() {
+            return None;
+        }
+        if param.get("in").and_then(Value::as_str) == Some("path") {
+            if let Some(name) = param.get("name").and_then(Value::as_str) {
+                names.insert(name.to_string());
+            }
+        }
+    }
+    Some(names)
+}
+
+pub fn path_template_params(path: &str) -> Vec<String> {
+    path.split('{')
+        .skip(1)
+        .filter_map(|s| s.split_once('}').map(|(name, _)| name.to_string()))
+        .collect()
+}
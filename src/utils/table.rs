@@ -0,0 +1,168 @@
+use std::{cmp::Ordering, env};
+
+use serde_json::Value;
+
+/// Separates the columns of a table.
+const GAP: &str = "   ";
+/// Columns are not truncated below this width, or their header if it is shorter.
+const MIN_WIDTH: usize = 8;
+
+#[derive(Debug, Clone)]
+pub struct TableOptions {
+    /// Dotted paths into each row, e.g. `labels.team`
+    pub columns: Vec<String>,
+    pub sort_by: Option<String>,
+    pub no_headers: bool,
+}
+
+/// The rows of a response: the items of a list response or of an array, or the object itself.
+pub fn rows(value: Value) -> Vec<Value> {
+    match value {
+        Value::Object(map) => match map.get("data") {
+            Some(Value::Array(items)) => items.clone(),
+            _ => vec![Value::Object(map)],
+        },
+        Value::Array(items) => items,
+        other => vec![other],
+    }
+}
+
+/// What `path` names in `value`, through lists: `portals.portal_name` lists the names of the
+/// portals.
+fn lookup(value: &Value, path: &str) -> Value {
+    path.split('.')
+        .fold(value.clone(), |value, key| match value {
+            Value::Object(mut map) => map.remove(key).unwrap_or(Value::Null),
+            Value::Array(items) => Value::Array(
+                items
+                    .iter()
+                    .map(|item| lookup(item, key))
+                    .filter(|v| !v.is_null())
+                    .collect(),
+            ),
+            _ => Value::Null,
+        })
+}
+
+/// A value as shown in a cell: strings as is, lists comma separated and objects, such as labels,
+/// as sorted key=value pairs.
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items.iter().map(cell).collect::<Vec<_>>().join(","),
+        Value::Object(map) => {
+            let mut pairs: Vec<String> = map
+                .iter()
+                .map(|(k, v)| format!("{}={}", k, cell(v)))
+                .collect();
+            pairs.sort();
+            pairs.join(",")
+        }
+        other => other.to_string(),
+    }
+}
+
+/// Numbers compare as numbers, anything else by its cell text. Empty values sort last.
+fn compare(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a
+            .as_f64()
+            .partial_cmp(&b.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Value::Null, Value::Null) => Ordering::Equal,
+        (Value::Null, _) => Ordering::Greater,
+        (_, Value::Null) => Ordering::Less,
+        _ => cell(a).cmp(&cell(b)),
+    }
+}
+
+/// Width of the terminal stdout is, from COLUMNS or the terminal itself. None when stdout is
+/// not a terminal, output going to a file or a pipe is not truncated.
+pub fn terminal_width() -> Option<usize> {
+    if let Some(columns) = env::var("COLUMNS").ok().and_then(|c| c.parse().ok()) {
+        return Some(columns);
+    }
+    let (terminal_size::Width(width), _) = terminal_size::terminal_size_of(std::io::stdout())?;
+    (width > 0).then_some(width.into())
+}
+
+/// Shrinks the widest columns until the table fits in `width`.
+fn fit(widths: &mut [usize], minimums: &[usize], width: usize) {
+    let gaps = GAP.len() * widths.len().saturating_sub(1);
+    while widths.iter().sum::<usize>() + gaps > width {
+        let Some((widest, _)) = widths
+            .iter()
+            .enumerate()
+            .filter(|(i, w)| **w > minimums[*i])
+            .max_by_key(|(_, w)| **w)
+        else {
+            return;
+        };
+        widths[widest] -= 1;
+    }
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(width.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
+
+/// `rows` as a table of `columns`, sorted by the `sort_by` column and truncated to `width` when
+/// given. Headers are the column paths in upper case.
+pub fn render_table(mut rows: Vec<Value>, options: &TableOptions, width: Option<usize>) -> String {
+    if let Some(sort_by) = &options.sort_by {
+        rows.sort_by(|a, b| compare(&lookup(a, sort_by), &lookup(b, sort_by)));
+    }
+    let columns = &options.columns;
+    let headers: Vec<String> = columns.iter().map(|c| c.to_uppercase()).collect();
+    let cells: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| {
+            columns
+                .iter()
+                .map(|column| {
+                    // Newlines would break the rows
+                    cell(&lookup(row, column)).replace(['\n', '\r', '\t'], " ")
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut widths: Vec<usize> = headers
+        .iter()
+        .enumerate()
+        .map(|(i, header)| {
+            let header_width = if options.no_headers { 0 } else { header.len() };
+            cells
+                .iter()
+                .map(|row| row[i].chars().count())
+                .fold(header_width, usize::max)
+        })
+        .collect();
+    if let Some(width) = width {
+        let minimums: Vec<usize> = widths
+            .iter()
+            .zip(&headers)
+            .map(|(w, h)| (*w).min(MIN_WIDTH.max(h.len())))
+            .collect();
+        fit(&mut widths, &minimums, width);
+    }
+
+    let mut table = String::new();
+    let header_row = (!options.no_headers).then_some(headers);
+    for row in header_row.iter().chain(&cells) {
+        let line: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(text, width)| format!("{:<width$}", truncate(text, *width), width = *width))
+            .collect();
+        table.push_str(line.join(GAP).trim_end());
+        table.push('\n');
+    }
+    table
+}
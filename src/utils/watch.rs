@@ -0,0 +1,189 @@
+use std::{
+    error::Error,
+    fmt, thread,
+    time::{Duration, SystemTime},
+};
+
+use log::error;
+use serde_json::Value;
+
+use crate::api::api_utils::ToJson;
+
+#[derive(Debug, PartialEq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// A field or list item that differs between two polls. `path` is dotted, list items are named
+/// by their name or id, e.g. `data[v1].publish_status`.
+#[derive(Debug)]
+pub struct Change {
+    pub kind: ChangeKind,
+    pub path: String,
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.path.is_empty() {
+            "value"
+        } else {
+            &self.path
+        };
+        match (&self.kind, &self.old, &self.new) {
+            (ChangeKind::Modified, Some(old), Some(new)) => {
+                write!(f, "modified {}: {} -> {}", path, old, new)
+            }
+            (ChangeKind::Added, _, Some(value)) | (ChangeKind::Removed, Some(value), _)
+                if !value.is_object() =>
+            {
+                write!(f, "{} {}: {}", self.kind, path, value)
+            }
+            _ => write!(f, "{} {}", self.kind, path),
+        }
+    }
+}
+
+impl fmt::Display for ChangeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChangeKind::Added => write!(f, "added"),
+            ChangeKind::Removed => write!(f, "removed"),
+            ChangeKind::Modified => write!(f, "modified"),
+        }
+    }
+}
+
+/// What changed between two responses of the same request. The paging `meta` of list responses
+/// is left out.
+pub fn changes(old: &Value, new: &Value) -> Vec<Change> {
+    let mut changes = Vec::new();
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            let without_meta = |map: &serde_json::Map<String, Value>| {
+                let mut map = map.clone();
+                map.remove("meta");
+                Value::Object(map)
+            };
+            collect("", &without_meta(old), &without_meta(new), &mut changes);
+        }
+        _ => collect("", old, new, &mut changes),
+    }
+    changes
+}
+
+fn child_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+/// Id of a list item, when it has one.
+fn item_id(item: &Value) -> Option<&str> {
+    item.get("id").and_then(Value::as_str)
+}
+
+/// The item of `items` with the id of `item`.
+fn find_item<'a>(items: &'a [Value], item: &Value) -> Option<&'a Value> {
+    items.iter().find(|i| item_id(i) == item_id(item))
+}
+
+fn item_path(path: &str, item: &Value) -> String {
+    let label = item
+        .get("name")
+        .and_then(Value::as_str)
+        .or_else(|| item_id(item))
+        .unwrap_or_default();
+    format!("{}[{}]", path, label)
+}
+
+fn collect(path: &str, old: &Value, new: &Value, changes: &mut Vec<Change>) {
+    if old == new {
+        return;
+    }
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            for (key, old_value) in old {
+                let path = child_path(path, key);
+                match new.get(key) {
+                    Some(new_value) => collect(&path, old_value, new_value, changes),
+                    None => changes.push(Change {
+                        kind: ChangeKind::Removed,
+                        path,
+                        old: Some(old_value.clone()),
+                        new: None,
+                    }),
+                }
+            }
+            for (key, new_value) in new.iter().filter(|(key, _)| !old.contains_key(*key)) {
+                changes.push(Change {
+                    kind: ChangeKind::Added,
+                    path: child_path(path, key),
+                    old: None,
+                    new: Some(new_value.clone()),
+                });
+            }
+        }
+        // Lists of objects with ids are compared item by item
+        (Value::Array(old), Value::Array(new))
+            if old.iter().chain(new).all(|item| item_id(item).is_some()) =>
+        {
+            for old_item in old {
+                match find_item(new, old_item) {
+                    Some(new_item) => {
+                        collect(&item_path(path, new_item), old_item, new_item, changes)
+                    }
+                    None => changes.push(Change {
+                        kind: ChangeKind::Removed,
+                        path: item_path(path, old_item),
+                        old: Some(old_item.clone()),
+                        new: None,
+                    }),
+                }
+            }
+            for new_item in new.iter().filter(|i| find_item(old, i).is_none()) {
+                changes.push(Change {
+                    kind: ChangeKind::Added,
+                    path: item_path(path, new_item),
+                    old: None,
+                    new: Some(new_item.clone()),
+                });
+            }
+        }
+        _ => changes.push(Change {
+            kind: ChangeKind::Modified,
+            path: path.to_string(),
+            old: Some(old.clone()),
+            new: Some(new.clone()),
+        }),
+    }
+}
+
+/// Prints what `fetch` returns, then polls it every `interval` and prints what changed, one
+/// timestamped line per change, until interrupted. Failed polls are logged and retried, only a
+/// failure of the first one is returned.
+pub fn watch(
+    interval: Duration,
+    mut fetch: impl FnMut() -> Result<Value, Box<dyn Error>>,
+) -> Result<(), Box<dyn Error>> {
+    let mut last = fetch()?;
+    println!("{}", last.to_json());
+    loop {
+        thread::sleep(interval);
+        let now = humantime::format_rfc3339_seconds(SystemTime::now());
+        match fetch() {
+            Ok(current) => {
+                for change in changes(&last, &current) {
+                    println!("{} {}", now, change);
+                }
+                last = current;
+            }
+            Err(e) => error!("Unable to poll: {}", e),
+        }
+    }
+}
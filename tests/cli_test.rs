@@ -5,7 +5,7 @@ use serde_json::Value;
 use std::{
     collections::HashMap,
     process::Command,
-    sync::{Arc, Mutex, Once},
+    sync::{Arc, Mutex, OnceLock},
 };
 
 use dotenv::dotenv;
@@ -34,15 +34,10 @@ impl TestContext {
     }
 }
 
-static INIT: Once = Once::new();
-static mut CONTEXT: Option<Arc<TestContext>> = None;
+static CONTEXT: OnceLock<Arc<TestContext>> = OnceLock::new();
 
 fn setup() -> Arc<TestContext> {
-    INIT.call_once(|| {
-        let context = Arc::new(TestContext::new());
-        unsafe { CONTEXT = Some(context.clone()) };
-    });
-    unsafe { CONTEXT.clone().unwrap() }
+    CONTEXT.get_or_init(|| Arc::new(TestContext::new())).clone()
 }
 
 fn execute_command(args: &[&str]) -> String {
@@ -129,6 +124,30 @@ fn test_12_patch_api_product() {
     }
 }
 
+#[test]
+fn test_12a_edit_api_product() {
+    let context = setup();
+    let id = context.get_id("API_PRODUCT_ID").unwrap();
+    let mut cmd = Command::cargo_bin("konnectctl").unwrap();
+    let output = cmd
+        .env(
+            "KONNECTCTL_EDITOR",
+            "sed -i 's/^description: .*/description: Edited API Product/'",
+        )
+        .args(["edit", "api-product", &id])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let json_value = handle_json_response_commands(String::from_utf8(output).unwrap());
+    assert_eq!(json_value["name"].as_str().unwrap(), "New API Product");
+    assert_eq!(
+        json_value["description"].as_str().unwrap(),
+        "Edited API Product"
+    );
+}
+
 #[test]
 fn test_13_get_api_product() {
     // println!("Testing Get API product and fields");
@@ -140,6 +159,18 @@ fn test_13_get_api_product() {
         handle_json_response_commands(execute_command(&["get", "api-product", "--id", &id]));
     assert_missing_fields(&json_value, &fields);
     assert_eq!(json_value["id"].as_str().unwrap(), &id);
+    let name = execute_command(&["get", "api-product", "--id", &id, "--query", ".name"]);
+    assert_eq!(name, "New API Product\n");
+    let table = execute_command(&[
+        "get",
+        "api-product",
+        "--id",
+        &id,
+        "--columns",
+        "id,name",
+        "--no-headers",
+    ]);
+    assert_eq!(table, format!("{}   New API Product\n", id));
 }
 #[test]
 fn test_14_create_api_product_version_without_gatewayservice() {
@@ -315,10 +346,50 @@ fn test_21_get_api_product_version_with_gatewayservice() {
     assert_eq!(json_value["id"].as_str().unwrap(), &id);
 }
 
+#[test]
+fn test_21a_clone_api_product() {
+    let context = setup();
+    let id = context.get_id("API_PRODUCT_ID").unwrap();
+    let json_value = handle_json_response_commands(execute_command(&[
+        "clone",
+        "api-product",
+        &id,
+        "--name",
+        "Cloned API Product",
+    ]));
+    assert_missing_fields(&json_value, &["product", "versions", "specifications"]);
+    assert_eq!(json_value["product"]["source_id"].as_str().unwrap(), &id);
+    assert_eq!(json_value["versions"].as_array().unwrap().len(), 1);
+    let clone_id = json_value["product"]["clone_id"].as_str().unwrap();
+    let output = execute_command(&[
+        "delete",
+        "api-product",
+        "--id",
+        clone_id,
+        "--yes",
+        "--cascade",
+    ]);
+    assert!(output.contains(&format!("({}) deleted", clone_id)));
+}
+
+#[test]
+fn test_21b_describe_api_product() {
+    let context = setup();
+    let id = context.get_id("API_PRODUCT_ID").unwrap();
+    let version_id = context.get_id("API_PRODUCT_VERSION_ID").unwrap();
+    let output = execute_command(&["describe", "api-product", &id]);
+    assert!(output.starts_with(&format!("New API Product ({})", id)));
+    assert!(output.contains("versions (1)"));
+    assert!(output.contains(&version_id));
+    assert!(output.contains("gateway service: "));
+}
+
 #[test]
 fn test_22_delete_api_product() {
     let context = setup();
     let id = context.get_id("API_PRODUCT_ID").unwrap();
-    assert_delete_response(&execute_command(&["delete", "api-product", "--id", &id]));
+    let output = execute_command(&["delete", "api-product", "--id", &id, "--yes", "--cascade"]);
+    assert!(output.contains("1 versions, 0 specifications"));
+    assert!(output.contains(&format!("({}) deleted along with", id)));
 }
 // Delete API Product
@@ -0,0 +1,178 @@
+use assert_cmd::prelude::*;
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+const PRODUCT_ID: &str = "7f6d2c1e-1111-4222-8333-444455556666";
+const TOKEN: &str = "not-a-token";
+
+fn run_command(args: &[&str], cache_dir: Option<&PathBuf>) -> (bool, String, String) {
+    let mut cmd = Command::cargo_bin("konnectctl").unwrap();
+    if let Some(dir) = cache_dir {
+        cmd.env("KONNECTCTL_CACHE_DIR", dir)
+            .env("KONNECT_REGION", "eu")
+            .env("KONNECT_PAT", TOKEN);
+    }
+    let output = cmd.args(args).output().unwrap();
+    (
+        output.status.success(),
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+/// Directory of the cache of `token` in the eu region.
+fn account_dir(dir: &Path, token: &str) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    token.hash(&mut hasher);
+    dir.join("completion")
+        .join(format!("eu-{:016x}", hasher.finish()))
+}
+
+/// Cache holding two products and a version of the first one.
+fn cache_dir(name: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let region = account_dir(&dir, TOKEN);
+    std::fs::create_dir_all(&region).unwrap();
+    std::fs::write(
+        region.join("api-products.json"),
+        format!(
+            r#"[{{"name":"payments","id":"{}"}},{{"name":"orders","id":"0a6d2c1e-1111-4222-8333-444455556666"}}]"#,
+            PRODUCT_ID
+        ),
+    )
+    .unwrap();
+    std::fs::write(
+        region.join(format!("api-products_{}_product-versions.json", PRODUCT_ID)),
+        r#"[{"name":"v1","id":"11111111-1111-4222-8333-444455556666"}]"#,
+    )
+    .unwrap();
+    dir
+}
+
+#[test]
+fn test_completion_scripts() {
+    for (shell, dynamic) in [
+        ("bash", "complete -F _konnectctl_dynamic"),
+        ("zsh", "compdef _konnectctl_dynamic konnectctl"),
+        ("fish", "function __konnectctl_names"),
+    ] {
+        let (ok, out, _) = run_command(&["completion", shell], None);
+        assert!(ok);
+        assert!(out.contains("api-product-version-specification"));
+        assert!(out.contains(dynamic));
+    }
+}
+
+#[test]
+fn test_completion_rejects_unknown_shell() {
+    let (ok, _, err) = run_command(&["completion", "tcsh"], None);
+    assert!(!ok);
+    assert!(err.contains("possible values: bash, zsh, fish"));
+}
+
+#[test]
+fn test_complete_products_from_cache() {
+    let dir = cache_dir("complete_products");
+    let (ok, out, _) = run_command(
+        &["__complete", "--", "get", "api-product", "-i", ""],
+        Some(&dir),
+    );
+    assert!(ok);
+    assert_eq!(out, "orders\npayments\n");
+
+    let (_, out, _) = run_command(
+        &[
+            "__complete",
+            "--",
+            "delete",
+            "api-product-version",
+            "--product",
+            "pay",
+        ],
+        Some(&dir),
+    );
+    assert_eq!(out, "payments\n");
+}
+
+#[test]
+fn test_complete_versions_of_named_product() {
+    let dir = cache_dir("complete_versions");
+    let (ok, out, _) = run_command(
+        &[
+            "__complete",
+            "--",
+            "get",
+            "api-product-version",
+            "-p",
+            "payments",
+            "-i",
+            "",
+        ],
+        Some(&dir),
+    );
+    assert!(ok);
+    assert_eq!(out, "v1\n");
+}
+
+#[test]
+fn test_complete_ids_once_typed() {
+    let dir = cache_dir("complete_ids");
+    let (_, out, _) = run_command(
+        &["__complete", "--", "get", "api-product", "-i", "7f"],
+        Some(&dir),
+    );
+    assert_eq!(out, format!("{}\n", PRODUCT_ID));
+}
+
+#[test]
+fn test_complete_nothing_for_other_flags() {
+    let dir = cache_dir("complete_nothing");
+    for words in [
+        ["get", "api-product", "--name", ""],
+        ["create", "api-product", "-p", ""],
+        ["get", "api-product-version", "-i", ""],
+    ] {
+        let mut args = vec!["__complete", "--"];
+        args.extend(words);
+        let (ok, out, _) = run_command(&args, Some(&dir));
+        assert!(ok);
+        assert_eq!(out, "");
+    }
+}
+
+#[test]
+fn test_complete_nothing_without_credentials() {
+    let dir = cache_dir("complete_no_credentials");
+    let mut cmd = Command::cargo_bin("konnectctl").unwrap();
+    let output = cmd
+        .env("KONNECTCTL_CACHE_DIR", &dir)
+        .env_remove("KONNECT_PAT")
+        .env_remove("KONNECT_REGION")
+        .args(["__complete", "--", "get", "api-product-version", "-p", ""])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn test_completion_cache_is_kept_per_token() {
+    let dir = cache_dir("complete_per_token");
+    let mut cmd = Command::cargo_bin("konnectctl").unwrap();
+    let output = cmd
+        .env("KONNECTCTL_CACHE_DIR", &dir)
+        .env("KONNECT_REGION", "eu")
+        .env("KONNECT_PAT", "token-of-another-org")
+        // Nothing is listening, the other organization cannot be fetched
+        .env("HTTPS_PROXY", "http://127.0.0.1:9")
+        .args(["__complete", "--", "get", "api-product", "-i", ""])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(account_dir(&dir, TOKEN).exists());
+}
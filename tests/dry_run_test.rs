@@ -0,0 +1,298 @@
+mod mock_server;
+
+use assert_cmd::prelude::*;
+use serde_json::json;
+use std::process::Command;
+
+const PRODUCT_ID: &str = "7f6d2c1e-1111-4222-8333-444455556666";
+const VERSION_ID: &str = "11111111-1111-4222-8333-444455556666";
+
+fn run_command(args: &[&str]) -> (bool, String, String) {
+    run(Command::cargo_bin("konnectctl").unwrap().args(args))
+}
+
+/// Runs `args` with the reads sent to `url`.
+fn run_command_against(args: &[&str], url: &str) -> (bool, String, String) {
+    run(Command::cargo_bin("konnectctl")
+        .unwrap()
+        .args(args)
+        .env("KONNECT_API_URL", url))
+}
+
+fn run(cmd: &mut Command) -> (bool, String, String) {
+    let output = cmd
+        .env("KONNECT_PAT", "not-a-token")
+        .env("KONNECT_REGION", "eu")
+        .output()
+        .unwrap();
+    (
+        output.status.success(),
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn test_dry_run_prints_create_request() {
+    let (ok, out, _) = run_command(&[
+        "create",
+        "api-product",
+        "-n",
+        "payments",
+        "-d",
+        "Payments API",
+        "--dry-run",
+    ]);
+    assert!(ok);
+    assert!(out.starts_with("POST https://eu.api.konghq.com/v2/api-products\n"));
+    assert!(out.contains("authorization: Bearer <redacted>"));
+    assert!(out.contains("\"description\": \"Payments API\""));
+    assert!(!out.contains("not-a-token"));
+}
+
+#[test]
+fn test_dry_run_prints_filtered_patch_body() {
+    let (ok, out, _) = run_command(&[
+        "--dry-run",
+        "patch",
+        "api-product",
+        "--id",
+        PRODUCT_ID,
+        "-n",
+        "payments",
+    ]);
+    assert!(ok);
+    assert!(out.starts_with(&format!(
+        "PATCH https://eu.api.konghq.com/v2/api-products/{}\n",
+        PRODUCT_ID
+    )));
+    assert!(out.contains("{\n  \"name\": \"payments\"\n}"));
+}
+
+#[test]
+fn test_dry_run_curl() {
+    let (ok, out, _) = run_command(&[
+        "delete",
+        "api-product-version",
+        "-p",
+        PRODUCT_ID,
+        "--id",
+        VERSION_ID,
+        "--dry-run=curl",
+    ]);
+    assert!(ok);
+    assert!(out.starts_with(&format!(
+        "curl -X DELETE 'https://eu.api.konghq.com/v2/api-products/{}/product-versions/{}' \\\n  -H \"authorization: Bearer ${{KONNECT_PAT}}\"\n",
+        PRODUCT_ID, VERSION_ID
+    )));
+    assert!(!out.contains("not-a-token"));
+}
+
+#[test]
+fn test_dry_run_curl_quotes_body() {
+    let (ok, out, _) = run_command(&[
+        "--dry-run=curl",
+        "create",
+        "api-product",
+        "-n",
+        "Shop's API",
+        "-d",
+        "shop",
+    ]);
+    assert!(ok);
+    assert!(out.contains(r#"--data-raw '{"name":"Shop'\''s API","#));
+}
+
+#[test]
+fn test_dry_run_rejects_unknown_format() {
+    let (ok, _, err) = run_command(&["--dry-run=yaml", "get", "api-product"]);
+    assert!(!ok);
+    assert!(err.contains("possible values: http, curl"));
+}
+
+#[test]
+fn test_dry_run_create_from_body_with_flags() {
+    let (ok, out, _) = run_command(&[
+        "--dry-run",
+        "create",
+        "api-product",
+        "-f",
+        "./tests/body/api-product.yaml",
+        "-n",
+        "payments-v2",
+        "-l",
+        "env:prod",
+    ]);
+    assert!(ok);
+    let body: serde_json::Value = serde_json::from_str(out.split("\n\n").nth(1).unwrap()).unwrap();
+    assert_eq!(body["name"], "payments-v2");
+    assert_eq!(body["description"], "Payments API");
+    assert_eq!(body["labels"]["team"], "payments");
+    assert_eq!(body["labels"]["env"], "prod");
+}
+
+#[test]
+fn test_dry_run_create_version_from_json_body() {
+    let (ok, out, _) = run_command(&[
+        "--dry-run",
+        "create",
+        "api-product-version",
+        "-p",
+        PRODUCT_ID,
+        "-f",
+        "./tests/body/api-product-version.json",
+        "-s",
+        "unpublished",
+    ]);
+    assert!(ok);
+    assert!(out.contains("\"publish_status\": \"unpublished\""));
+    assert!(out.contains("\"deprecated\": true"));
+}
+
+#[test]
+fn test_dry_run_patch_from_body() {
+    let (ok, out, _) = run_command(&[
+        "--dry-run",
+        "patch",
+        "api-product",
+        "--id",
+        PRODUCT_ID,
+        "-f",
+        "./tests/body/api-product.yaml",
+        "-d",
+        "Patched",
+    ]);
+    assert!(ok);
+    assert!(out.contains("\"name\": \"payments\""));
+    assert!(out.contains("\"description\": \"Patched\""));
+}
+
+#[test]
+fn test_body_must_be_an_object() {
+    let (ok, _, err) = run_command(&["create", "api-product", "-f", "./tests/body/list.yaml"]);
+    assert!(!ok);
+    assert!(err.contains("The body in ./tests/body/list.yaml must be an object"));
+}
+
+#[test]
+fn test_dry_run_prints_the_plan_of_a_command_with_several_writes() {
+    let (ok, out, _) = run_command(&[
+        "--dry-run",
+        "generate",
+        "gateway-config",
+        "-f",
+        "./tests/spec/kong_spec.yaml",
+        "-c",
+        PRODUCT_ID,
+    ]);
+    assert!(ok);
+    assert!(out.starts_with(&format!(
+        "Gateway service orders (https://eu.orders.example.com) would be created on control plane {}\n",
+        PRODUCT_ID
+    )));
+    assert!(out.contains("  route orders_get-order GET ~/orders/(?<orderId>[^#?/]+)$\n"));
+    assert!(!out.contains("POST "));
+}
+
+#[test]
+fn test_dry_run_patch_version_from_body_drops_its_id() {
+    let (ok, out, _) = run_command(&[
+        "--dry-run",
+        "patch",
+        "api-product-version",
+        "-p",
+        PRODUCT_ID,
+        "--id",
+        VERSION_ID,
+        "-f",
+        "./tests/body/api-product-version.yaml",
+        "-s",
+        "published",
+    ]);
+    assert!(ok);
+    let (request, body) = out.split_once("\n\n").unwrap();
+    assert!(request.contains(&format!("/product-versions/{}", VERSION_ID)));
+    let body: serde_json::Value = serde_json::from_str(body.trim()).unwrap();
+    assert_eq!(
+        body,
+        serde_json::json!({ "name": "v2", "publish_status": "published", "deprecated": false })
+    );
+}
+
+fn empty_list() -> String {
+    json!({ "data": [], "meta": { "page": { "total": 0, "size": 100, "number": 1 } } }).to_string()
+}
+
+#[test]
+fn test_dry_run_apply_prints_the_plan() {
+    let url = mock_server::serve(vec![empty_list()]);
+    let (ok, out, _) = run_command_against(
+        &["apply", "-f", "./tests/manifests/payments", "--dry-run"],
+        &url,
+    );
+    assert!(ok);
+    assert!(out.starts_with("+ APIProduct payments-api (create)\n"));
+    assert!(out.contains("+ APIProductVersion payments-api/v1 (create)\n"));
+    assert!(out.ends_with("to delete, 0 unchanged\n"));
+    assert!(!out.contains("POST "));
+}
+
+#[test]
+fn test_dry_run_clone_prints_the_plan() {
+    let product = json!({
+        "id": PRODUCT_ID,
+        "name": "payments",
+        "description": "Payments API",
+        "portal_ids": [],
+        "created_at": "2024-01-01T00:00:00Z",
+        "updated_at": "2024-01-01T00:00:00Z",
+        "labels": {}
+    });
+    let versions = json!({
+        "data": [{
+            "labels": {},
+            "id": VERSION_ID,
+            "name": "v1",
+            "publish_status": "published",
+            "deprecated": false,
+            "auth_strategy_sync_errors": null,
+            "portals": [],
+            "created_at": "2024-01-01T00:00:00Z",
+            "updated_at": "2024-01-01T00:00:00Z",
+            "gateway_service": null
+        }],
+        "meta": { "page": { "total": 1, "size": 100, "number": 1 } }
+    });
+    let specs = json!({
+        "data": [{
+            "id": "22222222-1111-4222-8333-444455556666",
+            "name": "openapi.yaml",
+            "content": "b3BlbmFwaTogMy4wLjM=",
+            "created_at": "2024-01-01T00:00:00Z",
+            "updated_at": "2024-01-01T00:00:00Z"
+        }],
+        "meta": { "page": { "total": 1, "size": 100, "number": 1 } }
+    });
+    let url = mock_server::serve(vec![
+        product.to_string(),
+        versions.to_string(),
+        specs.to_string(),
+        empty_list(),
+    ]);
+    let (ok, out, _) = run_command_against(
+        &[
+            "--dry-run",
+            "clone",
+            "api-product",
+            PRODUCT_ID,
+            "-n",
+            "payments-copy",
+        ],
+        &url,
+    );
+    assert!(ok);
+    assert_eq!(
+        out,
+        "API product payments would be cloned as payments-copy\n  version v1\n  specification v1/openapi.yaml\n"
+    );
+}
@@ -0,0 +1,300 @@
+use assert_cmd::prelude::*;
+use std::process::Command;
+
+fn run_command(args: &[&str]) -> (bool, String, String) {
+    let mut cmd = Command::cargo_bin("konnectctl").unwrap();
+    let output = cmd.args(args).output().unwrap();
+    (
+        output.status.success(),
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn test_apply_rejects_version_without_product() {
+    let (ok, _, err) = run_command(&[
+        "apply",
+        "-f",
+        "./tests/manifests/invalid/missing_product.yaml",
+    ]);
+    assert!(!ok);
+    assert!(err.contains("APIProductVersion v1 requires metadata.product"));
+}
+
+#[test]
+fn test_apply_rejects_unknown_kind() {
+    let (ok, _, err) = run_command(&["apply", "-f", "./tests/manifests/invalid/unknown_kind.yaml"]);
+    assert!(!ok);
+    assert!(err.contains("unknown_kind.yaml (document 1) is not a valid manifest"));
+}
+
+#[test]
+fn test_apply_rejects_duplicate_declarations() {
+    let (ok, _, err) = run_command(&[
+        "apply",
+        "-f",
+        "./tests/manifests/payments",
+        "-f",
+        "./tests/manifests/payments/product.yaml",
+    ]);
+    assert!(!ok);
+    assert!(err.contains("APIProduct payments-api is declared more than once"));
+}
+
+#[test]
+fn test_diff_manifests_rejects_invalid_manifest() {
+    let (ok, _, err) = run_command(&[
+        "diff",
+        "-f",
+        "./tests/manifests/invalid/missing_product.yaml",
+    ]);
+    assert!(!ok);
+    assert!(err.contains("requires metadata.product"));
+}
+
+#[test]
+fn test_export_refuses_non_empty_directory() {
+    let (ok, _, err) = run_command(&["export", "--dir", "./tests/manifests"]);
+    assert!(!ok);
+    assert!(err.contains("./tests/manifests is not empty"));
+}
+
+#[test]
+fn test_apply_prune_requires_selector() {
+    let (ok, _, err) = run_command(&["apply", "-f", "./tests/manifests/payments", "--prune"]);
+    assert!(!ok);
+    assert!(err.contains("--selector <SELECTOR>"));
+}
+
+#[test]
+fn test_apply_prune_rejects_invalid_selector() {
+    let (ok, _, err) = run_command(&[
+        "apply",
+        "-f",
+        "./tests/manifests/payments",
+        "--prune",
+        "-l",
+        "=platform-repo",
+    ]);
+    assert!(!ok);
+    assert!(err.contains("the label name is empty"));
+}
+
+#[test]
+fn test_prune_rejects_selector_without_positive_clause() {
+    for (command, selector) in [("apply", "env!=prod"), ("diff", "!pinned,env!=prod")] {
+        let (ok, _, err) = run_command(&[
+            command,
+            "-f",
+            "./tests/manifests/payments",
+            "--prune",
+            "-l",
+            selector,
+        ]);
+        assert!(!ok);
+        assert!(err.contains("also matches products without labels"));
+    }
+}
+
+#[test]
+fn test_render_overlay_with_values() {
+    let (ok, out, err) = run_command(&[
+        "render",
+        "-f",
+        "./tests/manifests/overlays/prod",
+        "--values",
+        "./tests/manifests/overlays/values-prod.yaml",
+    ]);
+    assert!(ok, "{}", err);
+    assert!(out.contains("env: prod"));
+    assert!(out.contains("team: payments"));
+    assert!(out.contains("- 5f8c7c1e-2a3b-4c5d-8e9f-0a1b2c3d4e5f"));
+    assert!(out.contains("deprecated: false"));
+    assert!(out.contains("tests/spec/spec-file.yaml"));
+}
+
+#[test]
+fn test_render_reads_variables_from_environment() {
+    let mut cmd = Command::cargo_bin("konnectctl").unwrap();
+    let output = cmd
+        .args(["render", "-f", "./tests/manifests/overlays/prod"])
+        .env("ENVIRONMENT", "dev")
+        .env("PORTAL_ID", "dev-portal")
+        .env("DEPRECATED", "true")
+        .output()
+        .unwrap();
+    let out = String::from_utf8(output.stdout).unwrap();
+    assert!(output.status.success());
+    assert!(out.contains("env: dev"));
+    assert!(out.contains("deprecated: true"));
+}
+
+#[test]
+fn test_render_leaves_spec_files_of_a_directory_alone() {
+    let mut cmd = Command::cargo_bin("konnectctl").unwrap();
+    let output = cmd
+        .args(["render", "-f", "./tests/manifests/templated"])
+        .env("ENVIRONMENT", "dev")
+        .output()
+        .unwrap();
+    let out = String::from_utf8(output.stdout).unwrap();
+    let err = String::from_utf8(output.stderr).unwrap();
+    assert!(output.status.success(), "{}", err);
+    assert!(out.contains("env: dev"));
+    assert!(out.contains("tests/manifests/templated/orders.yaml"));
+}
+
+#[test]
+fn test_render_rejects_undefined_variables() {
+    let mut cmd = Command::cargo_bin("konnectctl").unwrap();
+    let output = cmd
+        .args(["render", "-f", "./tests/manifests/overlays/prod"])
+        .env_remove("ENVIRONMENT")
+        .env_remove("PORTAL_ID")
+        .output()
+        .unwrap();
+    let err = String::from_utf8(output.stderr).unwrap();
+    assert!(!output.status.success());
+    assert!(err.contains("uses undefined variables: ENVIRONMENT, PORTAL_ID"));
+}
+
+#[test]
+fn test_render_rejects_patch_of_undeclared_object() {
+    let (ok, _, err) = run_command(&["render", "-f", "./tests/manifests/overlays/unknown-target"]);
+    assert!(!ok);
+    assert!(err.contains("patches APIProductVersion v2 which is not declared"));
+}
+
+#[test]
+fn test_drift_rejects_invalid_manifest() {
+    let (ok, _, err) = run_command(&[
+        "drift",
+        "-f",
+        "./tests/manifests/invalid/missing_product.yaml",
+        "--format",
+        "junit",
+        "-l",
+        "managed-by=platform-repo",
+    ]);
+    assert!(!ok);
+    assert!(err.contains("APIProductVersion v1 requires metadata.product"));
+}
+
+#[test]
+fn test_drift_rejects_unknown_format() {
+    let (ok, _, err) = run_command(&[
+        "drift",
+        "-f",
+        "./tests/manifests/payments",
+        "--format",
+        "xml",
+        "-l",
+        "managed-by=platform-repo",
+    ]);
+    assert!(!ok);
+    assert!(err.contains("invalid value 'xml'"));
+}
+
+#[test]
+fn test_drift_requires_ownership_selector() {
+    let (ok, _, err) = run_command(&["drift", "-f", "./tests/manifests/payments"]);
+    assert!(!ok);
+    assert!(err.contains("--selector <SELECTOR>"));
+
+    let (ok, _, err) = run_command(&[
+        "drift",
+        "-f",
+        "./tests/manifests/payments",
+        "-l",
+        "team!=payments",
+    ]);
+    assert!(!ok);
+    assert!(err.contains("also matches products without labels"));
+}
+
+/// Copy of the state fixture that a test can modify.
+fn state_copy(name: &str) -> String {
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("state.json");
+    std::fs::copy("./tests/state/state.json", &path).unwrap();
+    std::fs::remove_file(dir.join("state.json.lock")).ok();
+    path.display().to_string()
+}
+
+#[test]
+fn test_state_list() {
+    let (ok, out, err) = run_command(&["state", "list", "--state", "./tests/state/state.json"]);
+    assert!(ok, "{}", err);
+    assert!(
+        out.contains("APIProductVersion\tpayments-api/v1\t0a6b0c52-8e0b-4a3a-9d0e-4d0a1c1f0002")
+    );
+}
+
+#[test]
+fn test_state_mv_renames_children() {
+    let state = state_copy("state_mv");
+    let (ok, out, err) = run_command(&[
+        "state",
+        "mv",
+        "APIProduct",
+        "payments-api",
+        "payments",
+        "--state",
+        &state,
+    ]);
+    assert!(ok, "{}", err);
+    assert!(out.contains("3 entries updated"));
+
+    let (_, out, _) = run_command(&["state", "list", "--state", &state]);
+    assert!(out.contains("APIProduct\tpayments\t"));
+    assert!(out.contains("APIProductVersionSpecification\tpayments/v1/spec-file.yaml\t"));
+    assert!(!out.contains("payments-api"));
+}
+
+#[test]
+fn test_state_mv_rejects_new_parent() {
+    let state = state_copy("state_mv_parent");
+    let (ok, _, err) = run_command(&[
+        "state",
+        "mv",
+        "APIProductVersion",
+        "payments-api/v1",
+        "orders-api/v1",
+        "--state",
+        &state,
+    ]);
+    assert!(!ok);
+    assert!(err.contains("have different parents"));
+}
+
+#[test]
+fn test_state_is_locked_during_a_run() {
+    let state = state_copy("state_locked");
+    std::fs::write(format!("{}.lock", state), "4242").unwrap();
+    let (ok, _, err) = run_command(&[
+        "state",
+        "mv",
+        "APIProduct",
+        "payments-api",
+        "payments",
+        "--state",
+        &state,
+    ]);
+    assert!(!ok);
+    assert!(err.contains("is locked by another run (process 4242)"));
+}
+
+#[test]
+fn test_apply_rejects_zero_concurrency() {
+    let (ok, _, err) = run_command(&[
+        "apply",
+        "-f",
+        "./tests/manifests/payments",
+        "--concurrency",
+        "0",
+    ]);
+    assert!(!ok);
+    assert!(err.contains("invalid value '0' for '--concurrency <CONCURRENCY>'"));
+}
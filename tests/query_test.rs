@@ -0,0 +1,109 @@
+mod mock_server;
+
+use assert_cmd::prelude::*;
+use serde_json::{json, Value};
+use std::process::Command;
+
+fn run_command(args: &[&str]) -> (bool, String, String) {
+    let mut cmd = Command::cargo_bin("konnectctl").unwrap();
+    let output = cmd.args(args).output().unwrap();
+    (
+        output.status.success(),
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+/// Stdout of `args` run against a server answering `body`.
+fn run_against(body: Value, args: &[&str]) -> String {
+    let url = mock_server::serve(vec![body.to_string()]);
+    let output = Command::cargo_bin("konnectctl")
+        .unwrap()
+        .args(args)
+        .env("KONNECT_PAT", "kpat_test")
+        .env("KONNECT_REGION", "eu")
+        .env("KONNECT_API_URL", url)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_query_prints_raw_strings() {
+    let (ok, out, _) = run_command(&[
+        "diff",
+        "api-product-version-specification",
+        "--base-file",
+        "./tests/spec/another_spec.yaml",
+        "-f",
+        "./tests/spec/spec-file.yaml",
+        "-o",
+        "json",
+        "--query",
+        ".changes[] | select(.severity == \"breaking\") | .location",
+    ]);
+    assert!(!ok);
+    assert_eq!(out.lines().count(), 3);
+    assert!(out.lines().all(|line| !line.starts_with('"')));
+    assert!(out.contains("DELETE /pets/{petId}"));
+}
+
+#[test]
+fn test_query_length_and_index() {
+    let (_, out, _) = run_command(&[
+        "--query",
+        ".changes | length",
+        "diff",
+        "api-product-version-specification",
+        "--base-file",
+        "./tests/spec/another_spec.yaml",
+        "-f",
+        "./tests/spec/spec-file.yaml",
+        "-o",
+        "json",
+    ]);
+    assert_eq!(out, "4\n");
+}
+
+#[test]
+fn test_query_rejects_unsupported_expression() {
+    let (ok, _, err) = run_command(&["get", "api-product", "--query", "map(.name)"]);
+    assert!(!ok);
+    assert!(err.contains("map is not supported"));
+}
+
+#[test]
+fn test_query_keeps_the_field_order_of_the_response() {
+    let product = json!({
+        "id": "11111111-1111-1111-1111-111111111111",
+        "name": "orders",
+        "description": null,
+        "portal_ids": [],
+        "created_at": "2024-01-01T00:00:00Z",
+        "updated_at": "2024-01-01T00:00:00Z",
+        "labels": {}
+    });
+    let body = json!({
+        "data": [product],
+        "meta": { "page": { "total": 1, "size": 100, "number": 1 } }
+    });
+    let out = run_against(body, &["get", "api-product", "--query", ".data[0]"]);
+    let keys: Vec<&str> = out
+        .lines()
+        .filter(|l| l.starts_with("  \""))
+        .map(|l| l.trim().split('"').nth(1).unwrap())
+        .collect();
+    assert_eq!(
+        keys,
+        [
+            "id",
+            "name",
+            "description",
+            "portal_ids",
+            "created_at",
+            "updated_at",
+            "labels"
+        ]
+    );
+}
@@ -0,0 +1,110 @@
+use assert_cmd::prelude::*;
+use std::process::Command;
+
+fn run_command(args: &[&str]) -> (bool, String, String) {
+    let mut cmd = Command::cargo_bin("konnectctl").unwrap();
+    let output = cmd.args(args).output().unwrap();
+    (
+        output.status.success(),
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn test_get_api_product_rejects_invalid_selector() {
+    let (ok, _, err) = run_command(&["get", "api-product", "-l", "team=payments,"]);
+    assert!(!ok);
+    assert!(err.contains("it has an empty clause"));
+}
+
+#[test]
+fn test_get_api_product_selector_conflicts_with_name() {
+    let (ok, _, err) = run_command(&["get", "api-product", "-n", "payments", "-l", "team"]);
+    assert!(!ok);
+    assert!(err.contains("cannot be used with"));
+}
+
+#[test]
+fn test_delete_api_product_requires_a_target() {
+    let (ok, _, err) = run_command(&["delete", "api-product"]);
+    assert!(!ok);
+    assert!(err.contains(
+        "<--id <ID>|--selector <SELECTOR>|--name-prefix <PREFIX>|--older-than <AGE>|--filename <FILENAME>>"
+    ));
+}
+
+#[test]
+fn test_delete_api_product_id_conflicts_with_filters() {
+    let (ok, _, err) = run_command(&[
+        "delete",
+        "api-product",
+        "-i",
+        "payments",
+        "--name-prefix",
+        "test-",
+    ]);
+    assert!(!ok);
+    assert!(err.contains("cannot be used with"));
+}
+
+#[test]
+fn test_delete_api_product_rejects_invalid_age() {
+    let (ok, _, err) = run_command(&["delete", "api-product", "--older-than", "a week"]);
+    assert!(!ok);
+    assert!(err.contains("invalid value 'a week' for '--older-than <AGE>'"));
+}
+
+#[test]
+fn test_delete_api_product_from_stdin_requires_yes() {
+    let (ok, _, err) = run_command(&["delete", "api-product", "-f", "-"]);
+    assert!(!ok);
+    assert!(err.contains("Pass --yes with -f -"));
+}
+
+#[test]
+fn test_delete_api_product_from_file() {
+    let (ok, _, err) = run_command(&[
+        "delete",
+        "api-product",
+        "-f",
+        "./tests/missing.txt",
+        "--yes",
+    ]);
+    assert!(!ok);
+    assert!(err.contains("Unable to read ./tests/missing.txt"));
+
+    let (ok, out, _) = run_command(&[
+        "delete",
+        "api-product",
+        "-f",
+        "./tests/selector/no-products.txt",
+        "--yes",
+    ]);
+    assert!(ok);
+    assert_eq!(out, "No API products to delete\n");
+}
+
+#[test]
+fn test_delete_api_product_version_rejects_id_and_selector() {
+    let (ok, _, err) = run_command(&[
+        "delete",
+        "api-product-version",
+        "-p",
+        "product",
+        "-i",
+        "version",
+        "-l",
+        "env=dev",
+    ]);
+    assert!(!ok);
+    assert!(err.contains("cannot be used with"));
+}
+
+#[test]
+fn test_product_flag_accepts_alias() {
+    let (ok, _, err) = run_command(&["delete", "api-product-version", "--product", "payments-api"]);
+    assert!(!ok);
+    assert!(!err.contains("unexpected argument"));
+    assert!(err.contains("<--id <ID>|--selector <SELECTOR>>"));
+}
@@ -0,0 +1,176 @@
+use assert_cmd::prelude::*;
+use std::process::Command;
+
+fn run_command(args: &[&str]) -> (bool, String, String) {
+    let mut cmd = Command::cargo_bin("konnectctl").unwrap();
+    let output = cmd.args(args).output().unwrap();
+    (
+        output.status.success(),
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn test_validate_valid_openapi_spec() {
+    let (ok, out, _) = run_command(&["validate", "spec", "-f", "./tests/spec/spec-file.yaml"]);
+    assert!(ok);
+    assert!(out.contains("valid OpenAPI 3.0 document"));
+}
+
+#[test]
+fn test_validate_valid_asyncapi_spec() {
+    let (ok, out, _) = run_command(&["validate", "spec", "-f", "./tests/spec/asyncapi.yaml"]);
+    assert!(ok);
+    assert!(out.contains("valid AsyncAPI 2.x document"));
+}
+
+#[test]
+fn test_validate_invalid_spec_reports_lines() {
+    let (ok, _, err) = run_command(&["validate", "spec", "-f", "./tests/spec/invalid_spec.yaml"]);
+    assert!(!ok);
+    assert!(err.contains(r#"line 2: /info: "version" is a required property"#));
+    assert!(
+        err.contains(r#"line 6: /paths/~1pets~1{petId}/get: "responses" is a required property"#)
+    );
+    assert!(err.contains("duplicate operationId getPet"));
+    assert!(err.contains("path parameter {petId} is not declared"));
+}
+
+#[test]
+fn test_validate_against_the_json_schema_of_the_version() {
+    let (ok, _, err) = run_command(&[
+        "validate",
+        "spec",
+        "-f",
+        "./tests/spec/parameter_without_schema.yaml",
+    ]);
+    assert!(!ok);
+    assert!(err.contains("(1 error(s))"));
+    assert!(err.contains("line 10: /paths/~1orders~1{orderId}/get/parameters/0: "));
+}
+
+#[test]
+fn test_validate_reports_unknown_line_for_missing_field() {
+    let (ok, _, err) = run_command(&["validate", "spec", "-f", "./tests/spec/missing_info.yaml"]);
+    assert!(!ok);
+    assert!(err.contains(r#"line ?: "info" is a required property"#));
+}
+
+#[test]
+fn test_create_spec_fails_fast_on_missing_file() {
+    let (ok, _, err) = run_command(&[
+        "create",
+        "api-product-version-specification",
+        "-p",
+        "product",
+        "-v",
+        "version",
+        "-f",
+        "./tests/spec/does-not-exist.yaml",
+    ]);
+    assert!(!ok);
+    assert!(err.contains("Unable to read spec file"));
+}
+
+#[test]
+fn test_diff_spec_non_breaking_changes() {
+    let (ok, out, _) = run_command(&[
+        "diff",
+        "api-product-version-specification",
+        "--base-file",
+        "./tests/spec/spec-file.yaml",
+        "-f",
+        "./tests/spec/another_spec.yaml",
+    ]);
+    assert!(ok);
+    assert!(out.contains("DELETE /pets/{petId}: operation added"));
+    assert!(out.contains("0 breaking, 4 non-breaking change(s)"));
+}
+
+#[test]
+fn test_diff_spec_breaking_changes_fail() {
+    let (ok, out, _) = run_command(&[
+        "diff",
+        "api-product-version-specification",
+        "--base-file",
+        "./tests/spec/another_spec.yaml",
+        "-f",
+        "./tests/spec/spec-file.yaml",
+        "-o",
+        "json",
+    ]);
+    assert!(!ok);
+    let report: serde_json::Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(report["breaking"], 3);
+    assert_eq!(report["changes"][0]["severity"], "breaking");
+}
+
+#[test]
+fn test_generate_gateway_config_from_spec() {
+    let (ok, out, _) = run_command(&[
+        "generate",
+        "gateway-config",
+        "-f",
+        "./tests/spec/kong_spec.yaml",
+        "--with-plugins",
+    ]);
+    assert!(ok);
+    let config: serde_json::Value = serde_yaml::from_str(&out).unwrap();
+    let service = &config["services"][0];
+    assert_eq!(service["name"], "orders");
+    assert_eq!(service["url"], "https://eu.orders.example.com");
+    assert_eq!(service["retries"], 3);
+    assert_eq!(service["plugins"][0]["name"], "rate-limiting");
+    let route = &service["routes"][0];
+    assert_eq!(route["name"], "orders_get-order");
+    assert_eq!(route["paths"][0], "~/orders/(?<orderId>[^#?/]+)$");
+    assert_eq!(route["strip_path"], true);
+    assert_eq!(route["plugins"][0]["name"], "key-auth");
+}
+
+#[test]
+fn test_bundle_spec_hoists_external_refs() {
+    let (ok, out, _) = run_command(&["bundle", "spec", "-f", "./tests/spec/multi/root.yaml"]);
+    assert!(ok);
+    let bundled: serde_json::Value = serde_yaml::from_str(&out).unwrap();
+    let schemas = &bundled["components"]["schemas"];
+    assert!(schemas.get("Error").is_some());
+    assert_eq!(
+        schemas["pet"]["properties"]["owner"]["$ref"],
+        "#/components/schemas/owner"
+    );
+    assert_eq!(
+        schemas["owner"]["properties"]["pets"]["items"]["$ref"],
+        "#/components/schemas/pet"
+    );
+    assert!(bundled["components"]["parameters"].get("OwnerId").is_some());
+    assert_eq!(bundled["paths"]["/pets"]["get"]["operationId"], "listPets");
+    assert!(!out.contains(".yaml"));
+}
+
+#[test]
+fn test_bundle_spec_keeps_the_key_order_of_the_source() {
+    let (ok, out, _) = run_command(&["bundle", "spec", "-f", "./tests/spec/multi/root.yaml"]);
+    assert!(ok);
+    let top_level: Vec<&str> = out
+        .lines()
+        .filter(|l| !l.starts_with(' '))
+        .map(|l| l.trim_end_matches(|c| c != ':'))
+        .collect();
+    assert_eq!(top_level, ["openapi:", "info:", "paths:", "components:"]);
+    let bundled: serde_json::Value = serde_yaml::from_str(&out).unwrap();
+    let pet: Vec<&String> = bundled["components"]["schemas"]["pet"]
+        .as_object()
+        .unwrap()
+        .keys()
+        .collect();
+    assert_eq!(pet, ["type", "required", "properties"]);
+}
+
+#[test]
+fn test_bundle_spec_detects_cycles() {
+    let (ok, _, err) = run_command(&["bundle", "spec", "-f", "./tests/spec/cycle/root.yaml"]);
+    assert!(!ok);
+    assert!(err.contains("circular $ref detected"));
+}
@@ -0,0 +1,193 @@
+mod mock_server;
+
+use assert_cmd::prelude::*;
+use serde_json::{json, Value};
+use std::process::Command;
+
+fn run_command(args: &[&str]) -> (bool, String) {
+    let mut cmd = Command::cargo_bin("konnectctl").unwrap();
+    let output = cmd.args(args).output().unwrap();
+    (
+        output.status.success(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn test_table_options_conflict_with_json_output() {
+    let (ok, err) = run_command(&["get", "api-product", "-o", "json", "--columns", "id,name"]);
+    assert!(!ok);
+    assert!(err.contains("--columns, --sort-by and --no-headers print a table, not -o json"));
+}
+
+#[test]
+fn test_table_cannot_be_watched() {
+    let (ok, err) = run_command(&["get", "api-product", "--sort-by", "name", "--watch"]);
+    assert!(!ok);
+    assert!(err.contains("--watch prints changes, it cannot print a table"));
+}
+
+#[test]
+fn test_output_rejects_unknown_format() {
+    let (ok, err) = run_command(&["get", "api-product-version", "-p", "x", "-o", "yaml"]);
+    assert!(!ok);
+    assert!(err.contains("possible values: json, table"));
+}
+
+/// Stdout of `args` run against a server answering `body`, with `COLUMNS` set to `columns`.
+fn render(body: Value, args: &[&str], columns: &str) -> String {
+    let url = mock_server::serve(vec![body.to_string()]);
+    let output = Command::cargo_bin("konnectctl")
+        .unwrap()
+        .args(args)
+        .env("KONNECT_PAT", "kpat_test")
+        .env("KONNECT_REGION", "eu")
+        .env("KONNECT_API_URL", url)
+        .env("COLUMNS", columns)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn products() -> Value {
+    let product = |id: &str, name: &str, description: Value, labels: Value| {
+        json!({
+            "id": id,
+            "name": name,
+            "description": description,
+            "portal_ids": [],
+            "created_at": "2024-01-01T00:00:00Z",
+            "updated_at": "2024-01-01T00:00:00Z",
+            "labels": labels
+        })
+    };
+    json!({
+        "data": [
+            product("11111111-1111-1111-1111-111111111111", "orders", Value::Null, json!({})),
+            product(
+                "22222222-2222-2222-2222-222222222222",
+                "billing",
+                json!("Invoices and the payments of every customer"),
+                json!({ "team": "finance", "env": "prod" })
+            ),
+            product(
+                "33333333-3333-3333-3333-333333333333",
+                "accounts",
+                json!("Accounts"),
+                json!({ "team": "identity" })
+            ),
+        ],
+        "meta": { "page": { "total": 3, "size": 100, "number": 1 } }
+    })
+}
+
+#[test]
+fn test_table_sorts_by_column_with_empty_values_last() {
+    let out = render(
+        products(),
+        &[
+            "get",
+            "api-product",
+            "--columns",
+            "name,description,labels",
+            "--sort-by",
+            "description",
+        ],
+        "200",
+    );
+    assert_eq!(
+        out,
+        "NAME       DESCRIPTION                                   LABELS\n\
+         accounts   Accounts                                      team=identity\n\
+         billing    Invoices and the payments of every customer   env=prod,team=finance\n\
+         orders\n"
+    );
+}
+
+#[test]
+fn test_table_without_headers() {
+    let out = render(
+        products(),
+        &[
+            "get",
+            "api-product",
+            "--columns",
+            "name,labels.team",
+            "--no-headers",
+        ],
+        "200",
+    );
+    assert_eq!(out, "orders\nbilling    finance\naccounts   identity\n");
+}
+
+#[test]
+fn test_table_truncates_the_widest_column_to_the_terminal() {
+    let out = render(
+        products(),
+        &[
+            "get",
+            "api-product",
+            "--columns",
+            "name,description",
+            "--sort-by",
+            "name",
+        ],
+        "40",
+    );
+    assert_eq!(
+        out,
+        "NAME       DESCRIPTION\n\
+         accounts   Accounts\n\
+         billing    Invoices and the payments of…\n\
+         orders\n"
+    );
+}
+
+#[test]
+fn test_table_default_columns_reach_into_lists() {
+    let portal = |name: &str| {
+        json!({
+            "portal_id": "44444444-4444-4444-4444-444444444444",
+            "portal_name": name,
+            "portal_product_version_id": "55555555-5555-5555-5555-555555555555",
+            "publish_status": "published",
+            "deprecated": false,
+            "application_registration_enabled": false,
+            "auto_approve_registration": false,
+            "auth_strategies": []
+        })
+    };
+    let versions = json!({
+        "data": [{
+            "labels": {},
+            "id": "66666666-6666-6666-6666-666666666666",
+            "name": "v1",
+            "publish_status": "published",
+            "deprecated": false,
+            "auth_strategy_sync_errors": null,
+            "portals": [portal("internal"), portal("partners")],
+            "created_at": "2024-01-01T00:00:00Z",
+            "updated_at": "2024-02-01T00:00:00Z",
+            "gateway_service": null
+        }],
+        "meta": { "page": { "total": 1, "size": 100, "number": 1 } }
+    });
+    let out = render(
+        versions,
+        &[
+            "get",
+            "api-product-version",
+            "-p",
+            "11111111-1111-1111-1111-111111111111",
+            "-o",
+            "table",
+        ],
+        "200",
+    );
+    assert_eq!(
+        out,
+        "ID                                     NAME   PUBLISH_STATUS   DEPRECATED   PORTALS.PORTAL_NAME   UPDATED_AT\n\
+         66666666-6666-6666-6666-666666666666   v1     published        false        internal,partners     2024-02-01T00:00:00Z\n"
+    );
+}
@@ -0,0 +1,112 @@
+mod mock_server;
+
+use assert_cmd::prelude::*;
+use serde_json::json;
+use std::{
+    process::{Command, Stdio},
+    thread,
+    time::Duration,
+};
+
+fn run_command(args: &[&str]) -> (bool, String) {
+    let mut cmd = Command::cargo_bin("konnectctl").unwrap();
+    let output = cmd.args(args).output().unwrap();
+    (
+        output.status.success(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn test_interval_requires_watch() {
+    let (ok, err) = run_command(&["get", "api-product", "--interval", "10s"]);
+    assert!(!ok);
+    assert!(err.contains("the following required arguments were not provided"));
+    assert!(err.contains("--watch"));
+}
+
+#[test]
+fn test_watch_rejects_invalid_interval() {
+    let (ok, err) = run_command(&["get", "api-product", "--watch", "--interval", "soon"]);
+    assert!(!ok);
+    assert!(err.contains("invalid value 'soon' for '--interval <INTERVAL>'"));
+}
+
+fn product(id: &str, name: &str, description: &str) -> serde_json::Value {
+    json!({
+        "id": id,
+        "name": name,
+        "description": description,
+        "portal_ids": [],
+        "created_at": "2024-01-01T00:00:00Z",
+        "updated_at": "2024-01-01T00:00:00Z",
+        "labels": {}
+    })
+}
+
+fn products(data: Vec<serde_json::Value>, total: usize) -> String {
+    json!({ "data": data, "meta": { "page": { "total": total, "size": 100, "number": 1 } } })
+        .to_string()
+}
+
+#[test]
+fn test_watch_prints_what_changed_between_polls() {
+    let first = products(
+        vec![
+            product("11111111-1111-1111-1111-111111111111", "orders", "Orders"),
+            product("22222222-2222-2222-2222-222222222222", "billing", "Billing"),
+        ],
+        2,
+    );
+    let second = products(
+        vec![
+            product(
+                "11111111-1111-1111-1111-111111111111",
+                "orders",
+                "All orders",
+            ),
+            product(
+                "33333333-3333-3333-3333-333333333333",
+                "payments",
+                "Payments",
+            ),
+        ],
+        3,
+    );
+    let url = mock_server::serve(vec![first, second]);
+
+    let mut child = Command::cargo_bin("konnectctl")
+        .unwrap()
+        .args(["get", "api-product", "--watch", "--interval", "300ms"])
+        .env("KONNECT_PAT", "kpat_test")
+        .env("KONNECT_REGION", "eu")
+        .env("KONNECT_API_URL", url)
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    thread::sleep(Duration::from_millis(1500));
+    child.kill().unwrap();
+    let out = String::from_utf8(child.wait_with_output().unwrap().stdout).unwrap();
+
+    let changes: Vec<&str> = out
+        .lines()
+        .filter_map(|line| line.split_once(' ').map(|(_, change)| change))
+        .filter(|change| {
+            ["added ", "removed ", "modified "]
+                .iter()
+                .any(|kind| change.starts_with(kind))
+        })
+        .collect();
+    assert_eq!(
+        changes,
+        [
+            r#"modified data[orders].description: "Orders" -> "All orders""#,
+            "removed data[billing]",
+            "added data[payments]",
+        ],
+        "{}",
+        out
+    );
+    assert!(out.contains(r#""name": "billing""#), "{}", out);
+    assert!(!out.contains("meta.page"), "{}", out);
+}